@@ -2,7 +2,11 @@
 use bevy::{asset::Asset, prelude::*};
 use bevy_seedling::sample::Sample;
 // use bevy_shuffle_bag::ShuffleBag;
-use models::pre_load::Config;
+use models::{
+    attributes::StatFormulas, damage_table::DamageTables, item::ItemDb, job::JobDb, map::MapData,
+    monster::MonsterDb, pre_load::Config, progression::LevelTable, skill::SkillDb,
+    status::StatusDb,
+};
 
 mod ron;
 mod tracking;
@@ -16,6 +20,24 @@ pub fn plugin(app: &mut App) {
     app.add_plugins(tracking::plugin)
         .add_plugins(RonAssetPlugin::<Config>::new(&["config.ron"]))
         .load_resource_from_path::<Config>("config.ron")
+        .add_plugins(RonAssetPlugin::<StatFormulas>::new(&["stats.ron"]))
+        .load_resource_from_path::<StatFormulas>("stats.ron")
+        .add_plugins(RonAssetPlugin::<JobDb>::new(&["jobs.ron"]))
+        .load_resource_from_path::<JobDb>("jobs.ron")
+        .add_plugins(RonAssetPlugin::<ItemDb>::new(&["items.ron"]))
+        .load_resource_from_path::<ItemDb>("items.ron")
+        .add_plugins(RonAssetPlugin::<LevelTable>::new(&["levels.ron"]))
+        .load_resource_from_path::<LevelTable>("levels.ron")
+        .add_plugins(RonAssetPlugin::<MonsterDb>::new(&["monsters.ron"]))
+        .load_resource_from_path::<MonsterDb>("monsters.ron")
+        .add_plugins(RonAssetPlugin::<SkillDb>::new(&["skills.ron"]))
+        .load_resource_from_path::<SkillDb>("skills.ron")
+        .add_plugins(RonAssetPlugin::<StatusDb>::new(&["statuses.ron"]))
+        .load_resource_from_path::<StatusDb>("statuses.ron")
+        .add_plugins(RonAssetPlugin::<DamageTables>::new(&["damage.ron"]))
+        .load_resource_from_path::<DamageTables>("damage.ron")
+        .add_plugins(RonAssetPlugin::<MapData>::new(&["map.ron"]))
+        .load_resource_from_path::<MapData>("maps/field.map.ron")
         .load_resource::<AudioSources>()
         .load_resource::<Textures>()
         .load_resource::<Models>();
@@ -82,6 +104,8 @@ pub struct AudioSources {
     pub btn_press: Handle<Sample>,
     #[dependency]
     pub steps: Vec<Handle<Sample>>,
+    #[dependency]
+    pub level_up: Handle<Sample>,
 
     // music
     #[dependency]
@@ -98,6 +122,7 @@ impl AudioSources {
     ];
     pub const BTN_HOVER: &'static str = "audio/sfx/btn-hover.ogg";
     pub const BTN_PRESS: &'static str = "audio/sfx/btn-press.ogg";
+    pub const LEVEL_UP: &'static str = "audio/sfx/level-up.wav";
 
     pub const BG_MUSIC: &'static str = "audio/music/smnbl-time-for-fun.ogg";
 }
@@ -110,6 +135,7 @@ impl FromWorld for AudioSources {
             steps,
             btn_hover: assets.load(Self::BTN_HOVER),
             btn_press: assets.load(Self::BTN_PRESS),
+            level_up: assets.load(Self::LEVEL_UP),
             bg_music: assets.load(Self::BG_MUSIC),
         }
     }
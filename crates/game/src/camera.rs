@@ -1,5 +1,7 @@
 use super::*;
 use bevy_third_person_camera::*;
+use event::types::DamageResult;
+use rand::Rng;
 
 pub fn plugin(app: &mut App) {
     app.add_systems(Startup, spawn_camera)
@@ -13,6 +15,15 @@ pub fn plugin(app: &mut App) {
             Update,
             (tick_splash_timer.in_set(Set::TickTimers)).run_if(in_state(Screen::Gameplay)),
         )
+        .init_resource::<CameraShake>()
+        .add_systems(Update, shake_on_hit.run_if(in_state(Screen::Gameplay)))
+        .add_systems(
+            PostUpdate,
+            (
+                settle_camera.before(CameraSyncSet),
+                shake_camera.after(CameraSyncSet),
+            ),
+        )
         .add_observer(toggle_cam_cursor);
 }
 
@@ -126,6 +137,77 @@ fn toggle_cam_cursor(_: Trigger<OnCamCursorToggle>, mut cam: Query<&mut ThirdPer
     cam.cursor_lock_active = !cam.cursor_lock_active;
 }
 
+/// Shake of the camera after the local player got hit. The offset is taken off again before the
+/// third person camera follows the player, so it never drifts.
+#[derive(Resource, Debug, Default)]
+struct CameraShake {
+    timer: Timer,
+    strength: f32,
+    offset: Vec3,
+}
+
+fn shake_on_hit(
+    cfg: Res<Config>,
+    mut damage_results: EventReader<DamageResult>,
+    local: Query<&ComputedAttributes, With<LocalPlayer>>,
+    mut shake: ResMut<CameraShake>,
+) {
+    for hit in damage_results.read() {
+        let Ok(attribs) = local.get(hit.target) else {
+            continue;
+        };
+        if hit.amount <= 0.0 {
+            continue;
+        }
+
+        let heavy = hit.amount >= cfg.combat.reactions.heavy_hit * attribs.health.max_hp;
+        let shake_cfg = &cfg.player.camera_shake;
+        let strength = shake_cfg.strength * if heavy { 2.0 } else { 1.0 };
+        // a light hit doesn't cut a heavy shake short
+        if shake.timer.finished() || strength >= shake.strength {
+            shake.strength = strength;
+        }
+        shake.timer = Timer::from_seconds(shake_cfg.duration, TimerMode::Once);
+    }
+}
+
+fn settle_camera(
+    mut shake: ResMut<CameraShake>,
+    mut camera: Query<&mut Transform, With<SceneCamera>>,
+) {
+    if shake.offset == Vec3::ZERO {
+        return;
+    }
+    if let Ok(mut transform) = camera.single_mut() {
+        transform.translation -= shake.offset;
+    }
+    shake.offset = Vec3::ZERO;
+}
+
+fn shake_camera(
+    time: Res<Time>,
+    mut shake: ResMut<CameraShake>,
+    mut camera: Query<&mut Transform, With<SceneCamera>>,
+) {
+    if shake.timer.tick(time.delta()).finished() {
+        return;
+    }
+    let Ok(mut transform) = camera.single_mut() else {
+        return;
+    };
+
+    // eases out as the timer runs
+    let fade = (1.0 - shake.timer.fraction()).powi(2);
+    let mut rng = rand::thread_rng();
+    let jitter = Vec3::new(
+        rng.gen_range(-1.0..=1.0),
+        rng.gen_range(-1.0..=1.0),
+        rng.gen_range(-1.0..=1.0),
+    );
+    shake.offset = jitter * shake.strength * fade;
+    transform.translation += shake.offset;
+}
+
 /// Helper trait to get direction of movement based on camera transform
 pub trait MovementDirection {
     fn movement_direction(&self, input: Vec2) -> Vec3;
@@ -0,0 +1,205 @@
+//! The simulated part of a player character, the same on the client and on the dedicated server.
+//! The client dresses its own character up with the model, input and camera, see `player`. The
+//! dedicated server spawns a character for every player that entered the world of its
+//! [`LocalServer`] and keeps it where the server moved it, so enemies and combat see it. The
+//! attacks its client asks for go through the same [`AttackEvent`]s as everyone else's, and
+//! whatever combat did to players and monsters goes back to the clients through the server.
+
+use super::*;
+use bevy_tnua_avian3d::TnuaAvian3dSensorShape;
+use combat::AttackRateTimer;
+use event::types::{AttackEvent, RespawnRequest};
+use std::collections::{HashMap, HashSet};
+
+pub fn plugin(app: &mut App) {
+    app.add_systems(
+        Update,
+        (
+            spawn_hosted_players,
+            follow_hosted_players,
+            hosted_attacks,
+            hosted_respawns,
+            publish_attributes,
+        )
+            .chain()
+            .run_if(hosting)
+            .run_if(in_state(Screen::Gameplay)),
+    );
+}
+
+/// A dedicated server: clients connect to its [`LocalServer`] and it has no client of its own.
+/// A client hosting its own server spawns its player with the model instead.
+pub fn hosting(server: Option<Res<LocalServer>>, client: Option<Res<NetClient>>) -> bool {
+    server.is_some() && client.is_none()
+}
+
+/// Everything a player character needs to be simulated
+pub fn player_bundle(cfg: &Config, levels: &LevelTable, pos: Transform) -> impl Bundle {
+    let collider = Collider::capsule(cfg.player.hitbox.radius, cfg.player.hitbox.height);
+
+    let attribs = Attributes::default();
+    // the rest is derived from the attributes, see `stats`
+    let comp_attribs = ComputedAttributes {
+        attack_range: 2.0,
+        ..default()
+    };
+    // follows the derived move speed from then on
+    let player = Player {
+        id: Entity::PLACEHOLDER,
+        speed: comp_attribs.move_speed,
+        animation_state: AnimationState::StandIdle,
+        ..default()
+    };
+
+    let attack_timer = AttackRateTimer::new(comp_attribs.attack_rate);
+
+    (
+        StateScoped(Screen::Gameplay),
+        pos,
+        SavePoint(cfg.respawn.save_point),
+        player,
+        // stats
+        (
+            attribs,
+            (
+                Job::Novice,
+                Level::default(),
+                JobLevel::default(),
+                Experience::default(),
+            ),
+            (
+                StatPoints(levels.base.start_points),
+                SkillPoints(levels.job.start_points),
+            ),
+            StatModifiers::default(),
+            StatusEffects::default(),
+            comp_attribs,
+            // damage modifiers, bare handed until equipment sets them
+            (Element::Neutral, ElementLevel::default()),
+            (BodySize::Medium, Race::DemiHuman),
+            (WeaponType::Fist, AttackElement::default()),
+            RaceBonuses::default(),
+            // its weight slows the player down, see `inventory`
+            Inventory::default(),
+        ),
+        // skills
+        (
+            Hotbar::new(cfg.player.hotbar.iter().copied()),
+            SkillCooldowns::default(),
+            LearnedSkills::default(),
+        ),
+        (
+            TnuaController::default(),
+            NavPath::default(),
+            // Tnua can fix the rotation, but the character will still get rotated before it can do so.
+            // By locking the rotation we can prevent this.
+            LockedAxes::ROTATION_LOCKED.unlock_rotation_y(),
+            // A sensor shape is not strictly necessary, but without it we'll get weird results.
+            TnuaAvian3dSensorShape(collider.clone()),
+        ),
+        // physics
+        (
+            collider,
+            RigidBody::Dynamic,
+            // Friction::ZERO.with_combine_rule(CoefficientCombine::Multiply),
+        ),
+        // timers
+        StepTimer(Timer::from_seconds(cfg.timers.step, TimerMode::Repeating)),
+        attack_timer,
+        InheritedVisibility::default(), // silence the warning because of adding SceneRoot as a child
+    )
+}
+
+/// Characters come and go with the players of the server
+fn spawn_hosted_players(
+    cfg: Res<Config>,
+    levels: Res<LevelTable>,
+    server: Res<LocalServer>,
+    players: Query<(Entity, &NetId), With<Player>>,
+    mut cmds: Commands,
+) {
+    let hosted: HashSet<NetId> = server
+        .entities()
+        .filter(|(_, entity)| entity.kind == EntityKind::Player)
+        .map(|(id, _)| id)
+        .collect();
+
+    let mut spawned = HashSet::new();
+    for (entity, id) in &players {
+        if hosted.contains(id) {
+            spawned.insert(*id);
+        } else {
+            cmds.entity(entity).despawn();
+        }
+    }
+
+    for id in hosted.difference(&spawned) {
+        let Some(entity) = server.entity(*id) else {
+            continue;
+        };
+        let pos = Transform::from_translation(entity.position).with_rotation(entity.rotation);
+        cmds.spawn((
+            Name::new(format!("Player {}", id.0)),
+            *id,
+            player_bundle(&cfg, &levels, pos),
+        ));
+        info!("Player {} entered the world at {}", id.0, entity.position);
+    }
+}
+
+/// The server moves the players from their inputs, the character only follows
+fn follow_hosted_players(
+    server: Res<LocalServer>,
+    mut players: Query<(&NetId, &mut Transform), (With<Player>, Without<Dead>)>,
+) {
+    for (id, mut transform) in &mut players {
+        if let Some(entity) = server.entity(*id) {
+            transform.translation = entity.position;
+            transform.rotation = entity.rotation;
+        }
+    }
+}
+
+/// The server only checked the attacks, the hits are rolled by combat
+fn hosted_attacks(
+    mut server: ResMut<LocalServer>,
+    hosted: Query<(Entity, &NetId)>,
+    mut attack_events: EventWriter<AttackEvent>,
+) {
+    let attacks = server.take_attacks();
+    if attacks.is_empty() {
+        return;
+    }
+
+    let entities: HashMap<NetId, Entity> = hosted.iter().map(|(e, id)| (*id, e)).collect();
+    for AcceptedAttack { attacker, target } in attacks {
+        if let (Some(&attacker), Some(&target)) = (entities.get(&attacker), entities.get(&target)) {
+            attack_events.write(AttackEvent { attacker, target });
+        }
+    }
+}
+
+/// The server put the player back at the save point already, the character follows
+fn hosted_respawns(
+    mut server: ResMut<LocalServer>,
+    players: Query<(Entity, &NetId), With<Player>>,
+    mut requests: EventWriter<RespawnRequest>,
+) {
+    for id in server.take_respawns() {
+        if let Some((entity, _)) = players.iter().find(|(_, player)| **player == id) {
+            requests.write(RespawnRequest { entity });
+        }
+    }
+}
+
+/// Hits, heals and level ups of every hosted player and monster, for the clients to show
+fn publish_attributes(
+    mut server: ResMut<LocalServer>,
+    hosted: Query<(&NetId, Ref<ComputedAttributes>)>,
+) {
+    for (id, attribs) in &hosted {
+        if attribs.is_changed() {
+            server.set_attributes(*id, *attribs);
+        }
+    }
+}
@@ -0,0 +1,269 @@
+//! Damage resolution: crit roll, hit-vs-dodge roll, variance and defence mitigation.
+//! Every roll goes through [`CombatRng`] so a fixed seed replays the same fight.
+
+use super::*;
+use rand::{Rng, SeedableRng, rngs::StdRng};
+
+pub fn plugin(app: &mut App) {
+    // the config is an asset on the client, seed as soon as it is around
+    app.add_systems(
+        Update,
+        init_rng.run_if(resource_exists::<Config>.and(not(resource_exists::<CombatRng>))),
+    );
+}
+
+/// The random source of every combat roll
+#[derive(Resource)]
+pub struct CombatRng(pub StdRng);
+
+impl CombatRng {
+    pub fn seeded(seed: u64) -> Self {
+        Self(StdRng::seed_from_u64(seed))
+    }
+}
+
+impl Default for CombatRng {
+    fn default() -> Self {
+        Self(StdRng::from_entropy())
+    }
+}
+
+#[derive(Reflect, Debug, Copy, Clone, PartialEq, Eq)]
+pub enum HitOutcome {
+    Miss,
+    Normal,
+    Crit,
+}
+
+/// Rolls one attack of `attacker` on `target`, returns the outcome and the damage to apply
+pub fn resolve(
+    attacker: &ComputedAttributes,
+    target: &ComputedAttributes,
+    kind: DamageKind,
+    rules: &CombatConfig,
+    rng: &mut impl Rng,
+) -> (HitOutcome, f32) {
+    let (low, high) = rules.variance;
+    let variance = if high > low {
+        rng.gen_range(low..=high)
+    } else {
+        low
+    };
+
+    match kind {
+        DamageKind::Physical => {
+            // crits go through dodge and defence alike
+            if rng.r#gen::<f32>() < attacker.crit_rate / 100.0 {
+                let amount = attacker.attack * rules.crit_multiplier * variance;
+                return (HitOutcome::Crit, amount.max(1.0));
+            }
+
+            let hit_chance = (rules.base_hit_chance
+                + (attacker.hit_rate - target.dodge_rate) / 100.0)
+                .clamp(rules.min_hit_chance, rules.max_hit_chance);
+            if rng.r#gen::<f32>() >= hit_chance {
+                return (HitOutcome::Miss, 0.0);
+            }
+
+            let amount = mitigate(attacker.attack * variance, target.defence, rules);
+            (HitOutcome::Normal, amount)
+        }
+        DamageKind::Magical => {
+            let amount = mitigate(
+                attacker.magic_attack * variance,
+                target.magic_defence,
+                rules,
+            );
+            (HitOutcome::Normal, amount)
+        }
+    }
+}
+
+/// Every landed hit deals at least 1
+fn mitigate(damage: f32, defence: f32, rules: &CombatConfig) -> f32 {
+    let scale = rules.defence_scale / (rules.defence_scale + defence.max(0.0));
+    (damage * scale).max(1.0)
+}
+
+fn init_rng(cfg: Res<Config>, mut commands: Commands) {
+    let rng = match cfg.combat.seed {
+        Some(seed) => CombatRng::seeded(seed),
+        None => CombatRng::default(),
+    };
+    commands.insert_resource(rng);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ROLLS: usize = 10_000;
+
+    fn rules() -> CombatConfig {
+        CombatConfig {
+            base_hit_chance: 0.8,
+            min_hit_chance: 0.05,
+            max_hit_chance: 0.95,
+            crit_multiplier: 1.4,
+            variance: (0.9, 1.1),
+            defence_scale: 100.0,
+            seed: None,
+            reactions: HitReactionConfig {
+                hit_stun: 0.15,
+                heavy_hit: 0.15,
+                heavy_hit_stun: 0.5,
+                heavy_knockback: 8.0,
+            },
+        }
+    }
+
+    fn attacker() -> ComputedAttributes {
+        ComputedAttributes {
+            attack: 100.0,
+            magic_attack: 100.0,
+            hit_rate: 0.0,
+            crit_rate: 0.0,
+            ..default()
+        }
+    }
+
+    /// Outcomes and damage of many physical rolls from a fixed seed
+    fn roll(
+        attacker: &ComputedAttributes,
+        target: &ComputedAttributes,
+        kind: DamageKind,
+    ) -> Vec<(HitOutcome, f32)> {
+        let rules = rules();
+        let mut rng = CombatRng::seeded(7);
+        (0..ROLLS)
+            .map(|_| resolve(attacker, target, kind, &rules, &mut rng.0))
+            .collect()
+    }
+
+    fn share(rolls: &[(HitOutcome, f32)], outcome: HitOutcome) -> f32 {
+        rolls.iter().filter(|(o, _)| *o == outcome).count() as f32 / rolls.len() as f32
+    }
+
+    #[test]
+    fn same_seed_same_rolls() {
+        let target = ComputedAttributes::default();
+        let a = roll(&attacker(), &target, DamageKind::Physical);
+        let b = roll(&attacker(), &target, DamageKind::Physical);
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn hit_chance_is_clamped() {
+        let target = ComputedAttributes {
+            dodge_rate: 0.0,
+            ..default()
+        };
+        let sure = ComputedAttributes {
+            hit_rate: 1000.0,
+            ..attacker()
+        };
+        let rolls = roll(&sure, &target, DamageKind::Physical);
+        let misses = share(&rolls, HitOutcome::Miss);
+        assert!((0.03..0.07).contains(&misses), "missed {misses}");
+
+        let dodger = ComputedAttributes {
+            dodge_rate: 1000.0,
+            ..default()
+        };
+        let rolls = roll(&attacker(), &dodger, DamageKind::Physical);
+        let hits = share(&rolls, HitOutcome::Normal);
+        assert!((0.03..0.07).contains(&hits), "hit {hits}");
+    }
+
+    #[test]
+    fn misses_deal_nothing() {
+        let dodger = ComputedAttributes {
+            dodge_rate: 1000.0,
+            ..default()
+        };
+        let rolls = roll(&attacker(), &dodger, DamageKind::Physical);
+        assert!(
+            rolls
+                .iter()
+                .filter(|(o, _)| *o == HitOutcome::Miss)
+                .all(|(_, dmg)| *dmg == 0.0)
+        );
+    }
+
+    #[test]
+    fn crits_ignore_dodge_and_defence() {
+        let critter = ComputedAttributes {
+            crit_rate: 100.0,
+            ..attacker()
+        };
+        let tank = ComputedAttributes {
+            dodge_rate: 1000.0,
+            defence: 1000.0,
+            ..default()
+        };
+        for (outcome, dmg) in roll(&critter, &tank, DamageKind::Physical) {
+            assert_eq!(outcome, HitOutcome::Crit);
+            assert!((125.9..=154.1).contains(&dmg), "crit of {dmg}");
+        }
+    }
+
+    #[test]
+    fn damage_stays_in_the_variance_range() {
+        let target = ComputedAttributes::default();
+        let rolls = roll(&attacker(), &target, DamageKind::Physical);
+        let hits: Vec<f32> = rolls
+            .iter()
+            .filter(|(o, _)| *o == HitOutcome::Normal)
+            .map(|(_, dmg)| *dmg)
+            .collect();
+        assert!(!hits.is_empty());
+        assert!(hits.iter().all(|dmg| (89.9..=110.1).contains(dmg)));
+        // the whole range is used, not a single value
+        let low = hits.iter().copied().fold(f32::MAX, f32::min);
+        let high = hits.iter().copied().fold(f32::MIN, f32::max);
+        assert!(low < 92.0 && high > 108.0, "rolled {low} to {high}");
+    }
+
+    #[test]
+    fn defence_mitigates_down_to_1() {
+        let sure = ComputedAttributes {
+            hit_rate: 1000.0,
+            ..attacker()
+        };
+        let armored = ComputedAttributes {
+            defence: 100.0,
+            ..default()
+        };
+        for (outcome, dmg) in roll(&sure, &armored, DamageKind::Physical) {
+            if outcome == HitOutcome::Normal {
+                assert!((44.9..=55.1).contains(&dmg), "hit of {dmg}");
+            }
+        }
+
+        let weak = ComputedAttributes {
+            attack: 0.1,
+            ..sure
+        };
+        for (outcome, dmg) in roll(&weak, &armored, DamageKind::Physical) {
+            if outcome == HitOutcome::Normal {
+                assert_eq!(dmg, 1.0);
+            }
+        }
+    }
+
+    #[test]
+    fn magic_always_hits_and_never_crits() {
+        let caster = ComputedAttributes {
+            crit_rate: 100.0,
+            ..attacker()
+        };
+        let dodger = ComputedAttributes {
+            dodge_rate: 1000.0,
+            ..default()
+        };
+        for (outcome, dmg) in roll(&caster, &dodger, DamageKind::Magical) {
+            assert_eq!(outcome, HitOutcome::Normal);
+            assert!((89.9..=110.1).contains(&dmg), "magic hit of {dmg}");
+        }
+    }
+}
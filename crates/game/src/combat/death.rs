@@ -0,0 +1,67 @@
+//! What happens between health reaching zero and coming back: dead bodies stop moving, players
+//! return to their [`SavePoint`] on request and pay the experience penalty.
+
+use super::*;
+use event::types::{ExpPenalty, RespawnRequest};
+
+pub fn plugin(app: &mut App) {
+    app.add_systems(
+        Update,
+        (hold_still, respawn_players).run_if(in_state(Screen::Gameplay)),
+    );
+}
+
+/// Movement systems skip the dead, so the last walk basis has to be cleared here, keeping the
+/// float height of each kind so bodies stay on the ground
+fn hold_still(mut dead: Query<(&mut TnuaController, Has<Player>), With<Dead>>) {
+    for (mut controller, is_player) in dead.iter_mut() {
+        let float_height = if is_player {
+            Player::FLOAT_HEIGHT
+        } else {
+            enemy::FLOAT_HEIGHT
+        };
+        controller.basis(TnuaBuiltinWalk {
+            float_height,
+            ..default()
+        });
+    }
+}
+
+fn respawn_players(
+    cfg: Res<Config>,
+    mut requests: EventReader<RespawnRequest>,
+    mut players: Query<
+        (
+            &SavePoint,
+            &mut Transform,
+            &mut ComputedAttributes,
+            Option<&mut LinearVelocity>,
+        ),
+        (With<Player>, With<Dead>),
+    >,
+    mut penalties: EventWriter<ExpPenalty>,
+    mut commands: Commands,
+) {
+    for RespawnRequest { entity } in requests.read().copied() {
+        let Ok((save_point, mut transform, mut attribs, velocity)) = players.get_mut(entity) else {
+            continue;
+        };
+
+        transform.translation = save_point.0;
+        if let Some(mut velocity) = velocity {
+            velocity.0 = Vec3::ZERO;
+        }
+        attribs.health.hp = attribs.health.max_hp;
+        attribs.mana.mp = attribs.mana.max_mp;
+
+        commands.entity(entity).remove::<Dead>();
+        penalties.write(ExpPenalty {
+            entity,
+            fraction: cfg.respawn.exp_penalty,
+        });
+        info!(
+            "Player({entity}) returned to the save point {}",
+            save_point.0
+        );
+    }
+}
@@ -1,8 +1,21 @@
 use super::*;
+use bevy::ecs::{query::QueryData, system::SystemParam};
 use std::time::Duration;
-use event::types::AttackEvent;
+use event::types::{AttackEvent, DamageResult, Died};
+
+mod damage;
+mod death;
+mod reaction;
+
+pub use damage::*;
+pub use reaction::HitStun;
 
 pub fn plugin(app: &mut App) {
+    app.add_plugins((damage::plugin, death::plugin, reaction::plugin));
+    app.register_type::<AttackElement>()
+        .register_type::<ElementLevel>()
+        .register_type::<WeaponType>()
+        .register_type::<RaceBonuses>();
     app.add_systems(
         Update,
         (
@@ -23,6 +36,25 @@ impl AttackRateTimer {
         timer.set_elapsed(Duration::from_secs_f32(secs));
         Self(timer)
     }
+
+    pub fn ready(&self) -> bool {
+        self.0.finished()
+    }
+
+    /// Starts the cooldown over, for a swing combat doesn't resolve itself
+    pub fn restart(&mut self) {
+        self.0.reset();
+    }
+
+    /// Changes the cooldown, a ready attack stays ready
+    pub fn set_rate(&mut self, secs: f32) {
+        let ready = self.0.finished();
+        let duration = Duration::from_secs_f32(secs.max(0.0));
+        self.0.set_duration(duration);
+        if ready {
+            self.0.set_elapsed(duration);
+        }
+    }
 }
 
 fn tick_attack_timers(mut q: Query<&mut AttackRateTimer>, time: Res<Time>) {
@@ -39,19 +71,49 @@ fn handle_attack_event(
         &mut ComputedAttributes,
         Option<&Player>,
         &mut AttackRateTimer,
+        Option<&Lockouts>,
+        Has<Dead>,
+        Has<Casting>,
     )>,
-    mut cmd: Commands,
+    mut strikes: Strikes,
 ) {
     for AttackEvent { attacker, target } in ev.read().copied() {
+        // a despawned target or one attacking itself doesn't hold up the other attacks
         let Ok([atkr_bundle, tgt_bundle]) = combatant_q.get_many_mut([attacker, target]) else {
-            return;
+            continue;
         };
 
-        let (atkr_tfm, atkr_collider, atkr_cattribs, atkr_is_player, mut atkr_atkrate_timer) =
-            atkr_bundle;
+        let (
+            atkr_tfm,
+            atkr_collider,
+            atkr_cattribs,
+            atkr_is_player,
+            mut atkr_atkrate_timer,
+            atkr_lockouts,
+            atkr_dead,
+            atkr_casting,
+        ) = atkr_bundle;
+
+        let (
+            tgt_tfm,
+            tgt_collider,
+            mut tgt_cattribs,
+            _tgt_enemy_opt,
+            _tgt_atkrate_timer_opt,
+            _tgt_lockouts,
+            tgt_dead,
+            _tgt_casting,
+        ) = tgt_bundle;
 
-        let (tgt_tfm, tgt_collider, mut tgt_cattribs, _tgt_enemy_opt, _tgt_atkrate_timer_opt) =
-            tgt_bundle;
+        // nobody fights or beats a corpse, and nobody swings in the middle of a cast
+        if atkr_dead || tgt_dead || atkr_casting {
+            continue;
+        }
+
+        // stunned, frozen and the like
+        if atkr_lockouts.is_some_and(|l| l.attack) {
+            continue;
+        }
 
         if !is_in_attack_range(
             atkr_tfm,
@@ -69,8 +131,15 @@ fn handle_attack_event(
         }
 
         // Apply damage
-        let dmg = atkr_cattribs.attack;
-        let died = apply_damage(&mut tgt_cattribs, dmg);
+        let Some((outcome, dmg)) = strikes.strike(
+            (attacker, &atkr_cattribs),
+            (target, &mut tgt_cattribs),
+            DamageKind::Physical,
+            None,
+            1.0,
+        ) else {
+            continue;
+        };
 
         let (atkr_type, tgt_type) = if let Some(_) = atkr_is_player {
             ("Player", "Enemy")
@@ -79,21 +148,123 @@ fn handle_attack_event(
         };
 
         info!(
-            "{atkr_type}({attacker}) deals {dmg} to {tgt_type}({target}); remaining hp {}",
+            "{atkr_type}({attacker}) {outcome:?} {dmg:.0} to {tgt_type}({target}); remaining hp {}",
             tgt_cattribs.health.hp
         );
 
-        if died {
-            info!("Target({target:?}) died");
-            cmd.entity(target).despawn();
-        } else {
-            // Re-arm cooldown after a successful hit
+        if tgt_cattribs.health.hp > 0.0 {
+            // Re-arm cooldown after a swing, misses included
             atkr_atkrate_timer.0.reset();
         }
     }
 }
 
-fn is_in_attack_range(
+/// What the damage tables look at on both sides of a hit, anything missing counts as neutral
+#[derive(QueryData)]
+pub struct DamageProfile {
+    attack_element: Option<&'static AttackElement>,
+    weapon: Option<&'static WeaponType>,
+    race_bonuses: Option<&'static RaceBonuses>,
+    element: Option<&'static Element>,
+    element_level: Option<&'static ElementLevel>,
+    size: Option<&'static BodySize>,
+    race: Option<&'static Race>,
+}
+
+/// Lands hits for auto attacks and skills alike: rolls, reports and deaths
+#[derive(SystemParam)]
+pub struct Strikes<'w, 's> {
+    cfg: Res<'w, Config>,
+    tables: Option<Res<'w, DamageTables>>,
+    profiles: Query<'w, 's, DamageProfile>,
+    rng: Option<ResMut<'w, CombatRng>>,
+    damage_results: EventWriter<'w, DamageResult>,
+    deaths: EventWriter<'w, Died>,
+    commands: Commands<'w, 's>,
+}
+
+impl Strikes<'_, '_> {
+    /// Rolls one hit of `multiplier` times the usual damage and applies it, a target out of hp
+    /// is marked [`Dead`]. `element` overrides the attacker's [`AttackElement`].
+    /// None when the target was killed already or the rng isn't seeded yet.
+    pub fn strike(
+        &mut self,
+        (attacker, atkr_cattribs): (Entity, &ComputedAttributes),
+        (target, tgt_cattribs): (Entity, &mut ComputedAttributes),
+        kind: DamageKind,
+        element: Option<Element>,
+        multiplier: f32,
+    ) -> Option<(HitOutcome, f32)> {
+        // hp covers a kill earlier this frame, before Dead is inserted
+        if tgt_cattribs.health.hp <= 0.0 {
+            return None;
+        }
+        let modifier = self.modifier(attacker, target, kind, element);
+        let rng = self.rng.as_mut()?;
+
+        let rules = &self.cfg.combat;
+        let (outcome, dmg) = resolve(atkr_cattribs, tgt_cattribs, kind, rules, &mut rng.0);
+        let dmg = scale_damage(dmg, multiplier, modifier);
+        self.damage_results.write(DamageResult {
+            attacker,
+            target,
+            kind,
+            outcome,
+            amount: dmg,
+        });
+
+        if apply_damage(tgt_cattribs, dmg) {
+            info!("Target({target:?}) died");
+            self.commands.entity(target).insert(Dead);
+            self.deaths.write(Died {
+                entity: target,
+                killer: attacker,
+            });
+        }
+
+        Some((outcome, dmg))
+    }
+
+    /// Element, size and race multiplier of a hit from the [`DamageTables`]. Size and race only
+    /// count for physical hits.
+    fn modifier(
+        &self,
+        attacker: Entity,
+        target: Entity,
+        kind: DamageKind,
+        element: Option<Element>,
+    ) -> f32 {
+        let (Some(tables), Ok(atkr), Ok(tgt)) = (
+            self.tables.as_deref(),
+            self.profiles.get(attacker),
+            self.profiles.get(target),
+        ) else {
+            return 1.0;
+        };
+
+        let attack_element = element
+            .or(atkr.attack_element.map(|e| **e))
+            .unwrap_or_default();
+        let defence_element = tgt.element.copied().unwrap_or_default();
+        let level = tgt.element_level.copied().unwrap_or_default();
+        let mut modifier = tables.element(attack_element, defence_element, *level);
+
+        if kind == DamageKind::Physical {
+            let size = tgt.size.copied().unwrap_or_default();
+            let race = tgt.race.copied().unwrap_or_default();
+            if let Some(weapon) = atkr.weapon {
+                modifier *= tables.size(*weapon, size);
+            }
+            if let Some(bonuses) = atkr.race_bonuses {
+                modifier *= bonuses.against(race);
+            }
+        }
+
+        modifier
+    }
+}
+
+pub(crate) fn is_in_attack_range(
     atkr_tfm: &Transform,
     atkr_collider: &Collider,
     atkr_range: f32,
@@ -114,8 +285,31 @@ fn is_in_attack_range(
     planar_dist <= collider_dist + atkr_range
 }
 
+/// Damage of a rolled hit after the skill multiplier and the [`DamageTables`] modifier. Immune
+/// elements deal nothing, they never heal.
+fn scale_damage(dmg: f32, multiplier: f32, modifier: f32) -> f32 {
+    (dmg * multiplier * modifier).max(0.0)
+}
+
 // Returns true if target health drops to 0 or less
 fn apply_damage(target: &mut ComputedAttributes, amount: f32) -> bool {
     target.health.hp -= amount;
     target.health.hp <= 0.0
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scale_damage_applies_the_modifier() {
+        assert_eq!(scale_damage(10.0, 2.0, 1.5), 30.0);
+        assert_eq!(scale_damage(10.0, 1.0, 0.0), 0.0);
+    }
+
+    #[test]
+    fn negative_modifiers_deal_nothing() {
+        assert_eq!(scale_damage(10.0, 1.0, -0.25), 0.0);
+        assert_eq!(scale_damage(10.0, 1.5, -0.5), 0.0);
+    }
+}
@@ -0,0 +1,147 @@
+//! Hit reactions: a landed hit stuns its target for a moment, heavy hits and knockback skills
+//! shove it away through Tnua's knockback action. Movement of players and enemies stops while
+//! [`HitStun`] lasts, players flinch meanwhile.
+
+use super::*;
+use bevy_tnua::builtins::TnuaBuiltinKnockback;
+use event::types::{DamageResult, Knockback};
+
+pub fn plugin(app: &mut App) {
+    app.add_systems(
+        Update,
+        (
+            stun_on_hit,
+            start_knockbacks,
+            tick_hit_stun,
+            knock_back.in_set(TnuaUserControlsSystemSet),
+        )
+            .chain()
+            .run_if(in_state(Screen::Gameplay)),
+    );
+}
+
+/// Kept from moving until the timer runs out
+#[derive(Component, Debug, Clone)]
+pub struct HitStun {
+    pub timer: Timer,
+    /// Velocity change of a knockback, fed to the controller while the stun lasts
+    pub shove: Option<Vec3>,
+}
+
+impl HitStun {
+    pub fn new(secs: f32, shove: Option<Vec3>) -> Self {
+        Self {
+            timer: Timer::from_seconds(secs, TimerMode::Once),
+            shove,
+        }
+    }
+}
+
+/// Stuns `target`, a longer stun running already is kept
+fn stun(commands: &mut Commands, target: Entity, incoming: HitStun) {
+    commands
+        .entity(target)
+        .entry::<HitStun>()
+        .and_modify({
+            let incoming = incoming.clone();
+            move |mut running| {
+                if running.timer.remaining() < incoming.timer.remaining() {
+                    running.timer = incoming.timer;
+                }
+                running.shove = incoming.shove.or(running.shove);
+            }
+        })
+        .or_insert(incoming);
+}
+
+/// Every landed hit stuns, heavy ones knock the target back on top
+fn stun_on_hit(
+    cfg: Res<Config>,
+    mut damage_results: EventReader<DamageResult>,
+    targets: Query<&ComputedAttributes, Without<Dead>>,
+    mut knockbacks: EventWriter<Knockback>,
+    mut commands: Commands,
+) {
+    let reactions = &cfg.combat.reactions;
+
+    for hit in damage_results.read() {
+        if hit.amount <= 0.0 {
+            continue;
+        }
+        let Ok(attribs) = targets.get(hit.target) else {
+            continue;
+        };
+        // killed by it
+        if attribs.health.hp <= 0.0 {
+            continue;
+        }
+
+        if hit.amount >= reactions.heavy_hit * attribs.health.max_hp {
+            knockbacks.write(Knockback {
+                target: hit.target,
+                source: hit.attacker,
+                force: reactions.heavy_knockback,
+            });
+        } else {
+            let hit_stun = HitStun::new(reactions.hit_stun, None);
+            stun(&mut commands, hit.target, hit_stun);
+        }
+    }
+}
+
+fn start_knockbacks(
+    cfg: Res<Config>,
+    mut knockbacks: EventReader<Knockback>,
+    transforms: Query<&Transform>,
+    targets: Query<(), (With<TnuaController>, Without<Dead>)>,
+    mut commands: Commands,
+) {
+    for knockback in knockbacks.read() {
+        if !targets.contains(knockback.target) {
+            continue;
+        }
+        let Ok(target) = transforms.get(knockback.target) else {
+            continue;
+        };
+        let source = transforms
+            .get(knockback.source)
+            .map_or(target.translation, |source| source.translation);
+
+        // straight back when both stand on the same spot
+        let away = (target.translation - source).with_y(0.0);
+        let direction = away.try_normalize().unwrap_or(*target.back());
+        let shove = direction * knockback.force;
+
+        let hit_stun = HitStun::new(cfg.combat.reactions.heavy_hit_stun, Some(shove));
+        stun(&mut commands, knockback.target, hit_stun);
+    }
+}
+
+fn tick_hit_stun(
+    time: Res<Time>,
+    mut stunned: Query<(Entity, &mut HitStun)>,
+    mut commands: Commands,
+) {
+    for (entity, mut stun) in &mut stunned {
+        if stun.timer.tick(time.delta()).finished() {
+            commands.entity(entity).remove::<HitStun>();
+        }
+    }
+}
+
+/// Tnua actions end once they aren't fed, so the knockback is fed until the stun is over
+fn knock_back(
+    mut stunned: Query<(&HitStun, &mut TnuaController), (Without<Interpolated>, Without<Dead>)>,
+) {
+    for (stun, mut controller) in &mut stunned {
+        let Some(shove) = stun.shove else {
+            continue;
+        };
+        controller.action(TnuaBuiltinKnockback {
+            shove,
+            // facing whoever hit it
+            force_forward: Dir3::new(-shove).ok(),
+            ..default()
+        });
+    }
+}
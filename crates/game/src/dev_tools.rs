@@ -2,10 +2,12 @@
 
 use super::*;
 use bevy::{
-    dev_tools::states::log_transitions, input::common_conditions::input_toggle_active,
+    dev_tools::states::log_transitions,
+    input::common_conditions::{input_just_pressed, input_toggle_active},
     ui::UiDebugOptions,
 };
 use bevy_inspector_egui::{bevy_egui::EguiPlugin, quick::WorldInspectorPlugin};
+use event::types::ChangeJob;
 
 pub(super) fn plugin(app: &mut App) {
     app.add_plugins((EguiPlugin {
@@ -15,9 +17,41 @@ pub(super) fn plugin(app: &mut App) {
             WorldInspectorPlugin::new().run_if(input_toggle_active(false, KeyCode::Backquote)),
         )
         .add_systems(Update, log_transitions::<Screen>)
+        .add_systems(
+            Update,
+            cycle_job.run_if(in_state(Screen::Gameplay).and(input_just_pressed(KeyCode::F5))),
+        )
         .add_observer(toggle_debug_ui);
 }
 
 fn toggle_debug_ui(_: Trigger<OnDebugUiToggle>, mut options: ResMut<UiDebugOptions>) {
     options.toggle();
 }
+
+/// Changes the local player into the next job of the database, requirements or not
+fn cycle_job(
+    job_db: Option<Res<JobDb>>,
+    player: Single<(Entity, &Job), With<LocalPlayer>>,
+    mut change_job: EventWriter<ChangeJob>,
+) {
+    let Some(job_db) = job_db else {
+        return;
+    };
+    let (entity, job) = *player;
+
+    let mut jobs: Vec<Job> = job_db.jobs.keys().copied().collect();
+    jobs.sort();
+    let Some(next) = jobs
+        .iter()
+        .position(|j| j == job)
+        .map_or(jobs.first(), |i| jobs.get((i + 1) % jobs.len()))
+    else {
+        return;
+    };
+
+    change_job.write(ChangeJob {
+        entity,
+        job: *next,
+        forced: true,
+    });
+}
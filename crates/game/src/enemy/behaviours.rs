@@ -1,118 +1,278 @@
+//! Enemy AI: every enemy runs its own [`AiState`] machine. It idles and wanders around its
+//! [`SpawnPoint`], chases and attacks whoever it fights, flees at low hp and walks back home
+//! once it strays past its leash.
+
 use super::*;
-use event::types::{PositionChangeEvent, AttackEvent};
+use event::types::AttackEvent;
+use navigation::NavMesh;
+use rand::prelude::*;
+use std::f32::consts::TAU;
 
 pub const FLOAT_HEIGHT: f32 = 0.5;
 pub const IDLE_TO_RUN_TRESHOLD: f32 = 0.01;
+/// Close enough to a wander point or home
+const ARRIVE_DISTANCE: f32 = 0.5;
+/// Wandering is a stroll, not a run
+const WANDER_SPEED_FACTOR: f32 = 0.5;
+/// How far ahead of itself a fleeing enemy aims
+const FLEE_DISTANCE: f32 = 5.0;
+/// Random points tried before giving up on wandering for now
+const WANDER_TRIES: usize = 8;
 
 pub fn plugin(app: &mut App) {
-    app.add_systems(
-        Update,
-        (
-            update_target_position,
-            move_to_pos,
-            attack_player,
-        )
-            .run_if(in_state(Screen::Gameplay)),
-    );
+    app.register_type::<Ai>()
+        .register_type::<AiState>()
+        .add_systems(
+            Update,
+            (think, move_to_pos, attack_player)
+                .chain()
+                .run_if(in_state(Screen::Gameplay)),
+        );
 }
 
-fn update_target_position(
-    mut movement_evt: EventReader<PositionChangeEvent>,
-    player_query: Query<&Transform, With<Player>>,
-    mut enemy_query: Query<&mut Enemy, With<Enemy>>,
-) -> Result {
-    let Some(movement_evt) = movement_evt.read().next() else {
-        return Ok(());
-    };
+pub(super) fn think(
+    time: Res<Time>,
+    cfg: Res<Config>,
+    mut enemy_query: Query<
+        (
+            &mut Enemy,
+            &mut Ai,
+            &mut AiState,
+            &mut ThreatTable,
+            &ComputedAttributes,
+            &Transform,
+            &Collider,
+            &SpawnPoint,
+        ),
+        (Without<Interpolated>, Without<Dead>),
+    >,
+    target_query: Query<(&Transform, &Collider), (With<ComputedAttributes>, Without<Dead>)>,
+    nav: Res<NavMesh>,
+) {
+    let mut rng = thread_rng();
 
-    let Ok(mut enemy) = enemy_query.get_mut(movement_evt.entity) else {
-        return Ok(());
-    };
+    for (
+        mut enemy,
+        mut ai,
+        mut state,
+        mut threat,
+        comp_attribs,
+        transform,
+        collider,
+        spawn_point,
+    ) in &mut enemy_query
+    {
+        ai.timer.tick(time.delta());
+
+        let position = transform.translation;
+        let home = spawn_point.translation;
+        // picked from the threat table, it may have died or left the world since
+        let target = enemy.target_entity.and_then(|e| target_query.get(e).ok());
+        let low_hp = comp_attribs.health.hp < comp_attribs.health.max_hp * ai.flee_below;
+
+        let next = match *state {
+            AiState::Idle | AiState::Wander(_) if target.is_some() => AiState::Chase,
+            AiState::Idle if ai.timer.finished() => {
+                match wander_point(&nav, ai.wander_radius, position, home, &mut rng) {
+                    Some(point) => AiState::Wander(point),
+                    // walled in, tries again after another rest
+                    None => {
+                        rest(&mut ai, &cfg.ai, &mut rng);
+                        AiState::Idle
+                    }
+                }
+            }
+            AiState::Wander(point) if arrived(position, point) => {
+                rest(&mut ai, &cfg.ai, &mut rng);
+                AiState::Idle
+            }
+            AiState::Chase | AiState::Attack => match target {
+                None => AiState::Leash,
+                Some(_) if position.xz().distance(home.xz()) > ai.leash_radius => AiState::Leash,
+                Some(_) if low_hp => {
+                    ai.timer = Timer::from_seconds(cfg.ai.flee_time, TimerMode::Once);
+                    AiState::Flee
+                }
+                Some((target_transform, target_collider)) => {
+                    if combat::is_in_attack_range(
+                        transform,
+                        collider,
+                        comp_attribs.attack_range,
+                        target_transform,
+                        target_collider,
+                    ) {
+                        AiState::Attack
+                    } else {
+                        AiState::Chase
+                    }
+                }
+            },
+            AiState::Flee if target.is_none() || ai.timer.finished() => AiState::Leash,
+            AiState::Leash if arrived(position, home) => {
+                rest(&mut ai, &cfg.ai, &mut rng);
+                AiState::Idle
+            }
+            other => other,
+        };
+
+        // a fresh start once home, whoever was fought is forgotten
+        if next == AiState::Leash && *state != AiState::Leash {
+            threat.clear();
+            enemy.target_entity = None;
+        }
+
+        enemy.target_position = match next {
+            AiState::Idle | AiState::Attack => None,
+            AiState::Wander(point) => Some(point),
+            AiState::Chase => target.map(|(t, _)| t.translation),
+            AiState::Leash => Some(home),
+            AiState::Flee => target.map(|(t, _)| {
+                let away = (position - t.translation).with_y(0.0).normalize_or_zero();
+                position + away * FLEE_DISTANCE
+            }),
+        };
+
+        // keeps the inspector from flagging a change every frame
+        if *state != next {
+            *state = next;
+        }
+    }
+}
 
-    let Ok(player_transform) = player_query.get(movement_evt.target) else {
-        return Ok(());
+/// Stands around for a random while before wandering again
+fn rest(ai: &mut Ai, rules: &AiConfig, rng: &mut impl Rng) {
+    let (low, high) = rules.idle_time;
+    let secs = if high > low {
+        rng.gen_range(low..=high)
+    } else {
+        low
     };
+    ai.timer = Timer::from_seconds(secs, TimerMode::Once);
+}
 
-    enemy.target_entity = Some(movement_evt.target);
-    enemy.target_position = Some(player_transform.translation);
+/// A random point around `home` on walkable ground the enemy can walk to, anywhere before the
+/// first bake
+fn wander_point(
+    nav: &NavMesh,
+    radius: f32,
+    position: Vec3,
+    home: Vec3,
+    rng: &mut impl Rng,
+) -> Option<Vec3> {
+    (0..WANDER_TRIES).find_map(|_| {
+        let distance = radius * rng.r#gen::<f32>().sqrt();
+        let offset = Vec2::from_angle(rng.gen_range(0.0..TAU)) * distance;
+        let point = home + Vec3::new(offset.x, 0.0, offset.y);
+        let reachable =
+            !nav.is_baked() || (nav.is_walkable(point) && nav.find_path(position, point).is_some());
+        reachable.then_some(point)
+    })
+}
 
-    Ok(())
+fn arrived(position: Vec3, point: Vec3) -> bool {
+    position.xz().distance(point.xz()) <= ARRIVE_DISTANCE
 }
 
 fn move_to_pos(
     mut enemy_query: Query<
         (
-            &mut Enemy,
+            &Enemy,
+            &AiState,
             &ComputedAttributes,
             &mut TnuaController,
             &mut StepTimer,
+            &mut NavPath,
             &Transform,
-            &Collider,
+            Option<&Lockouts>,
+            Has<HitStun>,
         ),
-        With<Enemy>,
+        (Without<Interpolated>, Without<Dead>),
     >,
-    player_query: Query<&Collider, With<Player>>,
+    target_query: Query<&Transform, With<ComputedAttributes>>,
+    nav: Res<NavMesh>,
+    time: Res<Time>,
     cfg: Res<Config>,
-) -> Result {
-    let mut desired_velocity = Vec3::ZERO;
-    let mut desired_forward: Option<Dir3> = None;
-
+) {
     for (
         enemy,
+        state,
         enemy_comp_attribs,
         mut controller,
         mut step_timer,
+        mut path,
         enemy_transform,
-        enemy_collider,
+        lockouts,
+        stunned,
     ) in enemy_query.iter_mut()
     {
-        if let Some(target_position) = enemy.target_position {
-            let mut direction = target_position - enemy_transform.translation;
-            let dist = direction.xz().length();
-            direction = direction.normalize_or_zero();
+        let mut desired_velocity = Vec3::ZERO;
+        let mut desired_forward: Option<Dir3> = None;
 
-            let player_collider = player_query.single()?;
-            let Some(player_radius) = utils::get_capsule_radius(player_collider) else {
-                continue;
-            };
-
-            let Some(enemy_radius) = utils::get_capsule_radius(enemy_collider) else {
-                continue;
-            };
+        if let Some(target_position) = enemy.target_position {
+            let position = enemy_transform.translation;
+            // around rocks and walls instead of straight into them
+            let next = navigation::steer(
+                &nav,
+                &mut path,
+                position,
+                target_position,
+                time.delta_secs(),
+            );
+            let direction = (next - position).with_y(0.0).normalize_or_zero();
 
-            const COLLISION_BUFFER: f32 = 0.5;
-            let collision_dist = enemy_radius + player_radius + COLLISION_BUFFER;
+            if position.xz().distance(target_position.xz()) > ARRIVE_DISTANCE {
+                let speed = match state {
+                    AiState::Wander(_) => enemy_comp_attribs.move_speed * WANDER_SPEED_FACTOR,
+                    _ => enemy_comp_attribs.move_speed,
+                };
+                desired_velocity = direction * speed;
+                desired_forward = Dir3::new(direction).ok();
+            }
+        } else if path.goal.is_some() {
+            *path = NavPath::default();
+        }
 
-            if dist >= collision_dist {
-                desired_velocity = direction * enemy_comp_attribs.move_speed;
+        if *state == AiState::Attack {
+            // keep facing whoever is being hit
+            if let Some(Ok(target_transform)) = enemy.target_entity.map(|e| target_query.get(e)) {
+                let direction = (target_transform.translation - enemy_transform.translation)
+                    .with_y(0.0)
+                    .normalize_or_zero();
                 desired_forward = Dir3::new(direction).ok();
             }
+        }
 
-            controller.basis(TnuaBuiltinWalk {
-                float_height: FLOAT_HEIGHT,
-                cling_distance: FLOAT_HEIGHT + 0.01, // Slightly higher than float_height for a bit of "give".
-                spring_strength: 500.0,              // Stronger spring for a more grounded feel.
-                spring_dampening: 1.0, // Slightly reduced dampening for a more responsive spring.
-                acceleration: 80.0, // Increased acceleration for snappier movement starts and stops.
-                air_acceleration: 30.0, // Allow for some air control, but less than ground.
-                free_fall_extra_gravity: 70.0, // Slightly increased for a less floaty fall.
-                tilt_offset_angvel: 7.0, // Increased for a slightly faster righting response.
-                tilt_offset_angacl: 700.0, // Increased acceleration to reach the target righting speed.
-                turning_angvel: 12.0,      // Increased for more responsive turning.
-
-                desired_velocity,
-                desired_forward,
-
-                ..default()
-            });
+        // stunned, frozen, reeling from a hit and the like
+        if stunned || lockouts.is_some_and(|l| l.movement) {
+            desired_velocity = Vec3::ZERO;
+            desired_forward = None;
         }
 
+        // fed every frame, standing still included, so Tnua never keeps a stale velocity
+        controller.basis(TnuaBuiltinWalk {
+            float_height: FLOAT_HEIGHT,
+            cling_distance: FLOAT_HEIGHT + 0.01, // Slightly higher than float_height for a bit of "give".
+            spring_strength: 500.0,              // Stronger spring for a more grounded feel.
+            spring_dampening: 1.0, // Slightly reduced dampening for a more responsive spring.
+            acceleration: 80.0,    // Increased acceleration for snappier movement starts and stops.
+            air_acceleration: 30.0, // Allow for some air control, but less than ground.
+            free_fall_extra_gravity: 70.0, // Slightly increased for a less floaty fall.
+            tilt_offset_angvel: 7.0, // Increased for a slightly faster righting response.
+            tilt_offset_angacl: 700.0, // Increased acceleration to reach the target righting speed.
+            turning_angvel: 12.0,  // Increased for more responsive turning.
+
+            desired_velocity,
+            desired_forward,
+
+            ..default()
+        });
+
         // update step timer dynamically based on actual speed
         // normal step: 0.475
         // sprint step (x1.5): 0.354
         // step on sprint timer: 0.317
         let Some((_, basis_state)) = controller.concrete_basis::<TnuaBuiltinWalk>() else {
-            return Ok(());
+            continue;
         };
 
         let current_actual_speed = basis_state.running_velocity.length();
@@ -124,42 +284,26 @@ fn move_to_pos(
             step_timer.set_duration(adjusted_step_time);
         }
     }
-
-    Ok(())
 }
 
+/// Enemies in [`AiState::Attack`] swing at their target, the combat module gates the cooldown
 fn attack_player(
     mut attack_event: EventWriter<AttackEvent>,
-    mut enemy_query: Query<(&Enemy, &ComputedAttributes, &Transform, &Collider)>,
-    player_query: Query<(&Player, &Transform, &Collider)>,
-) -> Result {
-    let (player, player_transform, player_collider) = player_query.single()?;
-
-    for (enemy, enemy_comp_attribs, enemy_transform, enemy_collider) in enemy_query.iter_mut() {
-        let direction = player_transform.translation - enemy_transform.translation;
-        let dist = direction.xz().length();
-
-        let Some(player_radius) = utils::get_capsule_radius(player_collider) else {
-            continue;
-        };
-
-        let Some(enemy_radius) = utils::get_capsule_radius(enemy_collider) else {
+    enemy_query: Query<
+        (&Enemy, &AiState, Option<&Lockouts>),
+        (Without<Interpolated>, Without<Dead>),
+    >,
+) {
+    for (enemy, state, lockouts) in enemy_query.iter() {
+        if *state != AiState::Attack || lockouts.is_some_and(|l| l.attack) {
             continue;
-        };
-
-        // note: we do this same check in the combat module to prohibit out-of-range attacks
-        // however, later logic will process attack decisions based on multiple ranges (e.g spells, skills)
-        const COLLISION_BUFFER: f32 = 0.5;
-        let collider_dist = enemy_radius + player_radius + COLLISION_BUFFER;
-        let is_in_range = dist <= collider_dist + enemy_comp_attribs.attack_range;
+        }
 
-        if is_in_range {
+        if let Some(target) = enemy.target_entity {
             attack_event.write(AttackEvent {
                 attacker: enemy.id,
-                target: player.id,
+                target,
             });
         }
     }
-
-    Ok(())
-}
\ No newline at end of file
+}
@@ -0,0 +1,81 @@
+//! Dead enemies stay around as a fading [`Corpse`] for `RespawnConfig::corpse_fade` seconds.
+//! Replacing them is up to their spawn zone.
+
+use super::*;
+
+pub fn plugin(app: &mut App) {
+    app.add_observer(leave_corpse)
+        .add_systems(Update, rot_corpses.run_if(in_state(Screen::Gameplay)));
+}
+
+#[cfg(feature = "render")]
+pub fn render_plugin(app: &mut App) {
+    app.add_observer(make_translucent)
+        .add_systems(Update, fade_corpses.run_if(in_state(Screen::Gameplay)));
+}
+
+fn leave_corpse(
+    on: Trigger<OnAdd, Dead>,
+    cfg: Res<Config>,
+    mut enemies: Query<&mut Enemy>,
+    mut commands: Commands,
+) {
+    let Ok(mut enemy) = enemies.get_mut(on.target()) else {
+        return;
+    };
+
+    enemy.target_entity = None;
+    enemy.target_position = None;
+    commands
+        .entity(on.target())
+        .insert(Corpse(Timer::from_seconds(
+            cfg.respawn.corpse_fade,
+            TimerMode::Once,
+        )));
+}
+
+fn rot_corpses(time: Res<Time>, mut corpses: Query<(Entity, &mut Corpse)>, mut commands: Commands) {
+    for (entity, mut corpse) in corpses.iter_mut() {
+        if corpse.tick(time.delta()).finished() {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+/// Materials of the model are shared between enemies, every corpse gets its own copy to fade
+#[cfg(feature = "render")]
+fn make_translucent(
+    on: Trigger<OnAdd, Corpse>,
+    children: Query<&Children>,
+    mut meshes: Query<&mut MeshMaterial3d<StandardMaterial>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    for entity in children.iter_descendants(on.target()) {
+        let Ok(mut material) = meshes.get_mut(entity) else {
+            continue;
+        };
+        let Some(mut copy) = materials.get(&material.0).cloned() else {
+            continue;
+        };
+
+        copy.alpha_mode = AlphaMode::Blend;
+        material.0 = materials.add(copy);
+    }
+}
+
+#[cfg(feature = "render")]
+fn fade_corpses(
+    corpses: Query<(Entity, &Corpse)>,
+    children: Query<&Children>,
+    meshes: Query<&MeshMaterial3d<StandardMaterial>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    for (entity, corpse) in corpses.iter() {
+        let alpha = corpse.fraction_remaining();
+        for mesh in meshes.iter_many(children.iter_descendants(entity)) {
+            if let Some(material) = materials.get_mut(&mesh.0) {
+                material.base_color.set_alpha(alpha);
+            }
+        }
+    }
+}
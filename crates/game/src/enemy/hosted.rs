@@ -0,0 +1,49 @@
+//! Monsters of a dedicated server: whatever the spawn zones bring in is announced to the clients
+//! through the [`LocalServer`], which follows the simulation around and forgets a monster once
+//! its corpse is gone.
+
+use super::*;
+use std::collections::HashSet;
+
+pub fn plugin(app: &mut App) {
+    app.add_systems(
+        Update,
+        (forget_monsters, announce_monsters, follow_monsters)
+            .chain()
+            .run_if(character::hosting)
+            .run_if(in_state(Screen::Gameplay)),
+    );
+}
+
+fn forget_monsters(mut server: ResMut<LocalServer>, monsters: Query<&NetId, With<MonsterId>>) {
+    let hosted: HashSet<NetId> = monsters.iter().copied().collect();
+    let gone: Vec<NetId> = server
+        .entities()
+        .filter(|(id, entity)| entity.kind == EntityKind::Enemy && !hosted.contains(id))
+        .map(|(id, _)| id)
+        .collect();
+    for id in gone {
+        server.despawn(id);
+    }
+}
+
+fn announce_monsters(
+    mut server: ResMut<LocalServer>,
+    monsters: Query<(Entity, &Transform, &ComputedAttributes), (With<MonsterId>, Without<NetId>)>,
+    mut commands: Commands,
+) {
+    for (entity, transform, attribs) in &monsters {
+        let id = server.spawn(EntityKind::Enemy, transform.translation, *attribs);
+        commands.entity(entity).insert(id);
+    }
+}
+
+/// The simulation moves monsters, the server passes it on with its snapshots
+fn follow_monsters(
+    mut server: ResMut<LocalServer>,
+    monsters: Query<(&NetId, &Transform), (With<MonsterId>, Changed<Transform>)>,
+) {
+    for (id, transform) in &monsters {
+        server.place(*id, transform.translation, transform.rotation);
+    }
+}
@@ -1,99 +1,167 @@
 use super::*;
 use bevy_tnua_avian3d::TnuaAvian3dSensorShape;
-use std::{f32::consts::PI, time::Duration};
 use combat::*;
+use std::{collections::HashMap, time::Duration};
+use thiserror::Error;
 
 mod animation;
 mod behaviours;
+mod corpse;
+mod hosted;
+mod spawn_zone;
+mod threat;
+
+pub use behaviours::FLOAT_HEIGHT;
+pub use spawn_zone::{SpawnZone, SpawnedBy};
 
 pub fn plugin(app: &mut App) {
-    app.add_plugins(behaviours::plugin);
-    app.add_systems(OnEnter(Screen::Gameplay), spawn_enemy);
+    app.add_plugins((
+        behaviours::plugin,
+        corpse::plugin,
+        hosted::plugin,
+        spawn_zone::plugin,
+        threat::plugin,
+    ));
 }
 
-pub fn spawn_enemy(
-    cfg: Res<Config>,
-    models: Res<Models>,
-    gltf_assets: Res<Assets<Gltf>>,
-    mut commands: Commands,
-    // mut meshes: ResMut<Assets<Mesh>>,
-    // mut materials: ResMut<Assets<StandardMaterial>>,
-) -> Result {
-    let Some(gltf) = gltf_assets.get(&models.enemy) else {
-        return Ok(());
-    };
+/// Enemies are spawned without a model so the server can simulate them, the client dresses
+/// every new [`Enemy`] up here
+#[cfg(feature = "render")]
+pub fn render_plugin(app: &mut App) {
+    app.add_observer(attach_model)
+        .add_plugins(corpse::render_plugin);
+}
+
+#[derive(Debug, Error)]
+#[error("no monster with id {0} in the monster database")]
+pub struct UnknownMonster(pub u32);
+
+/// Everything an enemy needs to be simulated, looked up by id in the [`MonsterDb`].
+/// The model is added by [`render_plugin`].
+pub fn monster_bundle(
+    cfg: &Config,
+    monster_db: &MonsterDb,
+    id: MonsterId,
+    pos: Transform,
+) -> Result<impl Bundle, UnknownMonster> {
+    let def = monster_db.get(id).ok_or(UnknownMonster(id.0))?;
 
-    let enemy_rot = Quat::from_rotation_y(PI);
-    let mesh = SceneRoot(gltf.scenes[0].clone());
-    let pos = Transform::from_translation(Vec3::new(20.0, 5.0, 0.0)).with_rotation(enemy_rot);
     let enemy = Enemy {
         id: Entity::PLACEHOLDER,
+        aggro_radius: def.aggro_radius,
         // animation_state: AnimationState::StandIdle,
         ..default()
     };
 
     let collider = Collider::capsule(cfg.player.hitbox.radius, cfg.player.hitbox.height);
 
-    let attribs = Attributes::default();
+    // the rest is derived from the attributes, see `stats`
     let comp_attribs = ComputedAttributes {
-        move_speed: 3.0,
-        attack: 2.0,
-        attack_range: 2.0,
-        attack_rate: 1.6,
-        health: Health::new(10.0),
+        attack_range: def.attack_range,
         ..default()
     };
+    let overrides = StatOverrides(HashMap::from([
+        (Stat::MoveSpeed, def.move_speed),
+        (Stat::AttackRate, def.attack_rate),
+    ]));
 
-    let attack_timer = AttackRateTimer::new(comp_attribs.attack_rate);
+    let attack_timer = AttackRateTimer::new(def.attack_rate);
 
-    commands
-        .spawn((
-            StateScoped(Screen::Gameplay),
-            pos,
-            enemy.clone(),
-            attribs,
+    let ai = Ai {
+        temper: def.temper,
+        timer: Timer::from_seconds(cfg.ai.idle_time.0, TimerMode::Once),
+        wander_radius: cfg.ai.wander_radius,
+        leash_radius: cfg.ai.leash_radius,
+        flee_below: def.flee_below,
+    };
+
+    Ok((
+        Name::new(def.name.clone()),
+        StateScoped(Screen::Gameplay),
+        pos,
+        SpawnPoint(pos),
+        id,
+        enemy,
+        (ai, AiState::default(), ThreatTable::default()),
+        (
+            def.attributes,
+            Level(def.level),
+            StatModifiers::default(),
+            StatusEffects::resisting(def.status_resists.iter().copied()),
+            overrides,
             comp_attribs,
-            (
-                TnuaController::default(),
-                // Tnua can fix the rotation, but the character will still get rotated before it can do so.
-                // By locking the rotation we can prevent this.
-                LockedAxes::ROTATION_LOCKED.unlock_rotation_y(),
-                // TnuaAnimatingState::<AnimationState>::default(),
-                // TnuaSimpleAirActionsCounter::default(),
-                // A sensor shape is not strictly necessary, but without it we'll get weird results.
-                TnuaAvian3dSensorShape(collider.clone()),
-            ),
-            // physics
-            (
-                collider,
-                RigidBody::Dynamic,
-                // Friction::ZERO.with_combine_rule(CoefficientCombine::Multiply),
-            ),
-            // Timers
-            StepTimer(Timer::from_seconds(cfg.timers.step, TimerMode::Repeating)),
-            attack_timer,
-            InheritedVisibility::default(), // silence the warning because of adding SceneRoot as a child
-        ))
-        // spawn character mesh as child to adjust mesh position relative to the player origin
-        .with_children(|parent| {
-            parent.spawn((Transform::from_xyz(0.0, -1.5, 0.0), mesh));
-            // e.observe(prepare_animations);
+            def.element,
+            ElementLevel(def.element_level),
+            def.size,
+            def.race,
+        ),
+        (
+            TnuaController::default(),
+            NavPath::default(),
+            // Tnua can fix the rotation, but the character will still get rotated before it can do so.
+            // By locking the rotation we can prevent this.
+            LockedAxes::ROTATION_LOCKED.unlock_rotation_y(),
+            // TnuaAnimatingState::<AnimationState>::default(),
+            // TnuaSimpleAirActionsCounter::default(),
+            // A sensor shape is not strictly necessary, but without it we'll get weird results.
+            TnuaAvian3dSensorShape(collider.clone()),
+        ),
+        // physics
+        (
+            collider,
+            RigidBody::Dynamic,
+            // Friction::ZERO.with_combine_rule(CoefficientCombine::Multiply),
+        ),
+        // Timers
+        StepTimer(Timer::from_seconds(cfg.timers.step, TimerMode::Repeating)),
+        attack_timer,
+        InheritedVisibility::default(), // silence the warning because of adding SceneRoot as a child
+    ))
+}
+
+#[cfg(feature = "render")]
+fn attach_model(
+    on: Trigger<OnAdd, Enemy>,
+    models: Res<Models>,
+    monster_db: Res<MonsterDb>,
+    monster_ids: Query<&MonsterId>,
+    asset_server: Res<AssetServer>,
+    gltf_assets: Res<Assets<Gltf>>,
+    mut commands: Commands,
+) {
+    // replicated enemies don't know what monster they are yet, they all look like porings
+    let scene = match monster_ids
+        .get(on.target())
+        .ok()
+        .and_then(|id| monster_db.get(*id))
+    {
+        Some(def) => asset_server.load(GltfAssetLabel::Scene(0).from_asset(def.model.clone())),
+        None => {
+            let Some(gltf) = gltf_assets.get(&models.enemy) else {
+                return;
+            };
+            gltf.scenes[0].clone()
+        }
+    };
 
-            // DEBUG
-            // let collider_mesh = Mesh::from(Capsule3d::new(
-            //     cfg.player.hitbox.radius,
-            //     cfg.player.hitbox.height,
-            // ));
-            // let debug_collider_mesh = Mesh3d(meshes.add(collider_mesh.clone()));
-            // let debug_collider_color: MeshMaterial3d<StandardMaterial> =
-            //     MeshMaterial3d(materials.add(Color::srgba(0.9, 0.9, 0.9, 0.1)));
-            // parent.spawn((
-            //     debug_collider_mesh,
-            //     debug_collider_color,
-            //     Transform::from_xyz(0.0, -0.1, 0.0),
-            // ));
-            // DEBUG
-        });
+    // spawn character mesh as child to adjust mesh position relative to the enemy origin
+    commands.entity(on.target()).with_children(|parent| {
+        parent.spawn((Transform::from_xyz(0.0, -1.5, 0.0), SceneRoot(scene)));
+        // e.observe(prepare_animations);
 
-    Ok(())
-}
\ No newline at end of file
+        // DEBUG
+        // let collider_mesh = Mesh::from(Capsule3d::new(
+        //     cfg.player.hitbox.radius,
+        //     cfg.player.hitbox.height,
+        // ));
+        // let debug_collider_mesh = Mesh3d(meshes.add(collider_mesh.clone()));
+        // let debug_collider_color: MeshMaterial3d<StandardMaterial> =
+        //     MeshMaterial3d(materials.add(Color::srgba(0.9, 0.9, 0.9, 0.1)));
+        // parent.spawn((
+        //     debug_collider_mesh,
+        //     debug_collider_color,
+        //     Transform::from_xyz(0.0, -0.1, 0.0),
+        // ));
+        // DEBUG
+    });
+}
@@ -0,0 +1,192 @@
+//! Spawn zones declared in [`MapData`] keep their population of monsters alive. Every death
+//! queues a replacement that shows up after the delay of its [`SpawnEntry`], somewhere on
+//! walkable ground inside the zone.
+
+use super::*;
+use rand::prelude::*;
+use std::f32::consts::TAU;
+
+/// How often a random point is tried before waiting for the next frame
+const PLACEMENT_ATTEMPTS: usize = 8;
+/// Rays are cast down from this height to find the ground
+const RAY_HEIGHT: f32 = 100.0;
+/// Steeper ground isn't walkable
+const MIN_WALKABLE_NORMAL_Y: f32 = 0.7;
+/// Monsters are dropped a bit above the ground, the controller settles them
+const SPAWN_HEIGHT: f32 = 2.0;
+
+pub fn plugin(app: &mut App) {
+    app.add_systems(OnEnter(Screen::Gameplay), spawn_zones)
+        .add_systems(
+            Update,
+            (track_population, respawn_monsters)
+                .chain()
+                .run_if(in_state(Screen::Gameplay)),
+        );
+}
+
+#[derive(Component)]
+pub struct SpawnZone {
+    pub def: SpawnZoneDef,
+    pending: Vec<PendingSpawn>,
+}
+
+impl SpawnZone {
+    /// The whole population is due right away
+    pub fn new(def: SpawnZoneDef) -> Self {
+        let pending = def
+            .entries
+            .iter()
+            .enumerate()
+            .flat_map(|(entry, spawn)| {
+                (0..spawn.population).map(move |_| PendingSpawn {
+                    entry,
+                    timer: Timer::from_seconds(0.0, TimerMode::Once),
+                })
+            })
+            .collect();
+
+        Self { def, pending }
+    }
+}
+
+struct PendingSpawn {
+    /// Index in [`SpawnZoneDef::entries`]
+    entry: usize,
+    timer: Timer,
+}
+
+/// Monster kept alive by a [`SpawnZone`]
+#[derive(Component, Debug, Clone, Copy)]
+pub struct SpawnedBy {
+    pub zone: Entity,
+    pub entry: usize,
+}
+
+fn spawn_zones(map: Res<MapData>, mut commands: Commands) {
+    for def in map.spawn_zones.iter() {
+        commands.spawn((
+            Name::new(format!("Spawn Zone {}", def.name)),
+            StateScoped(Screen::Gameplay),
+            SpawnZone::new(def.clone()),
+        ));
+    }
+}
+
+/// Queues a replacement for every member that died or disappeared
+fn track_population(
+    mut zones: Query<(Entity, &mut SpawnZone)>,
+    members: Query<&SpawnedBy, Without<Dead>>,
+) {
+    let mut alive: HashMap<(Entity, usize), usize> = HashMap::new();
+    for member in members.iter() {
+        *alive.entry((member.zone, member.entry)).or_default() += 1;
+    }
+
+    for (zone_entity, mut zone) in zones.iter_mut() {
+        let SpawnZone { def, pending } = &mut *zone;
+        for (entry, spawn) in def.entries.iter().enumerate() {
+            let alive = alive.get(&(zone_entity, entry)).copied().unwrap_or(0);
+            let queued = pending.iter().filter(|p| p.entry == entry).count();
+            let missing = (spawn.population as usize).saturating_sub(alive + queued);
+
+            for _ in 0..missing {
+                pending.push(PendingSpawn {
+                    entry,
+                    timer: Timer::from_seconds(spawn.respawn, TimerMode::Once),
+                });
+            }
+        }
+    }
+}
+
+fn respawn_monsters(
+    time: Res<Time>,
+    cfg: Res<Config>,
+    monster_db: Res<MonsterDb>,
+    spatial_query: SpatialQuery,
+    bodies: Query<&RigidBody>,
+    mut zones: Query<(Entity, &mut SpawnZone)>,
+    mut commands: Commands,
+) {
+    let mut rng = thread_rng();
+
+    for (zone_entity, mut zone) in zones.iter_mut() {
+        let SpawnZone { def, pending } = &mut *zone;
+        pending.retain_mut(|spawn| {
+            if !spawn.timer.tick(time.delta()).finished() {
+                return true;
+            }
+
+            // nothing to stand on yet, e.g. physics didn't see the ground on the first frame
+            let Some(position) = find_walkable(&def.area, &spatial_query, &bodies, &mut rng) else {
+                return true;
+            };
+
+            let monster = MonsterId(def.entries[spawn.entry].monster);
+            let rotation = Quat::from_rotation_y(rng.gen_range(0.0..TAU));
+            let pos = Transform::from_translation(position).with_rotation(rotation);
+            // the database may have been edited since, hot reloading in dev builds
+            match monster_bundle(&cfg, &monster_db, monster, pos) {
+                Ok(bundle) => {
+                    commands.spawn((
+                        bundle,
+                        SpawnedBy {
+                            zone: zone_entity,
+                            entry: spawn.entry,
+                        },
+                    ));
+                }
+                Err(e) => warn!("spawn zone '{}': {e}", def.name),
+            }
+
+            false
+        });
+    }
+}
+
+/// Random point of the area on top of static ground that isn't too steep
+fn find_walkable(
+    area: &SpawnArea,
+    spatial_query: &SpatialQuery,
+    bodies: &Query<&RigidBody>,
+    rng: &mut impl Rng,
+) -> Option<Vec3> {
+    for _ in 0..PLACEMENT_ATTEMPTS {
+        let point = sample(area, rng);
+        let origin = Vec3::new(point.x, RAY_HEIGHT, point.y);
+        let Some(hit) = spatial_query.cast_ray(
+            origin,
+            Dir3::NEG_Y,
+            RAY_HEIGHT * 2.0,
+            true,
+            &SpatialQueryFilter::default(),
+        ) else {
+            continue;
+        };
+
+        let is_ground = bodies.get(hit.entity).is_ok_and(RigidBody::is_static);
+        if is_ground && hit.normal.y >= MIN_WALKABLE_NORMAL_Y {
+            let ground = origin + Vec3::NEG_Y * hit.distance;
+            return Some(ground + Vec3::Y * SPAWN_HEIGHT);
+        }
+    }
+
+    None
+}
+
+fn sample(area: &SpawnArea, rng: &mut impl Rng) -> Vec2 {
+    match area {
+        SpawnArea::Rect { center, half_size } => {
+            let x = rng.gen_range(-1.0..=1.0f32) * half_size.x;
+            let z = rng.gen_range(-1.0..=1.0f32) * half_size.y;
+            *center + Vec2::new(x, z)
+        }
+        SpawnArea::Circle { center, radius } => {
+            // sqrt keeps the points uniform instead of crowding the center
+            let distance = *radius * rng.r#gen::<f32>().sqrt();
+            let angle = rng.gen_range(0.0..TAU);
+            *center + Vec2::from_angle(angle) * distance
+        }
+    }
+}
@@ -0,0 +1,177 @@
+//! Threat tables: damage, healing and standing close build up threat on an enemy, which slowly
+//! decays. The highest threat is its target, a rival only pulls aggro by beating the current
+//! target by [`ThreatConfig::switch_ratio`].
+
+use super::*;
+use event::types::{DamageResult, Healed, PositionChangeEvent};
+
+/// Threat this low is forgotten
+const MIN_THREAT: f32 = 0.01;
+
+pub fn plugin(app: &mut App) {
+    app.register_type::<ThreatTable>().add_systems(
+        Update,
+        (
+            threat_from_proximity,
+            threat_from_damage,
+            threat_from_healing,
+            pick_target,
+        )
+            .chain()
+            .before(behaviours::think)
+            .run_if(in_state(Screen::Gameplay)),
+    );
+}
+
+/// Aggressive enemies resent the closest player in their aggro radius
+fn threat_from_proximity(
+    time: Res<Time>,
+    cfg: Res<Config>,
+    mut movement_evt: EventReader<PositionChangeEvent>,
+    mut enemy_query: Query<(&Ai, &AiState, &mut ThreatTable), Without<Dead>>,
+) {
+    let threat = cfg.threat.proximity * time.delta_secs();
+
+    for movement_evt in movement_evt.read() {
+        let Ok((ai, state, mut table)) = enemy_query.get_mut(movement_evt.entity) else {
+            continue;
+        };
+
+        if ai.temper == Temper::Aggressive && *state != AiState::Leash {
+            table.add(movement_evt.target, threat);
+        }
+    }
+}
+
+/// Any enemy resents being hit, passive ones included
+fn threat_from_damage(
+    cfg: Res<Config>,
+    mut damage_results: EventReader<DamageResult>,
+    mut enemy_query: Query<(&AiState, &mut ThreatTable), Without<Dead>>,
+) {
+    for hit in damage_results.read() {
+        let Ok((state, mut table)) = enemy_query.get_mut(hit.target) else {
+            continue;
+        };
+
+        if *state != AiState::Leash {
+            let threat = cfg.threat.hit + cfg.threat.damage * hit.amount;
+            table.add(hit.attacker, threat);
+        }
+    }
+}
+
+/// Healing someone an enemy fights makes the healer a target as well
+fn threat_from_healing(
+    cfg: Res<Config>,
+    mut heals: EventReader<Healed>,
+    mut enemy_query: Query<(&AiState, &mut ThreatTable), Without<Dead>>,
+) {
+    for heal in heals.read() {
+        for (state, mut table) in &mut enemy_query {
+            if *state != AiState::Leash && table.contains(heal.target) {
+                table.add(heal.healer, cfg.threat.heal * heal.amount);
+            }
+        }
+    }
+}
+
+/// Decays every table, forgets the dead and gone, then targets the highest threat
+fn pick_target(
+    time: Res<Time>,
+    cfg: Res<Config>,
+    mut enemy_query: Query<(&mut Enemy, &mut ThreatTable), Without<Dead>>,
+    alive: Query<(), (With<ComputedAttributes>, Without<Dead>)>,
+) {
+    let decay = (-cfg.threat.decay * time.delta_secs()).exp();
+
+    for (mut enemy, mut table) in &mut enemy_query {
+        if !table.0.is_empty() {
+            decay_threat(&mut table, decay, |entity| alive.contains(entity));
+        }
+
+        let target = choose_target(&table, enemy.target_entity, cfg.threat.switch_ratio);
+        if enemy.target_entity != target {
+            enemy.target_entity = target;
+        }
+    }
+}
+
+/// Scales every threat by `decay`, forgetting the faded ones and whoever isn't `alive`
+fn decay_threat(table: &mut ThreatTable, decay: f32, alive: impl Fn(Entity) -> bool) {
+    table.0.retain(|entity, threat| {
+        *threat *= decay;
+        *threat >= MIN_THREAT && alive(*entity)
+    });
+}
+
+/// The highest threat, unless the `current` target still holds its own within `switch_ratio`
+fn choose_target(
+    table: &ThreatTable,
+    current: Option<Entity>,
+    switch_ratio: f32,
+) -> Option<Entity> {
+    let top = table.top();
+    let current = current.filter(|e| table.contains(*e));
+    match (current, top) {
+        (Some(current), Some((_, top_threat)))
+            if table.get(current) * switch_ratio >= top_threat =>
+        {
+            Some(current)
+        }
+        (_, top) => top.map(|(entity, _)| entity),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table(threats: &[(u32, f32)]) -> ThreatTable {
+        ThreatTable(
+            threats
+                .iter()
+                .map(|&(index, threat)| (Entity::from_raw(index), threat))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn highest_threat_without_a_target() {
+        let table = table(&[(1, 5.0), (2, 8.0)]);
+        assert_eq!(choose_target(&table, None, 1.2), Some(Entity::from_raw(2)));
+        assert_eq!(choose_target(&ThreatTable::default(), None, 1.2), None);
+    }
+
+    #[test]
+    fn rival_has_to_beat_the_switch_ratio() {
+        let current = Some(Entity::from_raw(1));
+        // 10 * 1.2 = 12 still holds against 11
+        let table_close = table(&[(1, 10.0), (2, 11.0)]);
+        assert_eq!(choose_target(&table_close, current, 1.2), current);
+        let table_pulled = table(&[(1, 10.0), (2, 12.5)]);
+        assert_eq!(
+            choose_target(&table_pulled, current, 1.2),
+            Some(Entity::from_raw(2))
+        );
+    }
+
+    #[test]
+    fn forgotten_target_is_replaced() {
+        let table = table(&[(2, 1.0)]);
+        let current = Some(Entity::from_raw(1));
+        assert_eq!(
+            choose_target(&table, current, 1.2),
+            Some(Entity::from_raw(2))
+        );
+    }
+
+    #[test]
+    fn decay_drops_faded_and_dead_entries() {
+        let mut table = table(&[(1, 10.0), (2, 0.015), (3, 10.0)]);
+        decay_threat(&mut table, 0.5, |entity| entity != Entity::from_raw(3));
+        assert_eq!(table.get(Entity::from_raw(1)), 5.0);
+        assert!(!table.contains(Entity::from_raw(2)));
+        assert!(!table.contains(Entity::from_raw(3)));
+    }
+}
@@ -1,62 +1,97 @@
 use super::*;
 use event::types::*;
+use spatial::SpatialGrid;
+use std::collections::HashMap;
 
 pub(crate) fn plugin(app: &mut App) {
     app.add_systems(
         Update,
         (
-            proximity_emitter.run_if(transform_changed),
+            // every frame, standing close keeps building threat
+            proximity_emitter,
             movement_emitter,
-            ground_click_emitter,
         ).run_if(in_state(Screen::Gameplay)),
     );
 }
 
+#[cfg(feature = "render")]
+pub(crate) fn render_plugin(app: &mut App) {
+    app.add_systems(
+        Update,
+        ground_click_emitter.run_if(in_state(Screen::Gameplay)),
+    );
+}
+
+/// Every enemy notices the closest player within its aggro radius. Looks around the players
+/// in the [`SpatialGrid`], there are far fewer of them than enemies.
 pub fn proximity_emitter(
-    player_query: Query<(&Transform, Entity), With<Player>>,
-    enemy_query: Query<(&Transform, &Enemy, Entity)>,
+    grid: Res<SpatialGrid>,
+    player_query: Query<Entity, (With<Player>, Without<Dead>)>,
+    enemy_query: Query<&Enemy, Without<Dead>>,
     mut proximity_evt_writer: EventWriter<ProximityEvent>,
 ) {
-    for (enemy_transform, enemy, enemy_entity) in enemy_query.iter() {
-        for (player_transform, player_entity) in player_query.iter() {
-            let dist = enemy_transform
-                .translation
-                .xy()
-                .distance(player_transform.translation.xy());
+    let max_aggro_radius = enemy_query
+        .iter()
+        .map(|enemy| enemy.aggro_radius)
+        .fold(0.0, f32::max);
 
-            if dist <= enemy.aggro_radius {
-                proximity_evt_writer.write(ProximityEvent {
-                    player_entity,
-                    enemy_entity,
-                });
+    let mut closest: HashMap<Entity, (f32, Entity)> = HashMap::new();
+    for player_entity in player_query.iter() {
+        let Some(position) = grid.position(player_entity) else {
+            continue;
+        };
+
+        for (enemy_entity, dist) in grid.within(position, max_aggro_radius) {
+            let Ok(enemy) = enemy_query.get(enemy_entity) else {
+                continue;
+            };
+            if dist > enemy.aggro_radius {
+                continue;
             }
+
+            closest
+                .entry(enemy_entity)
+                .and_modify(|nearest| {
+                    if dist < nearest.0 {
+                        *nearest = (dist, player_entity);
+                    }
+                })
+                .or_insert((dist, player_entity));
         }
     }
+
+    for (enemy_entity, (_, player_entity)) in closest {
+        proximity_evt_writer.write(ProximityEvent {
+            player_entity,
+            enemy_entity,
+        });
+    }
 }
 
 fn movement_emitter(
     mut proximity_evt_reader: EventReader<ProximityEvent>,
     mut movement_event_writer: EventWriter<PositionChangeEvent>,
 ) {
-    let Some(proximity_evt) = proximity_evt_reader.read().next() else {
-        return;
-    };
-
-    let move_evt = PositionChangeEvent::new(proximity_evt.enemy_entity, proximity_evt.player_entity);
-    movement_event_writer.write(move_evt);
+    for proximity_evt in proximity_evt_reader.read() {
+        let move_evt =
+            PositionChangeEvent::new(proximity_evt.enemy_entity, proximity_evt.player_entity);
+        movement_event_writer.write(move_evt);
+    }
 }
 
-fn transform_changed(
-    player_query: Query<(), (With<Player>, Changed<GlobalTransform>)>,
-    enemy_query: Query<(), (With<Enemy>, Changed<GlobalTransform>)>,
-) -> bool {
-    !player_query.is_empty() || !enemy_query.is_empty()
-}
+/// Clicks on the ground this close to an enemy count as clicking the enemy
+#[cfg(feature = "render")]
+const CLICK_SNAP_RADIUS: f32 = 1.0;
 
+#[cfg(feature = "render")]
 pub fn ground_click_emitter(
     mut click_events: EventReader<Pointer<Click>>,
     ground_query: Query<&Transform, With<Ground>>,
+    grid: Res<SpatialGrid>,
+    enemy_query: Query<(), (With<Enemy>, Without<Dead>)>,
+    player_query: Query<&Player, With<LocalPlayer>>,
     mut ground_click_events: EventWriter<GroundClickEvent>,
+    mut enemy_click_events: EventWriter<EnemyClickEvent>,
 ) {
     for click in click_events.read() {
         if let Ok(transform) = ground_query.get(click.target) {
@@ -64,6 +99,18 @@ pub fn ground_click_emitter(
                 continue;
             };
 
+            // small monsters are easy to miss by a hair
+            let near_enemy = grid.nearest(click_position.xz(), CLICK_SNAP_RADIUS, |e| {
+                enemy_query.contains(e)
+            });
+            if let (Some((target, _)), Ok(player)) = (near_enemy, player_query.single()) {
+                enemy_click_events.write(EnemyClickEvent {
+                    target,
+                    player: player.id,
+                });
+                continue;
+            }
+
             let surface_y = transform.translation.y + (transform.scale.y / 2.0);
             let position = Vec3::new(click_position.x, surface_y, click_position.z);
 
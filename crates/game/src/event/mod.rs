@@ -12,4 +12,10 @@ pub fn plugin(app: &mut App) {
             types::plugin
         )
     );
-}
\ No newline at end of file
+}
+
+/// Events coming from clicks in the 3D scene
+#[cfg(feature = "render")]
+pub fn render_plugin(app: &mut App) {
+    app.add_plugins((emitters::render_plugin, triggers::render_plugin));
+}
@@ -6,17 +6,23 @@ pub fn plugin(app: &mut App) {
         .add_observer(player_spawned);
 }
 
+#[cfg(feature = "render")]
+pub fn render_plugin(app: &mut App) {
+    app.add_observer(observe_enemy_clicks);
+}
+
+#[cfg(feature = "render")]
 fn enemy_click_emitter(
     click_event: Trigger<Pointer<Click>>,
     enemy_query: Query<&Enemy>,
-    player_query: Query<&Player>,
+    player_query: Query<&Player, With<LocalPlayer>>,
     mut enemy_click_events: EventWriter<EnemyClickEvent>,
 ) {
     let target_entity = click_event.target();
 
-    // fixme: this will break when there are multiple players on screen
+    // clicks always come from whoever plays on this client
     let Ok(player_entity) = player_query.single() else {
-        warn!("No player found");
+        warn!("No local player found");
         return;
     };
 
@@ -30,23 +36,32 @@ fn enemy_click_emitter(
     }
 }
 
-fn enemy_spawned(on: Trigger<OnAdd, Enemy>, mut enemies: Query<&mut Enemy>, mut cmd: Commands) {
+fn enemy_spawned(on: Trigger<OnAdd, Enemy>, mut enemies: Query<&mut Enemy>) {
     let enemy = on.target();
     if let Ok(mut e) = enemies.get_mut(enemy) {
         e.id = enemy;
     }
+}
 
-    cmd.entity(enemy).observe(enemy_click_emitter);
+#[cfg(feature = "render")]
+fn observe_enemy_clicks(on: Trigger<OnAdd, Enemy>, mut cmd: Commands) {
+    cmd.entity(on.target()).observe(enemy_click_emitter);
 }
 
 fn player_spawned(
     on: Trigger<OnAdd, Player>,
-    mut players: Query<&mut Player>,
+    mut players: Query<(&mut Player, Has<LocalPlayer>)>,
     mut commands: Commands,
 ) {
     let player = on.target();
-    if let Ok(mut p) = players.get_mut(player) {
-        p.id = player;
+    let Ok((mut p, local)) = players.get_mut(player) else {
+        return;
+    };
+    p.id = player;
+
+    // other players are driven by the server, not by our input
+    if !local {
+        return;
     }
     commands.trigger(SwitchInputCtx::new(player, Context::Gameplay));
     commands.trigger(SwitchInputCtx::from_context(Context::Gameplay));
@@ -1,11 +1,36 @@
 use super::*;
+use combat::HitOutcome;
 
 pub(crate) fn plugin(app: &mut App) {
     app.add_event::<EnemyClickEvent>();
     app.add_event::<AttackEvent>();
+    app.add_event::<DamageResult>();
+    app.add_event::<Knockback>();
+    app.add_event::<Healed>();
+    app.add_event::<Died>();
+    app.add_event::<RespawnRequest>();
+    app.add_event::<ExpPenalty>();
+    app.add_event::<LeveledUp>();
+    app.add_event::<RaiseStats>();
+    app.add_event::<ChangeJob>();
+    app.add_event::<JobChanged>();
     app.add_event::<ProximityEvent>();
     app.add_event::<PositionChangeEvent>();
     app.add_event::<GroundClickEvent>();
+    app.add_event::<HotbarPressed>();
+    app.add_event::<CastRequest>();
+    app.add_event::<CastInterrupted>();
+    app.add_event::<SkillCast>();
+    app.add_event::<LearnSkill>();
+    app.add_event::<ApplyStatus>();
+    app.add_event::<StatusEnded>();
+    app.add_event::<AddItem>();
+    app.add_event::<RemoveItem>();
+    app.add_event::<UseItem>();
+    app.add_event::<SortInventory>();
+    app.add_event::<ItemAdded>();
+    app.add_event::<ItemRemoved>();
+    app.add_event::<ItemUsed>();
 }
 
 #[derive(Event)]
@@ -20,6 +45,84 @@ pub struct AttackEvent {
     pub target: Entity,
 }
 
+/// Outcome of a resolved attack, for damage numbers, hit sounds and logs
+#[derive(Event, Debug, Copy, Clone)]
+pub struct DamageResult {
+    pub attacker: Entity,
+    pub target: Entity,
+    pub kind: DamageKind,
+    pub outcome: HitOutcome,
+    /// 0 on a miss
+    pub amount: f32,
+}
+
+/// Shoves `target` away from `source` at `force` units/s, from heavy hits and skills
+#[derive(Event, Debug, Copy, Clone)]
+pub struct Knockback {
+    pub target: Entity,
+    pub source: Entity,
+    pub force: f32,
+}
+
+/// Hp restored by a skill or item, enemies fighting the target resent the healer
+#[derive(Event, Debug, Copy, Clone)]
+pub struct Healed {
+    pub healer: Entity,
+    pub target: Entity,
+    pub amount: f32,
+}
+
+#[derive(Event, Debug, Copy, Clone)]
+pub struct Died {
+    pub entity: Entity,
+    pub killer: Entity,
+}
+
+/// A dead player asks to come back at its [`SavePoint`]
+#[derive(Event, Debug, Copy, Clone)]
+pub struct RespawnRequest {
+    pub entity: Entity,
+}
+
+/// Experience lost by a player coming back from the dead, see `RespawnConfig::exp_penalty`
+#[derive(Event, Debug, Copy, Clone)]
+pub struct ExpPenalty {
+    pub entity: Entity,
+    /// Fraction of the experience needed for the next level
+    pub fraction: f32,
+}
+
+/// A player reached `level`, one event per level gained
+#[derive(Event, Debug, Copy, Clone)]
+pub struct LeveledUp {
+    pub entity: Entity,
+    pub kind: ExpKind,
+    pub level: u32,
+}
+
+/// A player spends stat points on its base attributes, all of them or none
+#[derive(Event, Debug, Clone)]
+pub struct RaiseStats {
+    pub entity: Entity,
+    /// Attribute and how many points to raise it by
+    pub raises: Vec<(Stat, u32)>,
+}
+
+/// A player asks to change into `job`, from the skill tree window or the dev tools
+#[derive(Event, Debug, Copy, Clone)]
+pub struct ChangeJob {
+    pub entity: Entity,
+    pub job: Job,
+    /// Skips the job and job level requirements, for the dev tools
+    pub forced: bool,
+}
+
+#[derive(Event, Debug, Copy, Clone)]
+pub struct JobChanged {
+    pub entity: Entity,
+    pub job: Job,
+}
+
 #[derive(Event)]
 pub struct ProximityEvent {
     pub player_entity: Entity,
@@ -41,4 +144,111 @@ impl PositionChangeEvent {
 #[derive(Event)]
 pub struct GroundClickEvent {
     pub position: Vec3,
-}
\ No newline at end of file
+}
+
+/// A hotbar slot used by key or by click, `entity` is the player owning the hotbar
+#[derive(Event, Debug, Copy, Clone)]
+pub struct HotbarPressed {
+    pub entity: Entity,
+    pub slot: usize,
+}
+
+/// Asks to start casting a skill, dropped when the caster can't use it right now
+#[derive(Event, Debug, Copy, Clone)]
+pub struct CastRequest {
+    pub caster: Entity,
+    pub skill: SkillId,
+    pub target: CastTarget,
+}
+
+/// A cast broken by a hit or by walking away, no mp is spent
+#[derive(Event, Debug, Copy, Clone)]
+pub struct CastInterrupted {
+    pub caster: Entity,
+    pub skill: SkillId,
+}
+
+/// A skill went off, its effects are applied already
+#[derive(Event, Debug, Copy, Clone)]
+pub struct SkillCast {
+    pub caster: Entity,
+    pub skill: SkillId,
+    pub target: CastTarget,
+}
+
+/// A player spends a skill point on the next level of a skill of its skill tree
+#[derive(Event, Debug, Copy, Clone)]
+pub struct LearnSkill {
+    pub entity: Entity,
+    pub skill: SkillId,
+}
+
+/// Asks to put a status on `target`, dropped when it is immune or the `chance` roll fails
+#[derive(Event, Debug, Copy, Clone)]
+pub struct ApplyStatus {
+    pub target: Entity,
+    pub status: StatusId,
+    pub source: Entity,
+    /// 0..1
+    pub chance: f32,
+    /// Overrides the duration of the status
+    pub duration: Option<f32>,
+}
+
+/// A status ran out, broke on a hit or went away with its bearer's death
+#[derive(Event, Debug, Copy, Clone)]
+pub struct StatusEnded {
+    pub entity: Entity,
+    pub status: StatusId,
+}
+
+/// Asks to put items in an inventory, dropped when they don't fit or weigh too much
+#[derive(Event, Debug, Copy, Clone)]
+pub struct AddItem {
+    pub entity: Entity,
+    pub item: ItemId,
+    pub amount: u32,
+}
+
+/// Asks to take items out of an inventory, dropped when there aren't enough
+#[derive(Event, Debug, Copy, Clone)]
+pub struct RemoveItem {
+    pub entity: Entity,
+    pub item: ItemId,
+    pub amount: u32,
+}
+
+/// Asks to use up one usable item from an inventory
+#[derive(Event, Debug, Copy, Clone)]
+pub struct UseItem {
+    pub entity: Entity,
+    pub item: ItemId,
+}
+
+#[derive(Event, Debug, Copy, Clone)]
+pub struct SortInventory {
+    pub entity: Entity,
+}
+
+/// Items went into an inventory
+#[derive(Event, Debug, Copy, Clone)]
+pub struct ItemAdded {
+    pub entity: Entity,
+    pub item: ItemId,
+    pub amount: u32,
+}
+
+/// Items left an inventory, removed or used up
+#[derive(Event, Debug, Copy, Clone)]
+pub struct ItemRemoved {
+    pub entity: Entity,
+    pub item: ItemId,
+    pub amount: u32,
+}
+
+/// An item was used, its effects are applied already
+#[derive(Event, Debug, Copy, Clone)]
+pub struct ItemUsed {
+    pub entity: Entity,
+    pub item: ItemId,
+}
@@ -0,0 +1,285 @@
+//! Inventories: items from the [`ItemDb`] go in and out of an [`Inventory`] through [`AddItem`],
+//! [`RemoveItem`] and [`UseItem`], the outcomes are sent as [`ItemAdded`], [`ItemRemoved`] and
+//! [`ItemUsed`]. Nothing goes past the weight limit, and the heavier the load the worse the
+//! [`Burden`]. Killers get the drops of the monster straight into their inventory.
+
+use super::*;
+use combat::CombatRng;
+use event::types::{
+    AddItem, ApplyStatus, Died, Healed, ItemAdded, ItemRemoved, ItemUsed, RemoveItem,
+    SortInventory, UseItem,
+};
+use rand::Rng;
+use thiserror::Error;
+
+pub fn plugin(app: &mut App) {
+    app.register_type::<Inventory>()
+        .register_type::<Burden>()
+        .add_systems(
+            Update,
+            (
+                loot_drops,
+                add_items,
+                remove_items,
+                use_items,
+                sort_inventories,
+                update_burden,
+            )
+                .chain()
+                .before(stats::derive_attributes)
+                .run_if(in_state(Screen::Gameplay).and(resource_exists::<ItemDb>)),
+        );
+}
+
+#[derive(Debug, Error)]
+enum ItemError {
+    #[error("item {0} isn't in the item database")]
+    Unknown(u32),
+    #[error("no free slot")]
+    NoRoom,
+    #[error("weighs {weight}, only {left} left")]
+    TooHeavy { weight: u32, left: f32 },
+    #[error("needs {needed}, has {has}")]
+    NotEnough { needed: u32, has: u32 },
+    #[error("{0} can't be used")]
+    NotUsable(String),
+}
+
+fn check_add<'a>(
+    item_db: &'a ItemDb,
+    inventory: &Inventory,
+    weight_limit: f32,
+    request: &AddItem,
+) -> Result<&'a ItemDef, ItemError> {
+    let def = item_db
+        .get(request.item)
+        .ok_or(ItemError::Unknown(*request.item))?;
+    if !inventory.fits(request.item, request.amount, def.stack) {
+        return Err(ItemError::NoRoom);
+    }
+
+    let weight = def.weight.saturating_mul(request.amount);
+    let left = weight_limit - inventory.weight(item_db) as f32;
+    if weight as f32 > left {
+        return Err(ItemError::TooHeavy { weight, left });
+    }
+    Ok(def)
+}
+
+/// Every drop of a killed monster is rolled on its own
+fn loot_drops(
+    monster_db: Option<Res<MonsterDb>>,
+    rng: Option<ResMut<CombatRng>>,
+    mut deaths: EventReader<Died>,
+    monsters: Query<&MonsterId>,
+    killers: Query<(), With<Inventory>>,
+    mut add: EventWriter<AddItem>,
+) {
+    let (Some(monster_db), Some(mut rng)) = (monster_db, rng) else {
+        return;
+    };
+
+    for death in deaths.read() {
+        if !killers.contains(death.killer) {
+            continue;
+        }
+        let Some(def) = monsters
+            .get(death.entity)
+            .ok()
+            .and_then(|id| monster_db.get(*id))
+        else {
+            continue;
+        };
+
+        for drop in &def.drops {
+            if rng.0.r#gen::<f32>() < drop.chance {
+                add.write(AddItem {
+                    entity: death.killer,
+                    item: ItemId(drop.item),
+                    amount: 1,
+                });
+            }
+        }
+    }
+}
+
+fn add_items(
+    item_db: Res<ItemDb>,
+    mut requests: EventReader<AddItem>,
+    mut holders: Query<(&mut Inventory, &ComputedAttributes)>,
+    mut added: EventWriter<ItemAdded>,
+) {
+    for request in requests.read() {
+        let Ok((mut inventory, attribs)) = holders.get_mut(request.entity) else {
+            continue;
+        };
+
+        match check_add(&item_db, &inventory, attribs.weight_limit, request) {
+            Ok(def) => {
+                inventory.add(request.item, request.amount, def.stack);
+                added.write(ItemAdded {
+                    entity: request.entity,
+                    item: request.item,
+                    amount: request.amount,
+                });
+                info!("{} got {} {}", request.entity, request.amount, def.name);
+            }
+            Err(e) => warn!("{} can't take item {}: {e}", request.entity, *request.item),
+        }
+    }
+}
+
+fn remove_items(
+    mut requests: EventReader<RemoveItem>,
+    mut holders: Query<&mut Inventory>,
+    mut removed: EventWriter<ItemRemoved>,
+) {
+    for request in requests.read() {
+        let Ok(mut inventory) = holders.get_mut(request.entity) else {
+            continue;
+        };
+
+        if inventory.remove(request.item, request.amount) {
+            removed.write(ItemRemoved {
+                entity: request.entity,
+                item: request.item,
+                amount: request.amount,
+            });
+        } else {
+            let e = ItemError::NotEnough {
+                needed: request.amount,
+                has: inventory.count(request.item),
+            };
+            warn!("{} can't drop item {}: {e}", request.entity, *request.item);
+        }
+    }
+}
+
+fn check_use<'a>(
+    item_db: &'a ItemDb,
+    inventory: &Inventory,
+    request: &UseItem,
+) -> Result<&'a ItemDef, ItemError> {
+    let def = item_db
+        .get(request.item)
+        .ok_or(ItemError::Unknown(*request.item))?;
+    if def.kind != ItemKind::Usable {
+        return Err(ItemError::NotUsable(def.name.clone()));
+    }
+    let has = inventory.count(request.item);
+    if has == 0 {
+        return Err(ItemError::NotEnough { needed: 1, has });
+    }
+    Ok(def)
+}
+
+/// The dead can't use items
+fn use_items(
+    item_db: Res<ItemDb>,
+    mut requests: EventReader<UseItem>,
+    mut holders: Query<(&mut Inventory, &mut ComputedAttributes), Without<Dead>>,
+    mut heals: EventWriter<Healed>,
+    mut statuses: EventWriter<ApplyStatus>,
+    mut removed: EventWriter<ItemRemoved>,
+    mut used: EventWriter<ItemUsed>,
+) {
+    for request in requests.read() {
+        let Ok((mut inventory, mut attribs)) = holders.get_mut(request.entity) else {
+            continue;
+        };
+        let def = match check_use(&item_db, &inventory, request) {
+            Ok(def) => def,
+            Err(e) => {
+                warn!("{} can't use item {}: {e}", request.entity, *request.item);
+                continue;
+            }
+        };
+
+        for effect in &def.effects {
+            match *effect {
+                ItemEffect::Heal { hp, mp } => {
+                    let health = &mut attribs.health;
+                    let amount = hp.min(health.max_hp - health.hp).max(0.0);
+                    health.hp += amount;
+                    let mana = &mut attribs.mana;
+                    mana.mp = (mana.mp + mp).min(mana.max_mp);
+                    if amount > 0.0 {
+                        heals.write(Healed {
+                            healer: request.entity,
+                            target: request.entity,
+                            amount,
+                        });
+                    }
+                }
+                ItemEffect::Status { status, duration } => {
+                    statuses.write(ApplyStatus {
+                        target: request.entity,
+                        status: StatusId(status),
+                        source: request.entity,
+                        chance: 1.0,
+                        duration,
+                    });
+                }
+            }
+        }
+
+        inventory.remove(request.item, 1);
+        removed.write(ItemRemoved {
+            entity: request.entity,
+            item: request.item,
+            amount: 1,
+        });
+        used.write(ItemUsed {
+            entity: request.entity,
+            item: request.item,
+        });
+        info!("{} used {}", request.entity, def.name);
+    }
+}
+
+fn sort_inventories(
+    item_db: Res<ItemDb>,
+    mut requests: EventReader<SortInventory>,
+    mut holders: Query<&mut Inventory>,
+) {
+    for request in requests.read() {
+        if let Ok(mut inventory) = holders.get_mut(request.entity) {
+            inventory.sort(&item_db);
+        }
+    }
+}
+
+/// Heavy and overweight characters are slowed down, `status` locks out the overweight ones
+fn update_burden(
+    cfg: Res<Config>,
+    item_db: Res<ItemDb>,
+    mut holders: Query<(
+        Entity,
+        Ref<Inventory>,
+        Ref<ComputedAttributes>,
+        &mut Burden,
+        &mut StatModifiers,
+    )>,
+) {
+    // a hot reloaded database touches everyone
+    let reloaded = item_db.is_changed();
+
+    for (entity, inventory, attribs, mut burden, mut modifiers) in &mut holders {
+        if !reloaded && !inventory.is_changed() && !attribs.is_changed() {
+            continue;
+        }
+
+        let weight = inventory.weight(&item_db);
+        let new = Burden::of(weight, attribs.weight_limit, &cfg.inventory);
+        if !burden.set_if_neq(new) {
+            continue;
+        }
+
+        modifiers.remove_source(Burden::SOURCE);
+        if new != Burden::Light {
+            let slow = cfg.inventory.move_speed;
+            modifiers.add(StatModifier::percent(Burden::SOURCE, Stat::MoveSpeed, slow));
+        }
+        info!("{entity} is {new:?} carrying {weight}");
+    }
+}
@@ -0,0 +1,228 @@
+//! Jobs: the [`Job`] of a player adds max hp and mp per base level and attribute bonuses per job
+//! level to its [`StatModifiers`], caps its job level and sets what it can equip and learn, as
+//! found in the [`JobDb`]. Jobs are changed through [`ChangeJob`].
+
+use super::*;
+use event::types::{ChangeJob, JobChanged};
+use thiserror::Error;
+
+pub fn plugin(app: &mut App) {
+    app.register_type::<Job>().add_systems(
+        Update,
+        (change_job, apply_job_modifiers, unequip_weapons)
+            .chain()
+            .before(stats::derive_attributes)
+            .run_if(in_state(Screen::Gameplay).and(resource_exists::<JobDb>)),
+    );
+}
+
+#[derive(Debug, Error)]
+enum JobChangeError {
+    #[error("{0:?} isn't in the job database")]
+    Unknown(Job),
+    #[error("a {0:?} can't change into it")]
+    NotFrom(Job),
+    #[error("needs job level {needed}, has {has}")]
+    JobLevel { needed: u32, has: u32 },
+}
+
+fn check_change(
+    job_db: &JobDb,
+    job: Job,
+    job_level: u32,
+    request: &ChangeJob,
+) -> Result<(), JobChangeError> {
+    let def = job_db
+        .get(request.job)
+        .ok_or(JobChangeError::Unknown(request.job))?;
+    if request.forced {
+        return Ok(());
+    }
+
+    let change = def
+        .change
+        .filter(|change| change.from == job)
+        .ok_or(JobChangeError::NotFrom(job))?;
+    if job_level < change.job_level {
+        return Err(JobChangeError::JobLevel {
+            needed: change.job_level,
+            has: job_level,
+        });
+    }
+    Ok(())
+}
+
+/// A new job starts over at job level 1, skill points left are kept
+fn change_job(
+    job_db: Res<JobDb>,
+    mut requests: EventReader<ChangeJob>,
+    mut players: Query<(&mut Job, &mut JobLevel, &mut Experience)>,
+    mut job_changed: EventWriter<JobChanged>,
+) {
+    for request in requests.read() {
+        let Ok((mut job, mut job_level, mut exp)) = players.get_mut(request.entity) else {
+            continue;
+        };
+        if let Err(e) = check_change(&job_db, *job, **job_level, request) {
+            warn!(
+                "{} can't change into {:?}: {e}",
+                request.entity, request.job
+            );
+            continue;
+        }
+
+        *job = request.job;
+        **job_level = 1;
+        exp.job = 0;
+        job_changed.write(JobChanged {
+            entity: request.entity,
+            job: request.job,
+        });
+        info!("{} is a {:?} now", request.entity, request.job);
+    }
+}
+
+fn apply_job_modifiers(
+    job_db: Res<JobDb>,
+    mut players: Query<(Ref<Job>, Ref<Level>, Ref<JobLevel>, &mut StatModifiers)>,
+) {
+    // a hot reloaded database touches everyone
+    let reloaded = job_db.is_changed();
+
+    for (job, level, job_level, mut modifiers) in &mut players {
+        if !reloaded && !job.is_changed() && !level.is_changed() && !job_level.is_changed() {
+            continue;
+        }
+        let Some(def) = job_db.get(*job) else {
+            continue;
+        };
+
+        modifiers.remove_source(Job::SOURCE);
+        for modifier in def.modifiers(**level, **job_level) {
+            modifiers.add(modifier);
+        }
+    }
+}
+
+/// The weapon a character of `job` keeps holding, bare hands when the job can't equip it
+fn usable_weapon(job_db: &JobDb, job: Job, weapon: WeaponType) -> WeaponType {
+    match job_db.get(job) {
+        Some(def) if !def.can_equip(weapon) => WeaponType::Fist,
+        _ => weapon,
+    }
+}
+
+/// Puts away weapons the job can't use, after a job change or when one gets equipped
+fn unequip_weapons(job_db: Res<JobDb>, mut players: Query<(Entity, Ref<Job>, &mut WeaponType)>) {
+    let reloaded = job_db.is_changed();
+
+    for (entity, job, mut weapon) in &mut players {
+        if !reloaded && !job.is_changed() && !weapon.is_changed() {
+            continue;
+        }
+        let usable = usable_weapon(&job_db, *job, *weapon);
+        if usable != *weapon {
+            info!("{entity} can't equip a {:?} as a {:?}", *weapon, *job);
+            *weapon = usable;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn job(name: &str, change: Option<(Job, u32)>) -> JobDef {
+        JobDef {
+            name: name.into(),
+            change: change.map(|(from, job_level)| JobChange { from, job_level }),
+            max_job_level: 50,
+            hp_per_level: 0.0,
+            mp_per_level: 0.0,
+            bonuses: vec![],
+            weapons: vec![],
+            skills: vec![],
+        }
+    }
+
+    fn job_db() -> JobDb {
+        JobDb {
+            jobs: HashMap::from([
+                (Job::Novice, job("Novice", None)),
+                (Job::Swordsman, job("Swordsman", Some((Job::Novice, 10)))),
+                (Job::Knight, job("Knight", Some((Job::Swordsman, 40)))),
+            ]),
+        }
+    }
+
+    fn request(job: Job, forced: bool) -> ChangeJob {
+        ChangeJob {
+            entity: Entity::PLACEHOLDER,
+            job,
+            forced,
+        }
+    }
+
+    #[test]
+    fn change_at_the_job_level() {
+        let job_db = job_db();
+        let swordsman = request(Job::Swordsman, false);
+        assert!(check_change(&job_db, Job::Novice, 10, &swordsman).is_ok());
+        let knight = request(Job::Knight, false);
+        assert!(check_change(&job_db, Job::Swordsman, 45, &knight).is_ok());
+    }
+
+    #[test]
+    fn job_level_too_low() {
+        let result = check_change(&job_db(), Job::Novice, 9, &request(Job::Swordsman, false));
+        assert!(matches!(
+            result,
+            Err(JobChangeError::JobLevel { needed: 10, has: 9 })
+        ));
+    }
+
+    #[test]
+    fn only_from_the_previous_job() {
+        let job_db = job_db();
+        // no skipping the first class, no going back
+        let knight = request(Job::Knight, false);
+        assert!(matches!(
+            check_change(&job_db, Job::Novice, 10, &knight),
+            Err(JobChangeError::NotFrom(Job::Novice))
+        ));
+        let novice = request(Job::Novice, false);
+        assert!(matches!(
+            check_change(&job_db, Job::Swordsman, 50, &novice),
+            Err(JobChangeError::NotFrom(Job::Swordsman))
+        ));
+    }
+
+    #[test]
+    fn unknown_job() {
+        let result = check_change(&job_db(), Job::Novice, 10, &request(Job::Mage, false));
+        assert!(matches!(result, Err(JobChangeError::Unknown(Job::Mage))));
+        let result = check_change(&job_db(), Job::Novice, 10, &request(Job::Mage, true));
+        assert!(matches!(result, Err(JobChangeError::Unknown(Job::Mage))));
+    }
+
+    #[test]
+    fn forced_skips_the_requirements() {
+        let job_db = job_db();
+        let knight = request(Job::Knight, true);
+        assert!(check_change(&job_db, Job::Novice, 1, &knight).is_ok());
+    }
+
+    #[test]
+    fn only_weapons_of_the_job() {
+        let mut job_db = job_db();
+        job_db.jobs.get_mut(&Job::Swordsman).unwrap().weapons = vec![WeaponType::OneHandSword];
+
+        let sword = WeaponType::OneHandSword;
+        assert_eq!(usable_weapon(&job_db, Job::Swordsman, sword), sword);
+        assert_eq!(usable_weapon(&job_db, Job::Novice, sword), WeaponType::Fist);
+        // bare hands always work
+        let fist = WeaponType::Fist;
+        assert_eq!(usable_weapon(&job_db, Job::Novice, fist), fist);
+    }
+}
@@ -1,40 +1,89 @@
 #![feature(duration_millis_float)]
 
+#[cfg(feature = "render")]
 use asset_loading::*;
+#[cfg(feature = "render")]
 use audio::*;
 use avian3d::prelude::*;
 use bevy::prelude::*;
+#[cfg(feature = "render")]
 use bevy_enhanced_input::prelude::*;
+#[cfg(feature = "render")]
 use bevy_seedling::prelude::*;
 use bevy_tnua::prelude::*;
+use bevy_tnua_avian3d::TnuaAvian3dPlugin;
 use models::*;
+use net::*;
+#[cfg(feature = "render")]
 use scene::*;
+#[cfg(feature = "render")]
 use camera::*;
 use event::*;
 
+#[cfg(feature = "render")]
 pub mod camera;
 #[cfg(feature = "dev_native")]
 pub mod dev_tools;
+#[cfg(feature = "render")]
 pub mod player;
 pub mod enemy;
+pub mod character;
+#[cfg(feature = "render")]
 pub mod sound;
 pub mod combat;
+pub mod stats;
+pub mod spatial;
+pub mod navigation;
+pub mod skills;
+pub mod status;
+pub mod progression;
+pub mod job;
+pub mod inventory;
 pub mod utils;
 pub mod event;
+#[cfg(feature = "render")]
+pub mod network;
 
+/// The whole game as the client runs it: the simulation plus camera, player control,
+/// models, sound and the network glue
+#[cfg(feature = "render")]
 pub fn plugin(app: &mut App) {
     app.add_plugins((
         models::plugin,
+        simulation_plugin,
         camera::plugin,
         scene::plugin,
         player::plugin,
-        enemy::plugin,
-        combat::plugin,
+        enemy::render_plugin,
+        progression::render_plugin,
         sound::plugin,
         #[cfg(feature = "dev_native")]
         dev_tools::plugin,
-        event::plugin,
+        event::render_plugin,
+        network::plugin,
 
         MeshPickingPlugin
     ));
-}
\ No newline at end of file
+}
+
+/// Everything that runs without a window: physics, character controllers, enemy AI and combat.
+/// Shared by the client and the dedicated server.
+pub fn simulation_plugin(app: &mut App) {
+    app.add_plugins((
+        PhysicsPlugins::default(),
+        TnuaControllerPlugin::new(FixedUpdate),
+        TnuaAvian3dPlugin::new(FixedUpdate),
+        enemy::plugin,
+        character::plugin,
+        combat::plugin,
+        stats::plugin,
+        spatial::plugin,
+        navigation::plugin,
+        skills::plugin,
+        status::plugin,
+        progression::plugin,
+        job::plugin,
+        inventory::plugin,
+        event::plugin,
+    ));
+}
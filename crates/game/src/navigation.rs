@@ -0,0 +1,493 @@
+//! Navigation: a walkable grid baked from the static colliders by casting rays down on every
+//! cell, A* over it and a string-pulled path of corners to walk. Click-to-move and enemy chases
+//! steer through [`steer`], which searches again when the goal moves away.
+
+use super::*;
+use std::{
+    cmp::Ordering,
+    collections::{BinaryHeap, HashSet},
+    f32::consts::SQRT_2,
+};
+
+/// Side of a cell in world units
+const CELL_SIZE: f32 = 1.0;
+/// Rays are cast down from this height to find the ground
+const RAY_HEIGHT: f32 = 100.0;
+/// Steeper ground isn't walkable
+const MIN_WALKABLE_NORMAL_Y: f32 = 0.7;
+/// Highest ledge a character walks up or down
+const MAX_STEP: f32 = 0.5;
+/// Statics added within this many seconds are baked together
+const BAKE_DELAY: f32 = 0.25;
+/// A goal moving farther than this from the end of the path asks for a new one
+const REPATH_DISTANCE: f32 = 1.0;
+/// Seconds between two searches of one walker
+const REPATH_INTERVAL: f32 = 0.25;
+/// Close enough to a corner to head for the next one
+const WAYPOINT_RADIUS: f32 = 0.5;
+/// How many cells around a blocked start or goal are searched for a walkable one
+const SNAP_CELLS: i32 = 4;
+
+pub fn plugin(app: &mut App) {
+    app.register_type::<NavPath>()
+        .init_resource::<NavMesh>()
+        .add_systems(Update, (watch_statics, bake_navmesh).chain());
+}
+
+/// Walkable cells of the ground plane, empty until the first bake
+#[derive(Resource, Debug, Default)]
+pub struct NavMesh {
+    /// World XZ of the corner of the first cell
+    origin: Vec2,
+    size: IVec2,
+    /// Ground height of every cell a character fits on
+    cells: Vec<Option<f32>>,
+    /// Counts down to the next bake after the statics changed
+    bake: Option<Timer>,
+    /// Static bodies the ground is baked from, only these going away asks for a new bake
+    statics: HashSet<Entity>,
+}
+
+impl NavMesh {
+    pub fn is_baked(&self) -> bool {
+        !self.cells.is_empty()
+    }
+
+    fn cell(&self, position: Vec3) -> IVec2 {
+        ((position.xz() - self.origin) / CELL_SIZE)
+            .floor()
+            .as_ivec2()
+    }
+
+    fn center(&self, cell: IVec2) -> Vec2 {
+        self.origin + (cell.as_vec2() + 0.5) * CELL_SIZE
+    }
+
+    fn index(&self, cell: IVec2) -> Option<usize> {
+        let inside = cell.x >= 0 && cell.y >= 0 && cell.x < self.size.x && cell.y < self.size.y;
+        inside.then(|| (cell.y * self.size.x + cell.x) as usize)
+    }
+
+    fn height(&self, cell: IVec2) -> Option<f32> {
+        self.index(cell).and_then(|i| self.cells[i])
+    }
+
+    /// Both cells are walkable and the ledge between them is low enough
+    fn can_step(&self, from: IVec2, to: IVec2) -> bool {
+        match (self.height(from), self.height(to)) {
+            (Some(a), Some(b)) => (a - b).abs() <= MAX_STEP,
+            _ => false,
+        }
+    }
+
+    /// A step to a neighbouring cell, diagonals only when both cells they cut past can be
+    /// stepped on as well so nothing cuts corners past a blocked cell
+    fn can_move(&self, from: IVec2, to: IVec2) -> bool {
+        let offset = to - from;
+        self.can_step(from, to)
+            && (offset.x == 0
+                || offset.y == 0
+                || (self.can_step(from, from + IVec2::new(offset.x, 0))
+                    && self.can_step(from, from + IVec2::new(0, offset.y))))
+    }
+
+    /// Whether the cell under `position` has ground a character fits on
+    pub fn is_walkable(&self, position: Vec3) -> bool {
+        self.height(self.cell(position)).is_some()
+    }
+
+    /// The walkable cell closest to `position`
+    fn snap(&self, position: Vec3) -> Option<IVec2> {
+        let cell = self.cell(position);
+        (0..=SNAP_CELLS).find_map(|ring| {
+            (-ring..=ring)
+                .flat_map(|x| (-ring..=ring).map(move |y| cell + IVec2::new(x, y)))
+                .filter(|c| (c.x - cell.x).abs() == ring || (c.y - cell.y).abs() == ring)
+                .filter(|c| self.height(*c).is_some())
+                .min_by(|a, b| {
+                    let da = self.center(*a).distance_squared(position.xz());
+                    let db = self.center(*b).distance_squared(position.xz());
+                    da.total_cmp(&db)
+                })
+        })
+    }
+
+    /// Corners to walk from `from` to `to`, the last one is `to` itself.
+    /// None when either end is off the walkable ground or they aren't connected.
+    pub fn find_path(&self, from: Vec3, to: Vec3) -> Option<Vec<Vec3>> {
+        let start = self.snap(from)?;
+        let goal = self.snap(to)?;
+        let cells = self.astar(start, goal)?;
+
+        // string pulling: skip every corner the next one can be seen from
+        let mut corners = Vec::new();
+        let mut anchor = start;
+        for pair in cells.windows(2) {
+            if !self.line_of_sight(anchor, pair[1]) {
+                corners.push(pair[0]);
+                anchor = pair[0];
+            }
+        }
+
+        let mut path: Vec<Vec3> = corners
+            .into_iter()
+            .map(|cell| {
+                let center = self.center(cell);
+                Vec3::new(center.x, self.height(cell).unwrap_or(to.y), center.y)
+            })
+            .collect();
+        path.push(to);
+        Some(path)
+    }
+
+    fn astar(&self, start: IVec2, goal: IVec2) -> Option<Vec<IVec2>> {
+        const NEIGHBOURS: [IVec2; 8] = [
+            IVec2::new(1, 0),
+            IVec2::new(-1, 0),
+            IVec2::new(0, 1),
+            IVec2::new(0, -1),
+            IVec2::new(1, 1),
+            IVec2::new(1, -1),
+            IVec2::new(-1, 1),
+            IVec2::new(-1, -1),
+        ];
+
+        let start_index = self.index(start)?;
+        let goal_index = self.index(goal)?;
+        let mut cost = vec![f32::INFINITY; self.cells.len()];
+        let mut came_from = vec![usize::MAX; self.cells.len()];
+        let mut closed = vec![false; self.cells.len()];
+        let mut open = BinaryHeap::new();
+
+        cost[start_index] = 0.0;
+        open.push(Open {
+            estimate: octile(start, goal),
+            cell: start,
+        });
+
+        while let Some(Open { cell, .. }) = open.pop() {
+            let index = self.index(cell)?;
+            // reached again through a cheaper route earlier
+            if std::mem::replace(&mut closed[index], true) {
+                continue;
+            }
+            if index == goal_index {
+                let mut cells = vec![goal];
+                let mut current = goal_index;
+                while current != start_index {
+                    current = came_from[current];
+                    let x = current as i32 % self.size.x;
+                    let y = current as i32 / self.size.x;
+                    cells.push(IVec2::new(x, y));
+                }
+                cells.reverse();
+                return Some(cells);
+            }
+
+            for offset in NEIGHBOURS {
+                let next = cell + offset;
+                if !self.can_move(cell, next) {
+                    continue;
+                }
+                let diagonal = offset.x != 0 && offset.y != 0;
+
+                let Some(next_index) = self.index(next) else {
+                    continue;
+                };
+                let step = if diagonal { SQRT_2 } else { 1.0 };
+                let next_cost = cost[index] + step;
+                if next_cost < cost[next_index] {
+                    cost[next_index] = next_cost;
+                    came_from[next_index] = index;
+                    open.push(Open {
+                        estimate: next_cost + octile(next, goal),
+                        cell: next,
+                    });
+                }
+            }
+        }
+
+        None
+    }
+
+    /// Walks the straight line between two cell centers in half cells
+    fn line_of_sight(&self, from: IVec2, to: IVec2) -> bool {
+        let (a, b) = (self.center(from), self.center(to));
+        let samples = (a.distance(b) / (CELL_SIZE * 0.5)).ceil() as i32;
+        let mut previous = from;
+        for i in 1..=samples {
+            let point = a.lerp(b, i as f32 / samples as f32);
+            let cell = ((point - self.origin) / CELL_SIZE).floor().as_ivec2();
+            if cell != previous && !self.can_move(previous, cell) {
+                return false;
+            }
+            previous = cell;
+        }
+        true
+    }
+}
+
+/// Cell waiting in the A* open list, the lowest estimate comes out first
+#[derive(PartialEq)]
+struct Open {
+    estimate: f32,
+    cell: IVec2,
+}
+
+impl Eq for Open {}
+
+impl Ord for Open {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.estimate.total_cmp(&self.estimate)
+    }
+}
+
+impl PartialOrd for Open {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+fn octile(a: IVec2, b: IVec2) -> f32 {
+    let d = (a - b).abs();
+    let (low, high) = (d.x.min(d.y) as f32, d.x.max(d.y) as f32);
+    high + (SQRT_2 - 1.0) * low
+}
+
+/// Where to head next on the way to `goal`. Searches a new path when the goal moved away from
+/// the current one, walks straight at it while there is no navmesh or no path.
+pub fn steer(nav: &NavMesh, path: &mut NavPath, position: Vec3, goal: Vec3, dt: f32) -> Vec3 {
+    path.cooldown -= dt;
+
+    let moved = path
+        .goal
+        .is_none_or(|old| old.xz().distance(goal.xz()) > REPATH_DISTANCE);
+    if moved && path.cooldown <= 0.0 && nav.is_baked() {
+        path.cooldown = REPATH_INTERVAL;
+        path.goal = Some(goal);
+        path.waypoints = nav.find_path(position, goal).unwrap_or_default();
+    }
+
+    while path
+        .waypoints
+        .first()
+        .is_some_and(|next| next.xz().distance(position.xz()) <= WAYPOINT_RADIUS)
+    {
+        path.waypoints.remove(0);
+    }
+
+    path.waypoints.first().copied().unwrap_or(goal)
+}
+
+/// Statics appearing or going away ask for a new bake, characters and corpses don't
+fn watch_statics(
+    mut nav: ResMut<NavMesh>,
+    added: Query<(Entity, &RigidBody), Added<Collider>>,
+    mut removed: RemovedComponents<Collider>,
+) {
+    let mut changed = false;
+    for (entity, _) in added.iter().filter(|(_, body)| body.is_static()) {
+        nav.statics.insert(entity);
+        changed = true;
+    }
+    // reads every removal, so the set doesn't keep entities that are gone
+    for entity in removed.read() {
+        changed |= nav.statics.remove(&entity);
+    }
+
+    if changed {
+        // physics only sees new colliders after its next step
+        nav.bake = Some(Timer::from_seconds(BAKE_DELAY, TimerMode::Once));
+    }
+}
+
+fn bake_navmesh(
+    time: Res<Time>,
+    cfg: Res<Config>,
+    mut nav: ResMut<NavMesh>,
+    spatial_query: SpatialQuery,
+    bodies: Query<&RigidBody>,
+) {
+    let Some(timer) = nav.bake.as_mut() else {
+        return;
+    };
+    if !timer.tick(time.delta()).finished() {
+        return;
+    }
+
+    let extent = cfg.geom.main_plane;
+    let side = (extent / CELL_SIZE).ceil() as i32;
+    let origin = Vec2::splat(-extent / 2.0);
+    let size = IVec2::splat(side);
+
+    // ground height under every cell, characters and enemies don't count
+    let is_static = |entity: Entity| bodies.get(entity).is_ok_and(RigidBody::is_static);
+    let mut ground = Vec::with_capacity((side * side) as usize);
+    for y in 0..side {
+        for x in 0..side {
+            let center = origin + (Vec2::new(x as f32, y as f32) + 0.5) * CELL_SIZE;
+            let ray_origin = Vec3::new(center.x, RAY_HEIGHT, center.y);
+            let hit = spatial_query.cast_ray_predicate(
+                ray_origin,
+                Dir3::NEG_Y,
+                RAY_HEIGHT * 2.0,
+                true,
+                &SpatialQueryFilter::default(),
+                &is_static,
+            );
+            ground.push(
+                hit.filter(|hit| hit.normal.y >= MIN_WALKABLE_NORMAL_Y)
+                    .map(|hit| RAY_HEIGHT - hit.distance),
+            );
+        }
+    }
+
+    // keep a character's radius away from walls, cliffs and the edge of the world
+    let clearance = (cfg.player.hitbox.radius / CELL_SIZE).ceil() as i32;
+    let height = |cell: IVec2| {
+        let inside = cell.x >= 0 && cell.y >= 0 && cell.x < side && cell.y < side;
+        if inside {
+            ground[(cell.y * side + cell.x) as usize]
+        } else {
+            None
+        }
+    };
+    let mut cells = Vec::with_capacity(ground.len());
+    for y in 0..side {
+        for x in 0..side {
+            let cell = IVec2::new(x, y);
+            let fits = height(cell).filter(|h| {
+                (-clearance..=clearance).all(|dy| {
+                    (-clearance..=clearance).all(|dx| {
+                        height(cell + IVec2::new(dx, dy))
+                            .is_some_and(|other| (other - h).abs() <= MAX_STEP)
+                    })
+                })
+            });
+            cells.push(fits);
+        }
+    }
+
+    let walkable = cells.iter().filter(|c| c.is_some()).count();
+    info!(
+        "navmesh baked: {walkable} of {} cells walkable",
+        cells.len()
+    );
+
+    nav.origin = origin;
+    nav.size = size;
+    nav.cells = cells;
+    nav.bake = None;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A flat navmesh from rows of `.` for walkable and `#` for blocked cells, the first row
+    /// at z 0
+    fn grid(rows: &[&str]) -> NavMesh {
+        let cells = rows
+            .iter()
+            .flat_map(|row| row.chars().map(|c| (c == '.').then_some(0.0)))
+            .collect();
+        NavMesh {
+            origin: Vec2::ZERO,
+            size: IVec2::new(rows[0].len() as i32, rows.len() as i32),
+            cells,
+            ..default()
+        }
+    }
+
+    fn at(x: i32, y: i32) -> Vec3 {
+        Vec3::new(x as f32 + 0.5, 0.0, y as f32 + 0.5)
+    }
+
+    #[test]
+    fn walkable_cells() {
+        let nav = grid(&[
+            ".#", //
+            "..", //
+        ]);
+        assert!(nav.is_walkable(at(0, 0)));
+        assert!(!nav.is_walkable(at(1, 0)));
+        assert!(!nav.is_walkable(at(2, 0)));
+        assert!(!nav.is_walkable(Vec3::new(-0.5, 0.0, 0.5)));
+    }
+
+    #[test]
+    fn path_goes_around_a_wall() {
+        let nav = grid(&[
+            ".....", //
+            ".###.", //
+            ".#...", //
+            ".#.#.", //
+            "...#.", //
+        ]);
+        let (start, goal) = (IVec2::new(2, 3), IVec2::new(4, 4));
+
+        let cells = nav.astar(start, goal).expect("a path around the wall");
+        assert_eq!(cells.first(), Some(&start));
+        assert_eq!(cells.last(), Some(&goal));
+        assert!(cells.iter().all(|cell| nav.height(*cell).is_some()));
+        for pair in cells.windows(2) {
+            assert!(nav.can_step(pair[0], pair[1]), "{pair:?} isn't a step");
+        }
+
+        let path = nav.find_path(at(2, 3), at(4, 4)).expect("a path");
+        assert_eq!(path.last(), Some(&at(4, 4)));
+        // the straight line is blocked, so there is at least one corner on the way
+        assert!(path.len() > 1);
+    }
+
+    #[test]
+    fn start_is_the_goal() {
+        let nav = grid(&["...", "...", "..."]);
+        let cell = IVec2::new(1, 1);
+        assert_eq!(nav.astar(cell, cell), Some(vec![cell]));
+        assert_eq!(nav.find_path(at(1, 1), at(1, 1)), Some(vec![at(1, 1)]));
+    }
+
+    #[test]
+    fn unreachable_goal() {
+        let nav = grid(&[
+            "..#..", //
+            "..#..", //
+            "..#..", //
+        ]);
+        assert_eq!(nav.astar(IVec2::new(0, 1), IVec2::new(4, 1)), None);
+        assert_eq!(nav.find_path(at(0, 1), at(4, 1)), None);
+    }
+
+    #[test]
+    fn no_cutting_corners() {
+        let nav = grid(&[
+            ".#", //
+            "..", //
+        ]);
+        let cells = nav.astar(IVec2::new(0, 0), IVec2::new(1, 1)).unwrap();
+        assert_eq!(cells.len(), 3);
+    }
+
+    #[test]
+    fn no_line_of_sight_between_blocked_corners() {
+        let nav = grid(&[
+            ".#", //
+            "#.", //
+        ]);
+        let (a, b) = (IVec2::new(0, 0), IVec2::new(1, 1));
+        assert!(!nav.can_move(a, b));
+        assert!(!nav.line_of_sight(a, b));
+        assert_eq!(nav.astar(a, b), None);
+
+        // one free side is not enough either
+        let nav = grid(&[
+            "..", //
+            "#.", //
+        ]);
+        assert!(!nav.line_of_sight(a, b));
+        // the path goes around the corner, not through it
+        let path = nav.find_path(at(0, 0), at(1, 1)).unwrap();
+        assert_eq!(path, [at(1, 0), at(1, 1)]);
+    }
+}
@@ -0,0 +1,273 @@
+//! Glue between gameplay entities and the network layer
+use super::*;
+use bevy_tnua::TnuaAnimatingState;
+use combat::AttackRateTimer;
+use event::types::{AttackEvent, RespawnRequest};
+use std::collections::HashMap;
+
+pub fn plugin(app: &mut App) {
+    app.init_resource::<NetEntities>()
+        .add_systems(
+            OnEnter(Screen::Gameplay),
+            enter_world.run_if(resource_exists::<NetClient>),
+        )
+        .add_systems(
+            OnExit(Screen::Gameplay),
+            logout.run_if(resource_exists::<NetClient>),
+        )
+        .add_systems(
+            Update,
+            (
+                attach_net_id,
+                send_respawn,
+                forward_attacks,
+                replicate_spawns,
+                buffer_snapshots.after(replicate_spawns),
+                apply_attributes.after(replicate_spawns),
+                interpolate.after(buffer_snapshots),
+            )
+                .run_if(resource_exists::<NetClient>)
+                .run_if(in_state(Screen::Gameplay)),
+        );
+}
+
+/// Local entities of everything replicated from the server
+#[derive(Resource, Default, Deref, DerefMut)]
+pub struct NetEntities(HashMap<NetId, Entity>);
+
+/// The character was picked on the character select screen, spawn it on the server now that
+/// the world is loaded on our side
+fn enter_world(client: Res<NetClient>, mut to_server: EventWriter<ToServer>) {
+    if client.state == ConnectionState::Authenticated {
+        to_server.write(ToServer(ClientMessage::EnterWorld));
+    }
+}
+
+/// Replicated entities are scoped to gameplay, so leave the world with them and get everything
+/// announced again on the next login
+fn logout(
+    mut client: ResMut<NetClient>,
+    mut clock: ResMut<ServerClock>,
+    mut net_entities: ResMut<NetEntities>,
+    mut to_server: EventWriter<ToServer>,
+) {
+    to_server.write(ToServer(ClientMessage::Logout));
+    client.state = ConnectionState::Disconnected;
+    clock.reset();
+    net_entities.clear();
+}
+
+/// Binds the locally controlled player to the character the server assigned to this client
+fn attach_net_id(
+    client: Res<NetClient>,
+    mut net_entities: ResMut<NetEntities>,
+    players: Query<Entity, (With<LocalPlayer>, Without<NetId>)>,
+    mut cmds: Commands,
+) {
+    let Some(id) = client.player() else {
+        return;
+    };
+
+    for player in players.iter() {
+        net_entities.insert(id, player);
+        cmds.entity(player).insert((
+            id,
+            PredictionHistory::default(),
+            PositionCorrection::default(),
+        ));
+    }
+}
+
+/// The server puts the character back at its own save point, or it would correct us back to
+/// where we died
+fn send_respawn(
+    mut requests: EventReader<RespawnRequest>,
+    players: Query<(), With<LocalPlayer>>,
+    mut to_server: EventWriter<ToServer>,
+) {
+    for request in requests.read() {
+        if players.contains(request.entity) {
+            to_server.write(ToServer(ClientMessage::Respawn));
+        }
+    }
+}
+
+/// Remote entities are fought on the server, combat only resolves the hits on local ones
+fn forward_attacks(
+    mut attacks: EventReader<AttackEvent>,
+    mut players: Query<&mut AttackRateTimer, (With<LocalPlayer>, Without<Dead>)>,
+    remote: Query<&NetId, With<Interpolated>>,
+    mut to_server: EventWriter<ToServer>,
+) {
+    for &AttackEvent { attacker, target } in attacks.read() {
+        let (Ok(mut timer), Ok(&target)) = (players.get_mut(attacker), remote.get(target)) else {
+            continue;
+        };
+        if timer.ready() {
+            timer.restart();
+            to_server.write(ToServer(ClientMessage::AttackIntent { target }));
+        }
+    }
+}
+
+fn replicate_spawns(
+    cfg: Res<Config>,
+    models: Res<Models>,
+    client: Res<NetClient>,
+    gltf_assets: Res<Assets<Gltf>>,
+    mut net_entities: ResMut<NetEntities>,
+    mut from_server: EventReader<FromServer>,
+    mut cmds: Commands,
+) {
+    for msg in from_server.read() {
+        match &msg.0 {
+            ServerMessage::EntitySpawned { id, kind, position } => {
+                if client.player() == Some(*id) || net_entities.contains_key(id) {
+                    continue;
+                }
+
+                let mut remote = cmds.spawn((
+                    StateScoped(Screen::Gameplay),
+                    Name::new(format!("Remote {kind:?} {}", id.0)),
+                    *id,
+                    Interpolated::default(),
+                    Transform::from_translation(*position),
+                    Collider::capsule(cfg.player.hitbox.radius, cfg.player.hitbox.height),
+                    // moved by snapshots, physics should only push others around
+                    RigidBody::Kinematic,
+                    InheritedVisibility::default(),
+                ));
+                match kind {
+                    EntityKind::Player => {
+                        remote.insert((
+                            Player::default(),
+                            TnuaAnimatingState::<AnimationState>::default(),
+                        ));
+                        if let Some(gltf) = gltf_assets.get(&models.player) {
+                            remote.with_children(|parent| {
+                                parent
+                                    .spawn((
+                                        Transform::from_xyz(0.0, -1.5, 0.0),
+                                        SceneRoot(gltf.scenes[0].clone()),
+                                    ))
+                                    .observe(player::prepare_animations);
+                            });
+                        }
+                    }
+                    EntityKind::Enemy => {
+                        // the model is attached by `enemy::render_plugin`, as for local enemies
+                        remote.insert((Enemy::default(), ComputedAttributes::default()));
+                    }
+                }
+
+                net_entities.insert(*id, remote.id());
+            }
+            ServerMessage::EntityDespawned { id } => {
+                if client.player() == Some(*id) {
+                    continue;
+                }
+                if let Some(entity) = net_entities.remove(id) {
+                    cmds.entity(entity).despawn();
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Hits, heals and level ups as the server has them. The local player keeps deriving its own
+/// attributes and only takes its health and mana from the server.
+fn apply_attributes(
+    net_entities: Res<NetEntities>,
+    mut from_server: EventReader<FromServer>,
+    mut attributes: Query<(&mut ComputedAttributes, Has<LocalPlayer>, Has<Dead>)>,
+    mut cmds: Commands,
+) {
+    for msg in from_server.read() {
+        let ServerMessage::AttributesUpdated {
+            id,
+            attributes: updated,
+        } = &msg.0
+        else {
+            continue;
+        };
+        let Some(&entity) = net_entities.get(id) else {
+            continue;
+        };
+        let Ok((mut attribs, local, dead)) = attributes.get_mut(entity) else {
+            continue;
+        };
+
+        if !local {
+            *attribs = *updated;
+            continue;
+        }
+        attribs.health = updated.health;
+        attribs.mana = updated.mana;
+        if attribs.health.hp <= 0.0 && !dead {
+            cmds.entity(entity).insert(Dead);
+        }
+    }
+}
+
+fn buffer_snapshots(
+    time: Res<Time<Real>>,
+    net_entities: Res<NetEntities>,
+    mut clock: ResMut<ServerClock>,
+    mut from_server: EventReader<FromServer>,
+    mut remote: Query<&mut Interpolated>,
+) {
+    for msg in from_server.read() {
+        let ServerMessage::Snapshot {
+            time: server_time,
+            entities,
+        } = &msg.0
+        else {
+            continue;
+        };
+
+        clock.observe(*server_time, time.elapsed_secs_f64());
+        for snapshot in entities {
+            let Some(&entity) = net_entities.get(&snapshot.id) else {
+                continue;
+            };
+            // the local player is predicted, not interpolated
+            let Ok(mut buffer) = remote.get_mut(entity) else {
+                continue;
+            };
+            buffer.push(Snapshot {
+                time: *server_time,
+                position: snapshot.position,
+                rotation: snapshot.rotation,
+                animation: snapshot.animation.clone(),
+            });
+        }
+    }
+}
+
+/// Moves remote entities to where the server had them `interpolation_delay` ago.
+/// Rendering and animation systems read [`Transform`] and [`AnimationState`] as usual.
+fn interpolate(
+    cfg: Res<Config>,
+    time: Res<Time<Real>>,
+    clock: Res<ServerClock>,
+    mut remote: Query<(&mut Transform, &mut Interpolated, Option<&mut Player>)>,
+) {
+    let Some(now) = clock.now(time.elapsed_secs_f64()) else {
+        return;
+    };
+    let render_time = now - cfg.net.interpolation_delay as f64;
+
+    for (mut transform, mut buffer, player) in remote.iter_mut() {
+        buffer.prune(render_time);
+        let Some(sample) = buffer.sample(render_time, cfg.net.max_extrapolation as f64) else {
+            continue;
+        };
+
+        transform.translation = sample.position;
+        transform.rotation = sample.rotation;
+        if let Some(mut player) = player {
+            player.animation_state = sample.animation;
+        }
+    }
+}
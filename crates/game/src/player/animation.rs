@@ -3,12 +3,16 @@ use bevy_tnua::{TnuaAnimatingState, TnuaAnimatingStateDirective, builtins::*};
 
 const ANIMATION_FACTOR: f32 = 0.1;
 
+/// Builds the animation graph once the model of a [`Player`] is spawned. The model is a child of
+/// the entity carrying the [`Player`], the [`AnimationPlayer`] is somewhere down its hierarchy.
 pub fn prepare_animations(
-    _: Trigger<SceneInstanceReady>,
+    on: Trigger<SceneInstanceReady>,
     models: Res<Models>,
     gltf_assets: Res<Assets<Gltf>>,
-    animation_player: Query<Entity, With<AnimationPlayer>>,
-    mut player: Query<&mut Player>,
+    parents: Query<&ChildOf>,
+    children: Query<&Children>,
+    animation_players: Query<(), With<AnimationPlayer>>,
+    mut players: Query<&mut Player>,
     mut commands: Commands,
     mut animation_graphs: ResMut<Assets<AnimationGraph>>,
 ) {
@@ -16,147 +20,186 @@ pub fn prepare_animations(
         return;
     };
 
-    for animation_player in animation_player {
-        let Ok(mut player) = player.single_mut() else {
-            println!("Multiple players in scene, ignoring");
-            return;
-        };
-        
-        let mut graph = AnimationGraph::new();
-        let root_node = graph.root;
+    let model = on.target();
+    let Ok(owner) = parents.get(model).map(ChildOf::parent) else {
+        return;
+    };
+    let Ok(mut player) = players.get_mut(owner) else {
+        return;
+    };
+    let Some(animation_player) = children
+        .iter_descendants(model)
+        .find(|e| animation_players.contains(*e))
+    else {
+        warn!("No AnimationPlayer in the model of player {owner}");
+        return;
+    };
 
-        for (name, clip) in gltf.named_animations.iter() {
-            let node_index = graph.add_clip(clip.clone(), 1.0, root_node);
-            player.animations.insert(name.to_string(), node_index);
-        }
+    let mut graph = AnimationGraph::new();
+    let root_node = graph.root;
 
-        // TODO: check if it still works on the second gamepad
-        commands
-            .entity(animation_player)
-            .insert(AnimationGraphHandle(animation_graphs.add(graph)));
+    for (name, clip) in gltf.named_animations.iter() {
+        let node_index = graph.add_clip(clip.clone(), 1.0, root_node);
+        player.animations.insert(name.to_string(), node_index);
     }
+
+    commands
+        .entity(animation_player)
+        .insert(AnimationGraphHandle(animation_graphs.add(graph)));
+    commands
+        .entity(owner)
+        .insert(PlayerAnimator(animation_player));
 }
 
 /// Tnua takes the heavy lifting with blending animations, but it leads to most of the animation
 /// being hidden behind tnua systems. Not for everyone, but definittely worth it as tnua implements
 /// more actions
 /// <https://github.com/idanarye/bevy-tnua/blob/main/demos/src/character_animating_systems/platformer_animating_systems.rs>
+///
+/// Only decides the [`AnimationState`] of locally simulated players, remote ones get it from
+/// server snapshots. Either way [`play_animations`] takes it from there.
 pub fn animating(
     cfg: Res<Config>,
-    mut player_q: Query<(
-        &TnuaController,
-        &mut Player,
-        &mut TnuaAnimatingState<AnimationState>,
-    )>,
-    mut animation_player: Query<&mut AnimationPlayer>,
+    mut player_q: Query<
+        (
+            &TnuaController,
+            &mut Player,
+            &ComputedAttributes,
+            Has<Dead>,
+            Has<HitStun>,
+        ),
+        Without<Interpolated>,
+    >,
 ) {
-    // An actual game should match the animation player and the controller. Here we cheat for
-    // simplicity and use the only controller and only player.
-    for (controller, mut player, mut animating_state) in player_q.iter_mut() {
-        for mut animation_player in animation_player.iter_mut() {
-            // Here we use the data from TnuaController to determine what the character is currently doing,
-            // so that we can later use that information to decide which animation to play.
-            // First we look at the `action_name` to determine which action (if at all) the character is currently performing:
-            let current_animation = match controller.action_name() {
-                Some(TnuaBuiltinKnockback::NAME) => {
-                    let (_, knockback_state) = controller
-                        .concrete_action::<TnuaBuiltinKnockback>()
-                        .expect("action name mismatch: Knockback");
-                    match knockback_state {
-                        TnuaBuiltinKnockbackState::Shove => AnimationState::KnockBack,
-                        TnuaBuiltinKnockbackState::Pushback { .. } => AnimationState::KnockBack,
-                    }
-                }
-                Some(TnuaBuiltinCrouch::NAME) => {
-                    let (_, crouch_state) = controller
-                        .concrete_action::<TnuaBuiltinCrouch>()
-                        .expect("action name mismatch: Crouch");
-                    // In case of crouch, we need the state of the basis to determine - based on
-                    // the speed - if the charcter is just crouching or also crawling.
-                    let Some((_, basis_state)) = controller.concrete_basis::<TnuaBuiltinWalk>() else {
-                        return;
-                    };
-                    let basis_speed = basis_state.running_velocity.length();
-                    let speed = Some(basis_speed)
-                        .filter(|speed| cfg.player.movement.idle_to_run_threshold < *speed);
-                    let is_crouching = basis_state.standing_offset.y < 0.05;
-                    // info!(
-                    //     "CROUCH: {is_crouching} speed: {basis_speed}, state:{crouch_state:?}, standing_offset: {}",
-                    //     basis_state.standing_offset.y
-                    // );
-                    match (speed, is_crouching) {
-                        (None, false) => AnimationState::StandIdle,
-                        (None, true) => match crouch_state {
-                            TnuaBuiltinCrouchState::Maintaining => AnimationState::Crouch,
-                            // TODO: have rise animation
-                            TnuaBuiltinCrouchState::Rising => AnimationState::Crouch,
-                            // TODO: sink animation
-                            TnuaBuiltinCrouchState::Sinking => AnimationState::Crouch,
-                        },
-                        (Some(speed), false) => AnimationState::Run(ANIMATION_FACTOR * speed),
-                        // TODO: place to handle slide here
-                        (Some(speed), true) => AnimationState::Crawl(ANIMATION_FACTOR * speed * 4.0),
-                    }
+    for (controller, mut player, attribs, dead, stunned) in player_q.iter_mut() {
+        if dead {
+            player.animation_state = AnimationState::Dead;
+            continue;
+        }
+        // knockbacks have their own animation below
+        if stunned && controller.action_name().is_none() {
+            player.animation_state = AnimationState::Flinch;
+            continue;
+        }
+
+        // Here we use the data from TnuaController to determine what the character is currently doing,
+        // so that we can later use that information to decide which animation to play.
+        // First we look at the `action_name` to determine which action (if at all) the character is currently performing:
+        let current_animation = match controller.action_name() {
+            Some(TnuaBuiltinKnockback::NAME) => {
+                let (_, knockback_state) = controller
+                    .concrete_action::<TnuaBuiltinKnockback>()
+                    .expect("action name mismatch: Knockback");
+                match knockback_state {
+                    TnuaBuiltinKnockbackState::Shove => AnimationState::KnockBack,
+                    TnuaBuiltinKnockbackState::Pushback { .. } => AnimationState::KnockBack,
                 }
-                // Unless you provide the action names yourself, prefer matching against the `NAME` const
-                // of the `TnuaAction` trait. Once `type_name` is stabilized as `const` Tnua will use it to
-                // generate these names automatically, which may result in a change to the name.
-                Some(TnuaBuiltinJump::NAME) => {
-                    // In case of jump, we want to cast it so that we can get the concrete jump state.
-                    let (_, jump_state) = controller
-                        .concrete_action::<TnuaBuiltinJump>()
-                        .expect("action name mismatch: Jump");
-                    // Depending on the state of the jump, we need to decide if we want to play the jump
-                    // animation or the fall animation.
-                    match jump_state {
-                        TnuaBuiltinJumpState::NoJump => return,
-                        TnuaBuiltinJumpState::StartingJump { .. } => AnimationState::JumpStart,
-                        TnuaBuiltinJumpState::SlowDownTooFastSlopeJump { .. } => AnimationState::JumpStart,
-                        TnuaBuiltinJumpState::MaintainingJump { .. } => AnimationState::JumpLoop,
-                        TnuaBuiltinJumpState::StoppedMaintainingJump => AnimationState::JumpLand,
-                        TnuaBuiltinJumpState::FallSection => AnimationState::Fall,
-                    }
+            }
+            Some(TnuaBuiltinCrouch::NAME) => {
+                let (_, crouch_state) = controller
+                    .concrete_action::<TnuaBuiltinCrouch>()
+                    .expect("action name mismatch: Crouch");
+                // In case of crouch, we need the state of the basis to determine - based on
+                // the speed - if the charcter is just crouching or also crawling.
+                let Some((_, basis_state)) = controller.concrete_basis::<TnuaBuiltinWalk>() else {
+                    continue;
+                };
+                let basis_speed = basis_state.running_velocity.length();
+                let speed = Some(basis_speed)
+                    .filter(|speed| cfg.player.movement.idle_to_run_threshold < *speed);
+                let is_crouching = basis_state.standing_offset.y < 0.05;
+                // info!(
+                //     "CROUCH: {is_crouching} speed: {basis_speed}, state:{crouch_state:?}, standing_offset: {}",
+                //     basis_state.standing_offset.y
+                // );
+                match (speed, is_crouching) {
+                    (None, false) => AnimationState::StandIdle,
+                    (None, true) => match crouch_state {
+                        TnuaBuiltinCrouchState::Maintaining => AnimationState::Crouch,
+                        // TODO: have rise animation
+                        TnuaBuiltinCrouchState::Rising => AnimationState::Crouch,
+                        // TODO: sink animation
+                        TnuaBuiltinCrouchState::Sinking => AnimationState::Crouch,
+                    },
+                    (Some(speed), false) => AnimationState::Run(ANIMATION_FACTOR * speed),
+                    // TODO: place to handle slide here
+                    (Some(speed), true) => AnimationState::Crawl(ANIMATION_FACTOR * speed * 4.0),
                 }
-                Some(TnuaBuiltinClimb::NAME) => {
-                    let Some((_, action_state)) = controller.concrete_action::<TnuaBuiltinClimb>() else {
-                        return;
-                    };
-                    let TnuaBuiltinClimbState::Climbing { climbing_velocity } = action_state else {
-                        return;
-                    };
-                    AnimationState::Climb(0.3 * climbing_velocity.dot(Vec3::Y))
+            }
+            // Unless you provide the action names yourself, prefer matching against the `NAME` const
+            // of the `TnuaAction` trait. Once `type_name` is stabilized as `const` Tnua will use it to
+            // generate these names automatically, which may result in a change to the name.
+            Some(TnuaBuiltinJump::NAME) => {
+                // In case of jump, we want to cast it so that we can get the concrete jump state.
+                let (_, jump_state) = controller
+                    .concrete_action::<TnuaBuiltinJump>()
+                    .expect("action name mismatch: Jump");
+                // Depending on the state of the jump, we need to decide if we want to play the jump
+                // animation or the fall animation.
+                match jump_state {
+                    TnuaBuiltinJumpState::NoJump => continue,
+                    TnuaBuiltinJumpState::StartingJump { .. } => AnimationState::JumpStart,
+                    TnuaBuiltinJumpState::SlowDownTooFastSlopeJump { .. } => AnimationState::JumpStart,
+                    TnuaBuiltinJumpState::MaintainingJump { .. } => AnimationState::JumpLoop,
+                    TnuaBuiltinJumpState::StoppedMaintainingJump => AnimationState::JumpLand,
+                    TnuaBuiltinJumpState::FallSection => AnimationState::Fall,
                 }
-                // TODO: replace roll with actual dash
-                Some(TnuaBuiltinDash::NAME) => AnimationState::Dash,
-                Some(TnuaBuiltinWallSlide::NAME) => AnimationState::WallSlide,
-                Some("walljump") => AnimationState::WallJump,
-                Some(other) => panic!("Unknown action {other}"),
-                None => {
-                    // If there is no action going on, we'll base the animation on the state of the basis.
-                    let Some((_, basis_state)) = controller.concrete_basis::<TnuaBuiltinWalk>() else {
-                        return;
-                    };
-                    if basis_state.standing_on_entity().is_none() {
-                        AnimationState::Fall
-                    } else {
-                        let basis_speed = basis_state.running_velocity.length();
-                        if basis_speed > cfg.player.movement.idle_to_run_threshold {
-                            let speed = ANIMATION_FACTOR * basis_speed;
-                            if basis_speed > cfg.player.movement.speed {
-                                AnimationState::Sprint(speed)
-                            } else {
-                                AnimationState::Run(speed)
-                            }
+            }
+            Some(TnuaBuiltinClimb::NAME) => {
+                let Some((_, action_state)) = controller.concrete_action::<TnuaBuiltinClimb>() else {
+                    continue;
+                };
+                let TnuaBuiltinClimbState::Climbing { climbing_velocity } = action_state else {
+                    continue;
+                };
+                AnimationState::Climb(0.3 * climbing_velocity.dot(Vec3::Y))
+            }
+            // TODO: replace roll with actual dash
+            Some(TnuaBuiltinDash::NAME) => AnimationState::Dash,
+            Some(TnuaBuiltinWallSlide::NAME) => AnimationState::WallSlide,
+            Some("walljump") => AnimationState::WallJump,
+            Some(other) => panic!("Unknown action {other}"),
+            None => {
+                // If there is no action going on, we'll base the animation on the state of the basis.
+                let Some((_, basis_state)) = controller.concrete_basis::<TnuaBuiltinWalk>() else {
+                    continue;
+                };
+                if basis_state.standing_on_entity().is_none() {
+                    AnimationState::Fall
+                } else {
+                    let basis_speed = basis_state.running_velocity.length();
+                    if basis_speed > cfg.player.movement.idle_to_run_threshold {
+                        let speed = ANIMATION_FACTOR * basis_speed;
+                        if basis_speed > attribs.move_speed {
+                            AnimationState::Sprint(speed)
                         } else {
-                            AnimationState::StandIdle
+                            AnimationState::Run(speed)
                         }
+                    } else {
+                        AnimationState::StandIdle
                     }
                 }
-            };
+            }
+        };
 
-            // Update player animation state, it could be useful in some systems
-            player.animation_state = current_animation.clone();
-            let animating_directive = animating_state.update_by_discriminant(current_animation);
+        // Update player animation state, it could be useful in some systems
+        player.animation_state = current_animation;
+    }
+}
+
+pub fn play_animations(
+    mut player_q: Query<(
+        &Player,
+        &PlayerAnimator,
+        &mut TnuaAnimatingState<AnimationState>,
+        Option<&TnuaController>,
+    )>,
+    mut animation_players: Query<&mut AnimationPlayer>,
+) {
+    for (player, animator, mut animating_state, controller) in player_q.iter_mut() {
+        if let Ok(mut animation_player) = animation_players.get_mut(animator.0) {
+            let animating_directive =
+                animating_state.update_by_discriminant(player.animation_state.clone());
 
             match animating_directive {
                 // `Maintain` means that we did not switch to a different variant, so there is no need to change animations.
@@ -175,7 +218,9 @@ pub fn animating(
                     // Jumping and dashing can be chained, we want to start a new jump/dash animation
                     // when one jump/dash is chained to another.
                     AnimationState::JumpStart | AnimationState::Dash => {
-                        if controller.action_flow_status().just_starting().is_some() {
+                        if controller
+                            .is_some_and(|c| c.action_flow_status().just_starting().is_some())
+                        {
                             animation_player.seek_all_by(0.0);
                         }
                     }
@@ -256,11 +301,22 @@ pub fn animating(
                                 animation_player.start(*index).set_speed(1.0);
                             }
                         }
+                        AnimationState::Flinch => {
+                            if let Some(index) = player.animations.get("Hit_Head") {
+                                animation_player.start(*index).set_speed(2.0);
+                            }
+                        }
                         AnimationState::Climb(speed) => {
                             if let Some(index) = player.animations.get("Jump_Loop") {
                                 animation_player.start(*index).set_speed(*speed).repeat();
                             }
                         }
+                        // played once, the last frame holds until the player respawns
+                        AnimationState::Dead => {
+                            if let Some(index) = player.animations.get("Death01") {
+                                animation_player.start(*index).set_speed(1.0);
+                            }
+                        }
                     }
                 }
             }
@@ -3,15 +3,20 @@ use bevy_tnua::{
     builtins::{TnuaBuiltinCrouch, TnuaBuiltinDash},
     control_helpers::TnuaSimpleAirActionsCounter,
 };
-use event::types::{AttackEvent, EnemyClickEvent, GroundClickEvent};
+use event::types::{AttackEvent, CastRequest, EnemyClickEvent, GroundClickEvent, HotbarPressed};
+use navigation::NavMesh;
 
 pub fn plugin(app: &mut App) {
     app.add_systems(
         Update,
         (
+            select_target,
+            use_hotbar,
+            auto_attack,
             movement.in_set(TnuaUserControlsSystemSet),
-            attack_enemy
-        ).run_if(in_state(Screen::Gameplay)),
+        )
+            .chain()
+            .run_if(in_state(Screen::Gameplay)),
     );
 
     app.add_observer(handle_sprint_in)
@@ -19,86 +24,155 @@ pub fn plugin(app: &mut App) {
         .add_observer(handle_jump)
         .add_observer(handle_dash)
         .add_observer(crouch_in)
-        .add_observer(crouch_out);
+        .add_observer(crouch_out)
+        .add_observer(press_hotbar::<Hotbar1>)
+        .add_observer(press_hotbar::<Hotbar2>)
+        .add_observer(press_hotbar::<Hotbar3>)
+        .add_observer(press_hotbar::<Hotbar4>)
+        .add_observer(press_hotbar::<Hotbar5>)
+        .add_observer(press_hotbar::<Hotbar6>)
+        .add_observer(press_hotbar::<Hotbar7>)
+        .add_observer(press_hotbar::<Hotbar8>)
+        .add_observer(press_hotbar::<Hotbar9>);
 }
 
 fn movement(
     cfg: Res<Config>,
-    actions: Single<&Actions<GameplayCtx>>,
+    camera: Query<&Transform, With<SceneCamera>>,
     mut ground_click_evt: EventReader<GroundClickEvent>,
-    mut player_query: Query<(&mut Player, &mut TnuaController, &mut StepTimer, &Transform)>,
+    mut player_query: Query<
+        (
+            Entity,
+            &Actions<GameplayCtx>,
+            &mut Player,
+            &ComputedAttributes,
+            &mut TnuaController,
+            &mut StepTimer,
+            &mut NavPath,
+            &Transform,
+            Option<&Lockouts>,
+            Has<HitStun>,
+            Has<AttackTarget>,
+        ),
+        (With<LocalPlayer>, Without<Dead>),
+    >,
+    nav: Res<NavMesh>,
+    time: Res<Time>,
+    mut commands: Commands,
 ) -> Result {
-    let actions = actions.into_inner();
-
-    let mut desired_velocity = Vec3::ZERO;
-    let mut desired_forward: Option<Dir3> = None;
-
-    let Ok((mut player, mut controller, mut step_timer, transform)) = player_query.single_mut()
-    else {
-        error!("Player not found");
-        return Ok(());
-    };
+    let click = ground_click_evt.read().last().map(|click| click.position);
+
+    for (
+        entity,
+        actions,
+        mut player,
+        attribs,
+        mut controller,
+        mut step_timer,
+        mut path,
+        transform,
+        lockouts,
+        stunned,
+        attacking,
+    ) in player_query.iter_mut()
+    {
+        let mut desired_velocity = Vec3::ZERO;
+        let mut desired_forward: Option<Dir3> = None;
 
-    if let Some(click) = ground_click_evt.read().next() {
-        player.target_position = Some(click.position);
-    };
+        // keyboard/gamepad movement takes over click-to-move
+        let navigate = actions.value::<Navigate>()?.as_axis2d();
 
-    if let Some(target_position) = player.target_position {
-        let mut direction = target_position - transform.translation;
-        let distance = direction.xz().length();
-        direction = direction.normalize_or_zero();
+        // walking away gives up the auto-attack
+        if attacking && (click.is_some() || navigate != Vec2::ZERO) {
+            commands.entity(entity).remove::<AttackTarget>();
+        }
 
-        desired_velocity = direction * player.speed;
-        desired_forward = Dir3::new(direction).ok();
+        if let Some(position) = click {
+            player.target_position = Some(position);
+        };
 
-        if distance <= 0.05 {
+        if navigate != Vec2::ZERO {
             player.target_position = None;
+
+            let direction = camera.single()?.movement_direction(navigate);
+            desired_velocity = direction * player.speed;
+            desired_forward = Dir3::new(direction).ok();
         }
-    };
 
-    controller.basis(TnuaBuiltinWalk {
-        float_height: FLOAT_HEIGHT,
-        cling_distance: FLOAT_HEIGHT + 0.01, // Slightly higher than float_height for a bit of "give".
-        spring_strength: 500.0,              // Stronger spring for a more grounded feel.
-        spring_dampening: 1.0, // Slightly reduced dampening for a more responsive spring.
-        acceleration: 80.0,    // Increased acceleration for snappier movement starts and stops.
-        air_acceleration: 30.0, // Allow for some air control, but less than ground.
-        free_fall_extra_gravity: 70.0, // Slightly increased for a less floaty fall.
-        tilt_offset_angvel: 7.0, // Increased for a slightly faster righting response.
-        tilt_offset_angacl: 700.0, // Increased acceleration to reach the target righting speed.
-        turning_angvel: 12.0,  // Increased for more responsive turning.
-
-        desired_velocity,
-        desired_forward,
-
-        ..default()
-    });
+        if let Some(target_position) = player.target_position {
+            let distance = transform.translation.xz().distance(target_position.xz());
+            // around rocks and walls instead of straight into them
+            let next = navigation::steer(
+                &nav,
+                &mut path,
+                transform.translation,
+                target_position,
+                time.delta_secs(),
+            );
+            let direction = (next - transform.translation)
+                .with_y(0.0)
+                .normalize_or_zero();
+
+            desired_velocity = direction * player.speed;
+            desired_forward = Dir3::new(direction).ok();
+
+            if distance <= 0.05 {
+                player.target_position = None;
+            }
+        } else if path.goal.is_some() {
+            *path = NavPath::default();
+        };
+
+        // stunned, frozen, reeling from a hit and the like, the walk resumes once it wears off
+        if stunned || lockouts.is_some_and(|l| l.movement) {
+            desired_velocity = Vec3::ZERO;
+            desired_forward = None;
+        }
 
-    // Check if crouch is currently active and apply TnuaBuiltinCrouch as an action
-    if actions.value::<Crouch>()?.as_bool() {
-        controller.action(TnuaBuiltinCrouch {
-            float_offset: 0.0,
-            height_change_impulse_for_duration: 0.1,
-            height_change_impulse_limit: 80.0,
-            uncancellable: false,
+        controller.basis(TnuaBuiltinWalk {
+            float_height: Player::FLOAT_HEIGHT,
+            cling_distance: Player::FLOAT_HEIGHT + 0.01, // Slightly higher than float_height for a bit of "give".
+            spring_strength: 500.0, // Stronger spring for a more grounded feel.
+            spring_dampening: 1.0,  // Slightly reduced dampening for a more responsive spring.
+            acceleration: 80.0, // Increased acceleration for snappier movement starts and stops.
+            air_acceleration: 30.0, // Allow for some air control, but less than ground.
+            free_fall_extra_gravity: 70.0, // Slightly increased for a less floaty fall.
+            tilt_offset_angvel: 7.0, // Increased for a slightly faster righting response.
+            tilt_offset_angacl: 700.0, // Increased acceleration to reach the target righting speed.
+            turning_angvel: 12.0, // Increased for more responsive turning.
+
+            desired_velocity,
+            desired_forward,
+
+            ..default()
         });
-    }
 
-    // update step timer dynamically based on actual speed
-    // normal step: 0.475
-    // sprint step (x1.5): 0.354
-    // step on sprint timer: 0.317
-    let Some((_, basis_state)) = controller.concrete_basis::<TnuaBuiltinWalk>() else {
-        return Ok(());
-    };
+        // Check if crouch is currently active and apply TnuaBuiltinCrouch as an action
+        if actions.value::<Crouch>()?.as_bool() {
+            controller.action(TnuaBuiltinCrouch {
+                float_offset: 0.0,
+                height_change_impulse_for_duration: 0.1,
+                height_change_impulse_limit: 80.0,
+                uncancellable: false,
+            });
+        }
 
-    let current_actual_speed = basis_state.running_velocity.length();
-    if current_actual_speed > IDLE_TO_RUN_TRESHOLD {
-        let ratio = cfg.player.movement.speed / current_actual_speed;
-        let adjusted_step_time_f32 = cfg.timers.step * ratio;
-        let adjusted_step_time = Duration::from_secs_f32(adjusted_step_time_f32);
-        // info!("step timer:{adjusted_step_time_f32}s");
-        step_timer.set_duration(adjusted_step_time);
+        // update step timer dynamically based on actual speed
+        // normal step: 0.475
+        // sprint step (x1.5): 0.354
+        // step on sprint timer: 0.317
+        let Some((_, basis_state)) = controller.concrete_basis::<TnuaBuiltinWalk>() else {
+            continue;
+        };
+
+        let current_actual_speed = basis_state.running_velocity.length();
+        if current_actual_speed > IDLE_TO_RUN_TRESHOLD {
+            let ratio = attribs.move_speed / current_actual_speed;
+            let adjusted_step_time_f32 = cfg.timers.step * ratio;
+            let adjusted_step_time = Duration::from_secs_f32(adjusted_step_time_f32);
+            // info!("step timer:{adjusted_step_time_f32}s");
+            step_timer.set_duration(adjusted_step_time);
+        }
     }
 
     Ok(())
@@ -107,13 +181,14 @@ fn movement(
 fn handle_sprint_in(
     on: Trigger<Started<Sprint>>,
     cfg: Res<Config>,
-    mut player_query: Query<&mut Player, With<GameplayCtx>>,
+    mut player_query: Query<(&mut Player, &ComputedAttributes), With<GameplayCtx>>,
 ) -> Result {
     let entity = on.target();
-    if let Ok(mut player) = player_query.get_mut(entity) {
-        if player.speed <= cfg.player.movement.speed {
-            player.speed *= cfg.player.movement.sprint_factor;
-        }
+    if let Ok((mut player, attribs)) = player_query.get_mut(entity)
+        && player.speed_factor <= 1.0
+    {
+        let factor = player.speed_factor * cfg.player.movement.sprint_factor;
+        player.set_speed_factor(factor, attribs.move_speed);
     }
 
     Ok(())
@@ -121,14 +196,13 @@ fn handle_sprint_in(
 
 fn handle_sprint_out(
     on: Trigger<Completed<Navigate>>,
-    cfg: Res<Config>,
-    mut player_query: Query<&mut Player, With<GameplayCtx>>,
+    mut player_query: Query<(&mut Player, &ComputedAttributes), With<GameplayCtx>>,
 ) {
     let entity = on.target();
-    if let Ok(mut player) = player_query.get_mut(entity)
-        && player.speed > cfg.player.movement.speed
+    if let Ok((mut player, attribs)) = player_query.get_mut(entity)
+        && player.speed_factor > 1.0
     {
-        player.speed = cfg.player.movement.speed;
+        player.set_speed_factor(1.0, attribs.move_speed);
     }
 }
 
@@ -141,11 +215,17 @@ fn handle_jump(
             &mut TnuaController,
             &mut TnuaSimpleAirActionsCounter,
             &mut JumpTimer,
+            Option<&Lockouts>,
+            Has<HitStun>,
         ),
         With<Player>,
     >,
 ) -> Result {
-    let (mut controller, mut air_counter, mut _jump_timer) = player_query.get_mut(on.target())?;
+    let (mut controller, mut air_counter, mut _jump_timer, lockouts, stunned) =
+        player_query.get_mut(on.target())?;
+    if stunned || lockouts.is_some_and(|l| l.movement) {
+        return Ok(());
+    }
 
     // if jump_timer.tick(time.delta()).just_finished() {
     air_counter.update(controller.as_mut()); // Update air counter
@@ -169,11 +249,20 @@ fn handle_jump(
 fn handle_dash(
     on: Trigger<Started<Dash>>,
     cfg: Res<Config>,
-    actions: Single<&Actions<GameplayCtx>>,
     camera: Query<&Transform, With<SceneCamera>>,
-    mut player_query: Query<(&mut TnuaController, &TnuaSimpleAirActionsCounter)>,
+    mut player_query: Query<(
+        &Actions<GameplayCtx>,
+        &mut TnuaController,
+        &TnuaSimpleAirActionsCounter,
+        Option<&Lockouts>,
+        Has<HitStun>,
+    )>,
 ) -> Result {
-    let (mut controller, air_counter) = player_query.get_mut(on.target())?;
+    let (actions, mut controller, air_counter, lockouts, stunned) =
+        player_query.get_mut(on.target())?;
+    if stunned || lockouts.is_some_and(|l| l.movement) {
+        return Ok(());
+    }
     let cam_transform = camera.single()?;
     let navigate = actions.value::<Navigate>()?.as_axis2d();
     let direction = cam_transform.movement_direction(navigate);
@@ -193,83 +282,169 @@ fn handle_dash(
 pub fn crouch_in(
     on: Trigger<Started<Crouch>>,
     cfg: Res<Config>,
-    mut player: Query<&mut Player, With<GameplayCtx>>,
+    mut player: Query<(&mut Player, &ComputedAttributes), With<GameplayCtx>>,
     mut tnua: Query<(&mut TnuaAvian3dSensorShape, &mut Collider), With<Player>>,
 ) -> Result {
-    let (mut avian_sensor, mut collider) = tnua.single_mut()?;
-    let mut player = player.get_mut(on.target())?;
+    let (mut avian_sensor, mut collider) = tnua.get_mut(on.target())?;
+    let (mut player, attribs) = player.get_mut(on.target())?;
 
     collider.set_scale(Vec3::new(1.0, 0.5, 1.0), 4);
     avian_sensor.0.set_scale(Vec3::new(1.0, 0.5, 1.0), 4);
-    player.speed *= cfg.player.movement.crouch_factor;
+    let factor = player.speed_factor * cfg.player.movement.crouch_factor;
+    player.set_speed_factor(factor, attribs.move_speed);
 
     Ok(())
 }
 
 pub fn crouch_out(
     on: Trigger<Completed<Crouch>>,
-    cfg: Res<Config>,
-    mut player: Query<&mut Player, With<GameplayCtx>>,
+    mut player: Query<(&mut Player, &ComputedAttributes), With<GameplayCtx>>,
     mut tnua: Query<
         (&mut TnuaAvian3dSensorShape, &mut Collider),
         (With<Player>, Without<SceneCamera>),
     >,
 ) -> Result {
     let (mut avian_sensor, mut collider) = tnua.get_mut(on.target())?;
-    let mut player = player.get_mut(on.target())?;
+    let (mut player, attribs) = player.get_mut(on.target())?;
 
     collider.set_scale(Vec3::ONE, 4);
     avian_sensor.0.set_scale(Vec3::ONE, 4);
-    player.speed = cfg.player.movement.speed;
+    player.set_speed_factor(1.0, attribs.move_speed);
 
     Ok(())
 }
 
-fn attack_enemy(
+/// Clicking an enemy makes it the target of the auto-attack
+fn select_target(
     mut enemy_click_event: EventReader<EnemyClickEvent>,
-    mut attack_event: EventWriter<AttackEvent>,
-    enemy_query: Query<(&Enemy, &Transform, &Collider)>,
-    player_query: Query<(&Player, &ComputedAttributes, &Transform, &Collider)>,
+    enemy_query: Query<(), (With<Enemy>, Without<Dead>)>,
+    player_query: Query<&Actions<GameplayCtx>, Without<Dead>>,
+    mut commands: Commands,
 ) -> Result {
-    const COLLISION_BUFFER: f32 = 0.5;
-
     for enemy_click_evt in enemy_click_event.read() {
-        let Ok((enemy, enemy_transform, enemy_collider)) = enemy_query.get(enemy_click_evt.target)
-        else {
+        if !enemy_query.contains(enemy_click_evt.target) {
             warn!("Invalid Enemy or no longer exists");
             continue;
-        };
+        }
 
-        let Ok((player, player_comp_attribs, player_transform, player_collider)) =
-            player_query.get(enemy_click_evt.player)
-        else {
+        let Ok(actions) = player_query.get(enemy_click_evt.player) else {
             warn!("Invalid Player or no longer exists");
             continue;
         };
 
-        let Some(player_radius) = utils::get_capsule_radius(player_collider) else {
-            error!("Player has no capsule");
-            continue;
+        let target = AttackTarget {
+            entity: enemy_click_evt.target,
+            in_place: actions.value::<ForceAttack>()?.as_bool(),
         };
+        commands.entity(enemy_click_evt.player).insert(target);
+    }
 
-        let Some(enemy_radius) = utils::get_capsule_radius(enemy_collider) else {
-            error!("Enemy has no capsule");
+    Ok(())
+}
+
+/// Walks the player into range of its target and keeps swinging, the [`AttackRateTimer`] sets
+/// the pace. Ends once the target is dead or gone.
+fn auto_attack(
+    mut attack_event: EventWriter<AttackEvent>,
+    mut player_query: Query<
+        (
+            Entity,
+            &mut Player,
+            &AttackTarget,
+            &ComputedAttributes,
+            &Transform,
+            &Collider,
+        ),
+        Without<Dead>,
+    >,
+    enemy_query: Query<(&Transform, &Collider), (With<Enemy>, Without<Dead>)>,
+    mut commands: Commands,
+) {
+    for (entity, mut player, target, player_comp_attribs, player_transform, player_collider) in
+        player_query.iter_mut()
+    {
+        let Ok((enemy_transform, enemy_collider)) = enemy_query.get(target.entity) else {
+            commands.entity(entity).remove::<AttackTarget>();
+            if !target.in_place {
+                player.target_position = None;
+            }
             continue;
         };
 
-        let direction = player_transform.translation - enemy_transform.translation;
-        let dist = direction.xz().length();
-
-        let collider_dist = enemy_radius + player_radius + COLLISION_BUFFER;
-        let is_in_range = dist <= collider_dist + player_comp_attribs.attack_range;
-
-        if is_in_range {
+        if is_in_attack_range(
+            player_transform,
+            player_collider,
+            player_comp_attribs.attack_range,
+            enemy_transform,
+            enemy_collider,
+        ) {
+            if !target.in_place {
+                player.target_position = None;
+            }
             attack_event.write(AttackEvent {
                 attacker: player.id,
-                target: enemy.id,
+                target: target.entity,
             });
+        } else if !target.in_place {
+            player.target_position = Some(enemy_transform.translation);
         }
     }
+}
 
-    Ok(())
+fn press_hotbar<A: HotbarAction>(on: Trigger<Started<A>>, mut pressed: EventWriter<HotbarPressed>) {
+    pressed.write(HotbarPressed {
+        entity: on.target(),
+        slot: A::SLOT,
+    });
+}
+
+/// Casts the skill in the pressed slot: single target skills go at the attack target,
+/// ground skills around it or at the player's feet without one
+fn use_hotbar(
+    skill_db: Option<Res<SkillDb>>,
+    mut pressed: EventReader<HotbarPressed>,
+    player_query: Query<(&Hotbar, &Transform, Option<&AttackTarget>), Without<Dead>>,
+    target_query: Query<&Transform>,
+    mut cast_requests: EventWriter<CastRequest>,
+) {
+    let Some(skill_db) = skill_db else {
+        return;
+    };
+
+    for HotbarPressed { entity, slot } in pressed.read().copied() {
+        let Ok((hotbar, transform, attack_target)) = player_query.get(entity) else {
+            continue;
+        };
+        let Some(skill) = hotbar.0.get(slot).copied().flatten() else {
+            continue;
+        };
+        let Some(def) = skill_db.get(skill) else {
+            warn!("no skill with id {} in the skill database", *skill);
+            continue;
+        };
+
+        let target = match def.target {
+            SkillTarget::SelfCast => CastTarget::Caster,
+            SkillTarget::Single => {
+                let Some(attack_target) = attack_target else {
+                    info!("{} needs a target", def.name);
+                    continue;
+                };
+                CastTarget::Entity(attack_target.entity)
+            }
+            SkillTarget::GroundArea { .. } => {
+                let point = attack_target
+                    .and_then(|target| target_query.get(target.entity).ok())
+                    .unwrap_or(transform)
+                    .translation;
+                CastTarget::Ground(point)
+            }
+        };
+
+        cast_requests.write(CastRequest {
+            caster: entity,
+            skill,
+            target,
+        });
+    }
 }
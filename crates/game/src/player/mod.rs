@@ -7,26 +7,28 @@ use std::{f32::consts::PI, time::Duration};
 
 mod animation;
 mod control;
+mod prediction;
 
-use animation::*;
+pub use animation::*;
 use combat::*;
 
 pub const IDLE_TO_RUN_TRESHOLD: f32 = 0.01;
-pub const FLOAT_HEIGHT: f32 = 0.5;
 
 pub fn plugin(app: &mut App) {
     app.add_plugins((
         ThirdPersonCameraPlugin,
-        TnuaControllerPlugin::new(FixedUpdate),
-        TnuaAvian3dPlugin::new(FixedUpdate),
         control::plugin,
+        prediction::plugin,
     ));
 
     app.configure_sets(PostUpdate, CameraSyncSet.after(PhysicsSet::Sync))
         .add_systems(OnEnter(Screen::Gameplay), spawn_player)
+        .add_observer(lock_input_on_death)
+        .add_observer(unlock_input_on_respawn)
         .add_systems(
             Update,
-            animating
+            (animating, play_animations)
+                .chain()
                 .in_set(TnuaUserControlsSystemSet)
                 .run_if(in_state(Screen::Gameplay)),
         );
@@ -34,6 +36,7 @@ pub fn plugin(app: &mut App) {
 
 pub fn spawn_player(
     cfg: Res<Config>,
+    levels: Res<LevelTable>,
     models: Res<Models>,
     gltf_assets: Res<Assets<Gltf>>,
     camera: Query<&Transform, With<SceneCamera>>,
@@ -52,34 +55,11 @@ pub fn spawn_player(
     let player_rot = Quat::from_rotation_y(PI);
     let mesh = SceneRoot(gltf.scenes[0].clone());
     let pos = Transform::from_translation(Vec3::new(0.0, 5.0, 0.0)).with_rotation(player_rot);
-    let player = Player {
-        id: Entity::PLACEHOLDER,
-        speed: cfg.player.movement.speed,
-        animation_state: AnimationState::StandIdle,
-        ..default()
-    };
-
-    let collider = Collider::capsule(cfg.player.hitbox.radius, cfg.player.hitbox.height);
-
-    let attribs = Attributes::default();
-    let comp_attribs = ComputedAttributes {
-        move_speed: cfg.player.movement.speed,
-        attack: 5.0,
-        attack_rate: 1.6,
-        attack_range: 2.0,
-        health: Health::new(30.0),
-        ..default()
-    };
-
-    let attack_timer = AttackRateTimer::new(comp_attribs.attack_rate);
 
     commands
         .spawn((
-            StateScoped(Screen::Gameplay),
-            pos,
-            player,
-            attribs,
-            comp_attribs,
+            character::player_bundle(&cfg, &levels, pos),
+            LocalPlayer,
             // input context
             (
                 GameplayCtx,
@@ -87,27 +67,9 @@ pub fn spawn_player(
                 Actions::<GameplayCtx>::default(),
             ),
             ThirdPersonCameraTarget,
-            (
-                TnuaController::default(),
-                // Tnua can fix the rotation, but the character will still get rotated before it can do so.
-                // By locking the rotation we can prevent this.
-                LockedAxes::ROTATION_LOCKED.unlock_rotation_y(),
-                TnuaAnimatingState::<AnimationState>::default(),
-                TnuaSimpleAirActionsCounter::default(),
-                // A sensor shape is not strictly necessary, but without it we'll get weird results.
-                TnuaAvian3dSensorShape(collider.clone()),
-            ),
-            // physics
-            (
-                collider,
-                RigidBody::Dynamic,
-                // Friction::ZERO.with_combine_rule(CoefficientCombine::Multiply),
-            ),
-            // timers
+            TnuaAnimatingState::<AnimationState>::default(),
+            TnuaSimpleAirActionsCounter::default(),
             JumpTimer(Timer::from_seconds(cfg.timers.jump, TimerMode::Repeating)),
-            StepTimer(Timer::from_seconds(cfg.timers.step, TimerMode::Repeating)),
-            InheritedVisibility::default(), // silence the warning because of adding SceneRoot as a child
-            attack_timer,
         ))
         // spawn character mesh as child to adjust mesh position relative to the player origin
         .with_children(|parent| {
@@ -132,3 +94,25 @@ pub fn spawn_player(
 
     Ok(())
 }
+
+/// The dead only get to use the menus
+fn lock_input_on_death(
+    on: Trigger<OnAdd, Dead>,
+    local: Query<(), With<LocalPlayer>>,
+    mut commands: Commands,
+) {
+    if local.contains(on.target()) {
+        commands.trigger(SwitchInputCtx::new(on.target(), Context::Modal));
+        commands.entity(on.target()).remove::<AttackTarget>();
+    }
+}
+
+fn unlock_input_on_respawn(
+    on: Trigger<OnRemove, Dead>,
+    local: Query<(), With<LocalPlayer>>,
+    mut commands: Commands,
+) {
+    if local.contains(on.target()) {
+        commands.trigger(SwitchInputCtx::new(on.target(), Context::Gameplay));
+    }
+}
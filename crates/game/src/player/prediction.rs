@@ -0,0 +1,89 @@
+//! Client-side prediction of the local player movement, see [`net::PredictionHistory`].
+//! The player moves locally as soon as the input is read, every input is sent to the server and
+//! the authoritative answers are reconciled with the local state.
+use super::*;
+
+pub fn plugin(app: &mut App) {
+    app.add_systems(
+        Update,
+        (
+            send_move_intent.after(TnuaUserControlsSystemSet),
+            reconcile,
+            apply_correction.after(reconcile),
+        )
+            .run_if(in_state(Screen::Gameplay)),
+    );
+}
+
+fn send_move_intent(
+    time: Res<Time>,
+    mut to_server: EventWriter<ToServer>,
+    mut player: Query<(&Player, &TnuaController, &mut PredictionHistory), With<LocalPlayer>>,
+) {
+    let dt = time.delta_secs();
+    for (player, controller, mut history) in player.iter_mut() {
+        let Some((walk, _)) = controller.concrete_basis::<TnuaBuiltinWalk>() else {
+            continue;
+        };
+
+        let velocity = walk.desired_velocity;
+        if !history.should_send(velocity) {
+            continue;
+        }
+
+        let input = history.record(velocity, dt);
+        to_server.write(ToServer(ClientMessage::MoveIntent {
+            seq: input.seq,
+            velocity,
+            dt,
+            animation: player.animation_state.clone(),
+        }));
+    }
+}
+
+fn reconcile(
+    cfg: Res<Config>,
+    mut from_server: EventReader<FromServer>,
+    mut player: Query<
+        (&Transform, &mut PredictionHistory, &mut PositionCorrection),
+        With<LocalPlayer>,
+    >,
+) {
+    for msg in from_server.read() {
+        let ServerMessage::PlayerState { ack, position } = msg.0 else {
+            continue;
+        };
+
+        for (transform, mut history, mut correction) in player.iter_mut() {
+            let predicted = history.reconcile(ack, position);
+            // height is up to physics, the server only knows about the ground plane
+            let error = (predicted - transform.translation).with_y(0.0);
+
+            correction.0 = if error.length() < cfg.net.min_correction {
+                Vec3::ZERO
+            } else {
+                error
+            };
+        }
+    }
+}
+
+fn apply_correction(
+    cfg: Res<Config>,
+    time: Res<Time>,
+    mut player: Query<(&mut Transform, &mut PositionCorrection)>,
+) {
+    for (mut transform, mut correction) in player.iter_mut() {
+        if correction.0 == Vec3::ZERO {
+            continue;
+        }
+
+        if correction.length() > cfg.net.snap_distance {
+            transform.translation += correction.0;
+            correction.0 = Vec3::ZERO;
+        } else {
+            transform.translation +=
+                correction.step(cfg.net.correction_smoothing, time.delta_secs());
+        }
+    }
+}
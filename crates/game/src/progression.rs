@@ -0,0 +1,397 @@
+//! Experience and levels: killers get the experience of the monster from the [`MonsterDb`],
+//! enough of it raises the base or job [`Level`] as set by the [`LevelTable`]. Base levels grant
+//! [`StatPoints`] and job levels [`SkillPoints`], `stats` derives the new stats on its own.
+//! Stat points are spent through [`RaiseStats`].
+
+use super::*;
+use event::types::{Died, ExpPenalty, LeveledUp, RaiseStats};
+use thiserror::Error;
+
+pub fn plugin(app: &mut App) {
+    app.register_type::<JobLevel>()
+        .register_type::<Experience>()
+        .register_type::<StatPoints>()
+        .register_type::<SkillPoints>()
+        .add_systems(
+            Update,
+            (
+                (award_exp, apply_exp_penalty, level_up, raise_stats)
+                    .chain()
+                    .before(stats::derive_attributes),
+                refill_on_level_up.after(stats::derive_attributes),
+            )
+                .run_if(in_state(Screen::Gameplay).and(resource_exists::<LevelTable>)),
+        );
+}
+
+/// Leveling up in the middle of a fight draws a light pillar around the player and plays a
+/// chime for the local one
+#[cfg(feature = "render")]
+pub fn render_plugin(app: &mut App) {
+    app.add_systems(
+        Update,
+        (spawn_level_up_pillars, fade_level_up_pillars).run_if(in_state(Screen::Gameplay)),
+    );
+}
+
+fn award_exp(
+    monster_db: Option<Res<MonsterDb>>,
+    mut deaths: EventReader<Died>,
+    monsters: Query<&MonsterId>,
+    mut players: Query<&mut Experience, Without<Dead>>,
+) {
+    let Some(monster_db) = monster_db else {
+        return;
+    };
+
+    for death in deaths.read() {
+        let Ok(mut exp) = players.get_mut(death.killer) else {
+            continue;
+        };
+        let Some(def) = monsters
+            .get(death.entity)
+            .ok()
+            .and_then(|id| monster_db.get(*id))
+        else {
+            continue;
+        };
+        if def.base_exp == 0 && def.job_exp == 0 {
+            continue;
+        }
+
+        exp.base += def.base_exp;
+        exp.job += def.job_exp;
+        info!(
+            "{} got {} base and {} job experience from {}",
+            death.killer, def.base_exp, def.job_exp, def.name
+        );
+    }
+}
+
+/// Dying costs a fraction of the experience of the next base level, never a level
+fn apply_exp_penalty(
+    levels: Res<LevelTable>,
+    mut penalties: EventReader<ExpPenalty>,
+    mut players: Query<(&Level, &mut Experience)>,
+) {
+    for penalty in penalties.read() {
+        let Ok((level, mut exp)) = players.get_mut(penalty.entity) else {
+            continue;
+        };
+        let Some(needed) = levels.base.needed(**level) else {
+            continue;
+        };
+
+        let lost = (needed as f32 * penalty.fraction) as u64;
+        exp.base = exp.base.saturating_sub(lost);
+        info!("{} lost {lost} base experience", penalty.entity);
+    }
+}
+
+/// Climbs as many levels as `exp` pays for up to `max`, returns the new level. Anything past the
+/// max level is dropped.
+fn climb(progression: &Progression, max: u32, mut level: u32, exp: &mut u64) -> u32 {
+    let max = max.min(progression.max_level());
+    while level < max
+        && let Some(needed) = progression.needed(level)
+        && *exp >= needed
+    {
+        *exp -= needed;
+        level += 1;
+    }
+    if level >= max {
+        *exp = 0;
+    }
+    level
+}
+
+fn level_up(
+    levels: Res<LevelTable>,
+    job_db: Option<Res<JobDb>>,
+    mut players: Query<
+        (
+            Entity,
+            Option<&Job>,
+            &mut Experience,
+            &mut Level,
+            &mut JobLevel,
+            &mut StatPoints,
+            &mut SkillPoints,
+        ),
+        Changed<Experience>,
+    >,
+    mut leveled_up: EventWriter<LeveledUp>,
+) {
+    for (entity, job, mut exp, mut level, mut job_level, mut stat_points, mut skill_points) in
+        &mut players
+    {
+        // the job sets its own max job level
+        let max_job_level = job
+            .zip(job_db.as_ref())
+            .and_then(|(job, job_db)| job_db.get(*job))
+            .map_or(u32::MAX, |def| def.max_job_level);
+
+        let mut remaining = *exp;
+        let base = climb(&levels.base, u32::MAX, **level, &mut remaining.base);
+        let job = climb(&levels.job, max_job_level, **job_level, &mut remaining.job);
+        // keeps this from running again next frame when nothing changed
+        exp.set_if_neq(remaining);
+
+        for reached in **level + 1..=base {
+            **stat_points += levels.base.points_at(reached);
+            leveled_up.write(LeveledUp {
+                entity,
+                kind: ExpKind::Base,
+                level: reached,
+            });
+            info!("{entity} reached base level {reached}");
+        }
+        for reached in **job_level + 1..=job {
+            **skill_points += levels.job.points_at(reached);
+            leveled_up.write(LeveledUp {
+                entity,
+                kind: ExpKind::Job,
+                level: reached,
+            });
+            info!("{entity} reached job level {reached}");
+        }
+
+        if base != **level {
+            **level = base;
+        }
+        if job != **job_level {
+            **job_level = job;
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+enum RaiseError {
+    #[error("{0:?} isn't a base attribute")]
+    NotAttribute(Stat),
+    #[error("{0:?} would go past the max")]
+    Maxed(Stat),
+    #[error("needs {needed} stat points, has {has}")]
+    NotEnoughPoints { needed: u32, has: u32 },
+}
+
+/// The attributes after every raise and what they cost
+fn raise(
+    costs: &StatCosts,
+    attribs: &Attributes,
+    points: u32,
+    raises: &[(Stat, u32)],
+) -> Result<(Attributes, u32), RaiseError> {
+    let mut raised = *attribs;
+    let mut needed = 0;
+    for &(stat, by) in raises {
+        let value = raised.get_mut(stat).ok_or(RaiseError::NotAttribute(stat))?;
+        needed += costs
+            .raise_cost(*value, by)
+            .ok_or(RaiseError::Maxed(stat))?;
+        *value += by as i32;
+    }
+
+    if needed > points {
+        return Err(RaiseError::NotEnoughPoints {
+            needed,
+            has: points,
+        });
+    }
+    Ok((raised, needed))
+}
+
+fn raise_stats(
+    levels: Res<LevelTable>,
+    mut requests: EventReader<RaiseStats>,
+    mut players: Query<(&mut Attributes, &mut StatPoints)>,
+) {
+    for request in requests.read() {
+        let Ok((mut attribs, mut points)) = players.get_mut(request.entity) else {
+            continue;
+        };
+
+        match raise(&levels.stats, &attribs, **points, &request.raises) {
+            Ok((raised, spent)) => {
+                *attribs = raised;
+                **points -= spent;
+                info!("{} spent {spent} stat points", request.entity);
+            }
+            Err(e) => warn!("{} can't raise its stats: {e}", request.entity),
+        }
+    }
+}
+
+/// A new base level comes with full hp and mp, once `stats` derived the new max values
+fn refill_on_level_up(
+    mut leveled_up: EventReader<LeveledUp>,
+    mut players: Query<&mut ComputedAttributes, Without<Dead>>,
+) {
+    for event in leveled_up.read() {
+        if event.kind != ExpKind::Base {
+            continue;
+        }
+        let Ok(mut attribs) = players.get_mut(event.entity) else {
+            continue;
+        };
+        attribs.health.hp = attribs.health.max_hp;
+        attribs.mana.mp = attribs.mana.max_mp;
+    }
+}
+
+/// Light pillar around a player that just leveled up, fading out with the timer
+#[cfg(feature = "render")]
+#[derive(Component)]
+struct LevelUpPillar(Timer);
+
+#[cfg(feature = "render")]
+fn spawn_level_up_pillars(
+    mut leveled_up: EventReader<LeveledUp>,
+    players: Query<Has<LocalPlayer>, With<Player>>,
+    state: Res<GameState>,
+    settings: Res<Settings>,
+    sources: Res<AudioSources>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut commands: Commands,
+) {
+    let mut chime = false;
+    for event in leveled_up.read() {
+        let Ok(local) = players.get(event.entity) else {
+            continue;
+        };
+        chime |= local;
+
+        // every pillar fades on its own
+        let material = materials.add(StandardMaterial {
+            base_color: Color::srgba(1.0, 0.9, 0.5, 0.6),
+            emissive: LinearRgba::rgb(4.0, 3.2, 1.2),
+            alpha_mode: AlphaMode::Blend,
+            unlit: true,
+            ..default()
+        });
+        let pillar = commands
+            .spawn((
+                Name::new("Level Up Pillar"),
+                LevelUpPillar(Timer::from_seconds(1.5, TimerMode::Once)),
+                Mesh3d(meshes.add(Cylinder::new(1.2, 6.0))),
+                MeshMaterial3d(material),
+                Transform::from_xyz(0.0, 2.0, 0.0),
+            ))
+            .id();
+        commands.entity(event.entity).add_child(pillar);
+    }
+
+    // once, gaining base and job levels together doesn't play it twice
+    if chime && !state.muted && !state.paused {
+        commands.spawn(SamplePlayer::new(sources.level_up.clone()).with_volume(settings.sfx()));
+    }
+}
+
+#[cfg(feature = "render")]
+fn fade_level_up_pillars(
+    time: Res<Time>,
+    mut pillars: Query<(
+        Entity,
+        &mut LevelUpPillar,
+        &mut Transform,
+        &MeshMaterial3d<StandardMaterial>,
+    )>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut commands: Commands,
+) {
+    for (entity, mut pillar, mut transform, material) in &mut pillars {
+        if pillar.0.tick(time.delta()).finished() {
+            materials.remove(&material.0);
+            commands.entity(entity).despawn();
+            continue;
+        }
+
+        let left = pillar.0.fraction_remaining();
+        // narrows into a beam while it fades
+        transform.scale = Vec3::new(left, 1.0, left);
+        if let Some(material) = materials.get_mut(&material.0) {
+            material.base_color.set_alpha(0.6 * left);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn progression() -> Progression {
+        Progression {
+            exp: vec![10, 20, 30],
+            start_points: 0,
+            points: 1,
+            points_every: 0,
+        }
+    }
+
+    fn costs() -> StatCosts {
+        StatCosts {
+            cost: 2,
+            cost_every: 10,
+            max: 12,
+        }
+    }
+
+    #[test]
+    fn climb_keeps_the_rest() {
+        let mut exp = 15;
+        assert_eq!(climb(&progression(), u32::MAX, 1, &mut exp), 2);
+        assert_eq!(exp, 5);
+    }
+
+    #[test]
+    fn climb_several_levels_at_once() {
+        let mut exp = 10 + 20 + 7;
+        assert_eq!(climb(&progression(), u32::MAX, 1, &mut exp), 3);
+        assert_eq!(exp, 7);
+    }
+
+    #[test]
+    fn climb_stops_at_the_max_level() {
+        let mut exp = 1000;
+        assert_eq!(climb(&progression(), u32::MAX, 1, &mut exp), 4);
+        assert_eq!(exp, 0);
+        // the job's own max job level
+        let mut exp = 1000;
+        assert_eq!(climb(&progression(), 2, 1, &mut exp), 2);
+        assert_eq!(exp, 0);
+        let mut exp = 1000;
+        assert_eq!(climb(&progression(), 4, 4, &mut exp), 4);
+        assert_eq!(exp, 0);
+    }
+
+    #[test]
+    fn raise_spends_the_points() {
+        let attribs = Attributes {
+            strength: 9,
+            ..default()
+        };
+        let raises = [(Stat::Strength, 3), (Stat::Luck, 1)];
+        let (raised, spent) = raise(&costs(), &attribs, 10, &raises).unwrap();
+        assert_eq!(raised.strength, 12);
+        assert_eq!(raised.luck, 2);
+        assert_eq!(spent, 2 + 2 + 3 + 2);
+    }
+
+    #[test]
+    fn raise_rejects() {
+        let attribs = Attributes::default();
+        let result = raise(&costs(), &attribs, 100, &[(Stat::Attack, 1)]);
+        assert!(matches!(
+            result,
+            Err(RaiseError::NotAttribute(Stat::Attack))
+        ));
+        let result = raise(&costs(), &attribs, 100, &[(Stat::Vitality, 12)]);
+        assert!(matches!(result, Err(RaiseError::Maxed(Stat::Vitality))));
+        let result = raise(&costs(), &attribs, 3, &[(Stat::Vitality, 2)]);
+        assert!(matches!(
+            result,
+            Err(RaiseError::NotEnoughPoints { needed: 4, has: 3 })
+        ));
+    }
+}
@@ -0,0 +1,587 @@
+//! Active skills: a [`CastRequest`] is checked against cooldowns, mp, target and range, then
+//! casts for the skill's cast time. Getting hit or walking away breaks an interruptible cast,
+//! a finished one spends the mp, starts the cooldowns and applies the effects through
+//! [`combat::Strikes`] like any other hit.
+//!
+//! Players cast at the level they learned, skill points buy levels of the skills in the trees
+//! of their [`Job`] through [`LearnSkill`].
+
+use super::*;
+use combat::Strikes;
+use event::types::{
+    ApplyStatus, CastInterrupted, CastRequest, DamageResult, Healed, Knockback, LearnSkill,
+    SkillCast,
+};
+use spatial::SpatialGrid;
+use thiserror::Error;
+
+/// Farther than this from where the cast started breaks it
+const MOVE_TOLERANCE: f32 = 0.5;
+
+pub fn plugin(app: &mut App) {
+    app.register_type::<Casting>()
+        .register_type::<SkillCooldowns>()
+        .register_type::<Hotbar>()
+        .register_type::<LearnedSkills>()
+        .add_systems(
+            Update,
+            (
+                (tick_cooldowns, start_casts, interrupt_casts, finish_casts).chain(),
+                learn_skills.run_if(resource_exists::<JobDb>),
+            )
+                .run_if(in_state(Screen::Gameplay).and(resource_exists::<SkillDb>)),
+        );
+}
+
+#[derive(Error, Debug)]
+enum CastError {
+    #[error("no skill with id {0} in the skill database")]
+    UnknownSkill(u32),
+    #[error("{0} isn't learned")]
+    NotLearned(String),
+    #[error("already casting")]
+    Busy,
+    #[error("can't cast right now")]
+    Locked,
+    #[error("{0} isn't ready yet")]
+    Cooldown(String),
+    #[error("not enough mp for {0}")]
+    NoMana(String),
+    #[error("no valid target for {0}")]
+    NoTarget(String),
+    #[error("out of range of {0}")]
+    OutOfRange(String),
+}
+
+fn tick_cooldowns(time: Res<Time>, mut cooldowns: Query<&mut SkillCooldowns>) {
+    for mut cooldowns in &mut cooldowns {
+        cooldowns.tick(time.delta());
+    }
+}
+
+/// What the caster is doing when it asks to cast
+struct CasterState<'a> {
+    /// Level of the skill, 0 when not learned
+    level: u32,
+    casting: bool,
+    locked: bool,
+    cooldowns: &'a SkillCooldowns,
+    mp: f32,
+}
+
+/// The skill at the caster's level, when nothing keeps it from being cast
+fn check_ready(
+    skill_db: &SkillDb,
+    skill: SkillId,
+    caster: CasterState,
+) -> Result<SkillDef, CastError> {
+    let def = skill_db.get(skill).ok_or(CastError::UnknownSkill(*skill))?;
+    if caster.level == 0 {
+        return Err(CastError::NotLearned(def.name.clone()));
+    }
+    let def = def.at_level(caster.level);
+    if caster.casting {
+        return Err(CastError::Busy);
+    }
+    if caster.locked {
+        return Err(CastError::Locked);
+    }
+    if !caster.cooldowns.ready(skill) {
+        return Err(CastError::Cooldown(def.name.clone()));
+    }
+    if caster.mp < def.mp_cost {
+        return Err(CastError::NoMana(def.name.clone()));
+    }
+    Ok(def)
+}
+
+fn start_casts(
+    skill_db: Res<SkillDb>,
+    mut requests: EventReader<CastRequest>,
+    casters: Query<
+        (
+            &Transform,
+            &ComputedAttributes,
+            &SkillCooldowns,
+            Option<&LearnedSkills>,
+            Option<&Lockouts>,
+            Has<Enemy>,
+            Has<Casting>,
+        ),
+        Without<Dead>,
+    >,
+    targets: Query<(&Transform, Has<Enemy>), (With<ComputedAttributes>, Without<Dead>)>,
+    mut commands: Commands,
+) {
+    for request in requests.read() {
+        let Ok((transform, attribs, cooldowns, learned, lockouts, is_enemy, casting)) =
+            casters.get(request.caster)
+        else {
+            continue;
+        };
+
+        let validate = || {
+            // anyone without a skill tree casts at level 1
+            let level = learned.map_or(1, |learned| learned.level(request.skill));
+            let caster = CasterState {
+                level,
+                casting,
+                locked: lockouts.is_some_and(|l| l.cast),
+                cooldowns,
+                mp: attribs.mana.mp,
+            };
+            let def = check_ready(&skill_db, request.skill, caster)?;
+
+            let point = match (def.target, request.target) {
+                (SkillTarget::SelfCast, CastTarget::Caster) => transform.translation,
+                (SkillTarget::GroundArea { .. }, CastTarget::Ground(point)) => point,
+                (SkillTarget::Single, CastTarget::Entity(entity)) => match targets.get(entity) {
+                    // players and enemies only fight each other
+                    Ok((target, target_is_enemy)) if target_is_enemy != is_enemy => {
+                        target.translation
+                    }
+                    _ => return Err(CastError::NoTarget(def.name.clone())),
+                },
+                _ => return Err(CastError::NoTarget(def.name.clone())),
+            };
+            if transform.translation.xz().distance(point.xz()) > def.range {
+                return Err(CastError::OutOfRange(def.name.clone()));
+            }
+
+            Ok((def, level))
+        };
+
+        match validate() {
+            Ok((def, level)) => {
+                commands.entity(request.caster).insert(Casting {
+                    skill: request.skill,
+                    level,
+                    target: request.target,
+                    timer: Timer::from_seconds(def.cast_time, TimerMode::Once),
+                    origin: transform.translation,
+                });
+            }
+            Err(e) => info!("{} can't cast: {e}", request.caster),
+        }
+    }
+}
+
+/// Hits and steps away break the casts that allow it, the dead and the stunned stop casting
+/// altogether
+fn interrupt_casts(
+    skill_db: Res<SkillDb>,
+    mut damage_results: EventReader<DamageResult>,
+    casters: Query<(Entity, &Casting, &Transform, Option<&Lockouts>, Has<Dead>)>,
+    mut interrupted: EventWriter<CastInterrupted>,
+    mut commands: Commands,
+) {
+    let hit: Vec<Entity> = damage_results
+        .read()
+        .filter(|hit| hit.amount > 0.0)
+        .map(|hit| hit.target)
+        .collect();
+
+    for (entity, casting, transform, lockouts, dead) in &casters {
+        let interruptible = skill_db
+            .get(casting.skill)
+            .is_none_or(|def| def.interruptible);
+        let moved = transform.translation.xz().distance(casting.origin.xz()) > MOVE_TOLERANCE;
+
+        let locked = dead || lockouts.is_some_and(|l| l.cast);
+        if locked || (interruptible && (moved || hit.contains(&entity))) {
+            commands.entity(entity).remove::<Casting>();
+            interrupted.write(CastInterrupted {
+                caster: entity,
+                skill: casting.skill,
+            });
+        }
+    }
+}
+
+/// Spends the mp, starts the cooldowns and applies the effects of every cast that's done
+pub(crate) fn finish_casts(
+    time: Res<Time>,
+    skill_db: Res<SkillDb>,
+    grid: Res<SpatialGrid>,
+    mut casters: Query<(Entity, &mut Casting, &mut SkillCooldowns), Without<Dead>>,
+    mut combatants: Query<(&mut ComputedAttributes, Has<Enemy>), Without<Dead>>,
+    mut strikes: Strikes,
+    mut heals: EventWriter<Healed>,
+    mut statuses: EventWriter<ApplyStatus>,
+    mut knockbacks: EventWriter<Knockback>,
+    mut skill_casts: EventWriter<SkillCast>,
+    mut commands: Commands,
+) {
+    for (caster, mut casting, mut cooldowns) in &mut casters {
+        if !casting.timer.tick(time.delta()).finished() {
+            continue;
+        }
+        commands.entity(caster).remove::<Casting>();
+
+        let Some(def) = skill_db
+            .get(casting.skill)
+            .map(|def| def.at_level(casting.level))
+        else {
+            continue;
+        };
+        let Ok((mut caster_attribs, caster_is_enemy)) = combatants.get_mut(caster) else {
+            continue;
+        };
+        // mp may have gone somewhere else while casting
+        if caster_attribs.mana.mp < def.mp_cost {
+            continue;
+        }
+        caster_attribs.mana.mp -= def.mp_cost;
+        let caster_attribs = *caster_attribs;
+        cooldowns.start(casting.skill, &def);
+
+        let targets: Vec<Entity> = match (def.target, casting.target) {
+            (SkillTarget::GroundArea { radius }, CastTarget::Ground(point)) => grid
+                .within(point.xz(), radius)
+                .map(|(entity, _)| entity)
+                .filter(|entity| {
+                    combatants
+                        .get(*entity)
+                        .is_ok_and(|(_, is_enemy)| is_enemy != caster_is_enemy)
+                })
+                .collect(),
+            (_, CastTarget::Entity(entity)) => vec![entity],
+            _ => vec![caster],
+        };
+
+        for target in targets {
+            let Ok((mut target_attribs, _)) = combatants.get_mut(target) else {
+                continue;
+            };
+
+            for effect in &def.effects {
+                match *effect {
+                    SkillEffect::Damage {
+                        kind,
+                        multiplier,
+                        element,
+                    } => {
+                        strikes.strike(
+                            (caster, &caster_attribs),
+                            (target, &mut target_attribs),
+                            kind,
+                            element,
+                            multiplier,
+                        );
+                    }
+                    SkillEffect::Heal { base, magic_attack } => {
+                        let health = &mut target_attribs.health;
+                        let amount = (base + magic_attack * caster_attribs.magic_attack)
+                            .min(health.max_hp - health.hp)
+                            .max(0.0);
+                        health.hp += amount;
+                        heals.write(Healed {
+                            healer: caster,
+                            target,
+                            amount,
+                        });
+                    }
+                    SkillEffect::Status {
+                        status,
+                        chance,
+                        duration,
+                    } => {
+                        statuses.write(ApplyStatus {
+                            target,
+                            status: StatusId(status),
+                            source: caster,
+                            chance,
+                            duration,
+                        });
+                    }
+                    SkillEffect::Knockback { force } => {
+                        knockbacks.write(Knockback {
+                            target,
+                            source: caster,
+                            force,
+                        });
+                    }
+                }
+            }
+        }
+
+        info!("{caster} cast {}", def.name);
+        skill_casts.write(SkillCast {
+            caster,
+            skill: casting.skill,
+            target: casting.target,
+        });
+    }
+}
+
+#[derive(Error, Debug)]
+enum LearnError {
+    #[error("no skill with id {0} in the skill database")]
+    UnknownSkill(u32),
+    #[error("{0} isn't in the skill tree of the job")]
+    NotInTree(String),
+    #[error("{0} is at its max level")]
+    Maxed(String),
+    #[error("needs {0} at level {1} first")]
+    Prerequisite(String, u32),
+    #[error("no skill points left")]
+    NoPoints,
+}
+
+fn check_learn(
+    skill_db: &SkillDb,
+    job_db: &JobDb,
+    (job, learned, points): (Job, &LearnedSkills, u32),
+    skill: SkillId,
+) -> Result<(), LearnError> {
+    let def = skill_db
+        .get(skill)
+        .ok_or(LearnError::UnknownSkill(*skill))?;
+    let tree_skill = job_db
+        .tree_skill(job, skill)
+        .ok_or(LearnError::NotInTree(def.name.clone()))?;
+    if learned.level(skill) >= def.max_level {
+        return Err(LearnError::Maxed(def.name.clone()));
+    }
+    for &(required, level) in &tree_skill.requires {
+        if learned.level(SkillId(required)) < level {
+            let name = skill_db
+                .get(SkillId(required))
+                .map_or_else(|| format!("skill {required}"), |def| def.name.clone());
+            return Err(LearnError::Prerequisite(name, level));
+        }
+    }
+    if points == 0 {
+        return Err(LearnError::NoPoints);
+    }
+    Ok(())
+}
+
+fn learn_skills(
+    skill_db: Res<SkillDb>,
+    job_db: Res<JobDb>,
+    mut requests: EventReader<LearnSkill>,
+    mut players: Query<(&Job, &mut LearnedSkills, &mut SkillPoints)>,
+) {
+    for request in requests.read() {
+        let Ok((job, mut learned, mut points)) = players.get_mut(request.entity) else {
+            continue;
+        };
+        let player = (*job, learned.as_ref(), **points);
+        if let Err(e) = check_learn(&skill_db, &job_db, player, request.skill) {
+            info!("{} can't learn: {e}", request.entity);
+            continue;
+        }
+
+        **points -= 1;
+        let level = learned.0.entry(request.skill).or_default();
+        *level += 1;
+        info!(
+            "{} learned {:?} level {level}",
+            request.entity, request.skill
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{collections::HashMap, time::Duration};
+
+    const BASH: SkillId = SkillId(1);
+    const MAGNUM_BREAK: SkillId = SkillId(2);
+
+    fn skill_db() -> SkillDb {
+        let bash = SkillDef {
+            name: "Bash".into(),
+            max_level: 3,
+            per_level: LevelScaling {
+                mp_cost: 5.0,
+                ..default()
+            },
+            mp_cost: 10.0,
+            cast_time: 0.0,
+            cooldown: 1.0,
+            after_cast_delay: 0.5,
+            range: 2.0,
+            target: SkillTarget::Single,
+            effects: vec![],
+            interruptible: true,
+        };
+        let magnum_break = SkillDef {
+            name: "Magnum Break".into(),
+            max_level: 1,
+            ..bash.clone()
+        };
+        SkillDb {
+            skills: HashMap::from([(*BASH, bash), (*MAGNUM_BREAK, magnum_break)]),
+        }
+    }
+
+    fn job(change: Option<Job>, skills: Vec<TreeSkill>) -> JobDef {
+        JobDef {
+            name: "Job".into(),
+            change: change.map(|from| JobChange {
+                from,
+                job_level: 10,
+            }),
+            max_job_level: 50,
+            hp_per_level: 0.0,
+            mp_per_level: 0.0,
+            bonuses: vec![],
+            weapons: vec![],
+            skills,
+        }
+    }
+
+    /// Novices learn Bash, swordsmen learn Magnum Break once Bash is at level 2
+    fn job_db() -> JobDb {
+        let bash = TreeSkill {
+            skill: *BASH,
+            at: (0, 0),
+            requires: vec![],
+        };
+        let magnum_break = TreeSkill {
+            skill: *MAGNUM_BREAK,
+            at: (0, 1),
+            requires: vec![(*BASH, 2)],
+        };
+        JobDb {
+            jobs: HashMap::from([
+                (Job::Novice, job(None, vec![bash])),
+                (Job::Swordsman, job(Some(Job::Novice), vec![magnum_break])),
+            ]),
+        }
+    }
+
+    fn learned(skills: &[(SkillId, u32)]) -> LearnedSkills {
+        LearnedSkills(skills.iter().copied().collect())
+    }
+
+    fn caster(level: u32, cooldowns: &SkillCooldowns, mp: f32) -> CasterState<'_> {
+        CasterState {
+            level,
+            casting: false,
+            locked: false,
+            cooldowns,
+            mp,
+        }
+    }
+
+    #[test]
+    fn cast_when_ready() {
+        let cooldowns = SkillCooldowns::default();
+        let def = check_ready(&skill_db(), BASH, caster(2, &cooldowns, 15.0)).unwrap();
+        assert_eq!(def.mp_cost, 15.0);
+    }
+
+    #[test]
+    fn not_enough_mp() {
+        let cooldowns = SkillCooldowns::default();
+        // level 1 costs 10, level 3 costs 20
+        assert!(check_ready(&skill_db(), BASH, caster(1, &cooldowns, 15.0)).is_ok());
+        let result = check_ready(&skill_db(), BASH, caster(3, &cooldowns, 15.0));
+        assert!(matches!(result, Err(CastError::NoMana(_))));
+    }
+
+    #[test]
+    fn waits_for_the_cooldowns() {
+        let skill_db = skill_db();
+        let mut cooldowns = SkillCooldowns::default();
+        cooldowns.start(BASH, skill_db.get(BASH).unwrap());
+        let result = check_ready(&skill_db, BASH, caster(1, &cooldowns, 100.0));
+        assert!(matches!(result, Err(CastError::Cooldown(_))));
+
+        // the after-cast delay is over, the skill's own cooldown isn't
+        cooldowns.tick(Duration::from_secs_f32(0.5));
+        let result = check_ready(&skill_db, BASH, caster(1, &cooldowns, 100.0));
+        assert!(matches!(result, Err(CastError::Cooldown(_))));
+
+        cooldowns.tick(Duration::from_secs_f32(0.5));
+        assert!(check_ready(&skill_db, BASH, caster(1, &cooldowns, 100.0)).is_ok());
+    }
+
+    #[test]
+    fn busy_locked_or_unlearned() {
+        let skill_db = skill_db();
+        let cooldowns = SkillCooldowns::default();
+        let unlearned = check_ready(&skill_db, BASH, caster(0, &cooldowns, 100.0));
+        assert!(matches!(unlearned, Err(CastError::NotLearned(_))));
+        let busy = CasterState {
+            casting: true,
+            ..caster(1, &cooldowns, 100.0)
+        };
+        assert!(matches!(
+            check_ready(&skill_db, BASH, busy),
+            Err(CastError::Busy)
+        ));
+        let locked = CasterState {
+            locked: true,
+            ..caster(1, &cooldowns, 100.0)
+        };
+        assert!(matches!(
+            check_ready(&skill_db, BASH, locked),
+            Err(CastError::Locked)
+        ));
+        let unknown = check_ready(&skill_db, SkillId(9), caster(1, &cooldowns, 100.0));
+        assert!(matches!(unknown, Err(CastError::UnknownSkill(9))));
+    }
+
+    #[test]
+    fn learn_with_a_point() {
+        let none = learned(&[]);
+        let result = check_learn(&skill_db(), &job_db(), (Job::Novice, &none, 1), BASH);
+        assert!(result.is_ok());
+        let result = check_learn(&skill_db(), &job_db(), (Job::Novice, &none, 0), BASH);
+        assert!(matches!(result, Err(LearnError::NoPoints)));
+    }
+
+    #[test]
+    fn only_from_the_tree_of_the_job() {
+        let none = learned(&[]);
+        let result = check_learn(
+            &skill_db(),
+            &job_db(),
+            (Job::Novice, &none, 1),
+            MAGNUM_BREAK,
+        );
+        assert!(matches!(result, Err(LearnError::NotInTree(_))));
+        // the trees of the previous jobs carry over
+        let result = check_learn(&skill_db(), &job_db(), (Job::Swordsman, &none, 1), BASH);
+        assert!(result.is_ok());
+        let result = check_learn(&skill_db(), &job_db(), (Job::Novice, &none, 1), SkillId(9));
+        assert!(matches!(result, Err(LearnError::UnknownSkill(9))));
+    }
+
+    #[test]
+    fn prerequisites_first() {
+        let skill_db = skill_db();
+        let job_db = job_db();
+        let bash_1 = learned(&[(BASH, 1)]);
+        let result = check_learn(
+            &skill_db,
+            &job_db,
+            (Job::Swordsman, &bash_1, 1),
+            MAGNUM_BREAK,
+        );
+        assert!(matches!(result, Err(LearnError::Prerequisite(name, 2)) if name == "Bash"));
+        let bash_2 = learned(&[(BASH, 2)]);
+        let result = check_learn(
+            &skill_db,
+            &job_db,
+            (Job::Swordsman, &bash_2, 1),
+            MAGNUM_BREAK,
+        );
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn not_past_the_max_level() {
+        let bash_2 = learned(&[(BASH, 2)]);
+        let result = check_learn(&skill_db(), &job_db(), (Job::Novice, &bash_2, 1), BASH);
+        assert!(result.is_ok());
+        let bash_3 = learned(&[(BASH, 3)]);
+        let result = check_learn(&skill_db(), &job_db(), (Job::Novice, &bash_3, 1), BASH);
+        assert!(matches!(result, Err(LearnError::Maxed(_))));
+    }
+}
@@ -49,8 +49,7 @@ fn movement_sound(
     state: Res<GameState>,
     settings: Res<Settings>,
     sources: ResMut<AudioSources>,
-    tnua: Query<&TnuaController, With<Player>>,
-    actions: Single<&Actions<GameplayCtx>>,
+    tnua: Query<(&TnuaController, &Actions<GameplayCtx>), With<Player>>,
     mut cmds: Commands,
     mut step_timer: Query<&mut StepTimer, With<Player>>,
 ) -> Result {
@@ -58,7 +57,7 @@ fn movement_sound(
         return Ok(());
     }
 
-    let controller = tnua.get(on.target())?;
+    let (controller, actions) = tnua.get(on.target())?;
     let mut step_timer = step_timer.get_mut(on.target())?;
 
     let Some((_, basis)) = controller.concrete_basis::<TnuaBuiltinWalk>() else {
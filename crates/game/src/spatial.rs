@@ -0,0 +1,222 @@
+//! Uniform grid over the ground plane for radius and nearest-entity queries, so proximity,
+//! click targeting and area effects only look at the cells around a point instead of
+//! every player and enemy in the world.
+
+use super::*;
+use bevy::transform::TransformSystem;
+use std::collections::HashMap;
+
+/// Side of a cell in world units, about half the usual aggro radius
+const CELL_SIZE: f32 = 8.0;
+
+pub fn plugin(app: &mut App) {
+    app.init_resource::<SpatialGrid>().add_systems(
+        PostUpdate,
+        (forget_removed, index_moved)
+            .chain()
+            .after(TransformSystem::TransformPropagate),
+    );
+}
+
+/// Players and enemies by cell, positions on the XZ plane as of the last transform propagation
+#[derive(Resource, Debug)]
+pub struct SpatialGrid {
+    cell_size: f32,
+    cells: HashMap<IVec2, Vec<Entity>>,
+    entries: HashMap<Entity, (IVec2, Vec2)>,
+}
+
+impl Default for SpatialGrid {
+    fn default() -> Self {
+        Self::new(CELL_SIZE)
+    }
+}
+
+impl SpatialGrid {
+    pub fn new(cell_size: f32) -> Self {
+        Self {
+            cell_size,
+            cells: HashMap::new(),
+            entries: HashMap::new(),
+        }
+    }
+
+    fn cell(&self, position: Vec2) -> IVec2 {
+        (position / self.cell_size).floor().as_ivec2()
+    }
+
+    /// Adds the entity or moves it to its new position
+    pub fn insert(&mut self, entity: Entity, position: Vec2) {
+        let cell = self.cell(position);
+        if let Some((old_cell, old_position)) = self.entries.get_mut(&entity) {
+            *old_position = position;
+            if *old_cell == cell {
+                return;
+            }
+            let old_cell = std::mem::replace(old_cell, cell);
+            self.remove_from_cell(old_cell, entity);
+        } else {
+            self.entries.insert(entity, (cell, position));
+        }
+        self.cells.entry(cell).or_default().push(entity);
+    }
+
+    pub fn remove(&mut self, entity: Entity) {
+        if let Some((cell, _)) = self.entries.remove(&entity) {
+            self.remove_from_cell(cell, entity);
+        }
+    }
+
+    fn remove_from_cell(&mut self, cell: IVec2, entity: Entity) {
+        let Some(entities) = self.cells.get_mut(&cell) else {
+            return;
+        };
+        entities.retain(|e| *e != entity);
+        if entities.is_empty() {
+            self.cells.remove(&cell);
+        }
+    }
+
+    pub fn position(&self, entity: Entity) -> Option<Vec2> {
+        self.entries.get(&entity).map(|(_, position)| *position)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Every entity within `radius` of `center` with its distance, in no particular order
+    pub fn within(&self, center: Vec2, radius: f32) -> impl Iterator<Item = (Entity, f32)> + '_ {
+        let min = self.cell(center - Vec2::splat(radius));
+        let max = self.cell(center + Vec2::splat(radius));
+
+        (min.x..=max.x)
+            .flat_map(move |x| (min.y..=max.y).map(move |y| IVec2::new(x, y)))
+            .filter_map(move |cell| self.cells.get(&cell))
+            .flatten()
+            .filter_map(move |entity| {
+                let distance = self.entries.get(entity)?.1.distance(center);
+                (distance <= radius).then_some((*entity, distance))
+            })
+    }
+
+    /// The closest entity within `radius` of `center` that passes `filter`
+    pub fn nearest(
+        &self,
+        center: Vec2,
+        radius: f32,
+        mut filter: impl FnMut(Entity) -> bool,
+    ) -> Option<(Entity, f32)> {
+        self.within(center, radius)
+            .filter(|(entity, _)| filter(*entity))
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))
+    }
+}
+
+fn index_moved(
+    mut grid: ResMut<SpatialGrid>,
+    moved: Query<
+        (Entity, &GlobalTransform),
+        (Or<(With<Player>, With<Enemy>)>, Changed<GlobalTransform>),
+    >,
+) {
+    for (entity, transform) in &moved {
+        grid.insert(entity, transform.translation().xz());
+    }
+}
+
+fn forget_removed(
+    mut grid: ResMut<SpatialGrid>,
+    mut players: RemovedComponents<Player>,
+    mut enemies: RemovedComponents<Enemy>,
+) {
+    for entity in players.read().chain(enemies.read()) {
+        grid.remove(entity);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entity(index: u32) -> Entity {
+        Entity::from_raw(index)
+    }
+
+    /// Sorted so the order of the cells doesn't matter
+    fn found(grid: &SpatialGrid, center: Vec2, radius: f32) -> Vec<Entity> {
+        let mut found: Vec<_> = grid.within(center, radius).map(|(e, _)| e).collect();
+        found.sort();
+        found
+    }
+
+    #[test]
+    fn cell_boundaries() {
+        let grid = SpatialGrid::new(8.0);
+        assert_eq!(grid.cell(Vec2::new(0.0, 0.0)), IVec2::ZERO);
+        assert_eq!(grid.cell(Vec2::new(7.99, 7.99)), IVec2::ZERO);
+        assert_eq!(grid.cell(Vec2::new(8.0, 0.0)), IVec2::new(1, 0));
+        assert_eq!(grid.cell(Vec2::new(-0.01, 0.0)), IVec2::new(-1, 0));
+        assert_eq!(grid.cell(Vec2::new(-8.0, -8.01)), IVec2::new(-1, -2));
+    }
+
+    #[test]
+    fn within_filters_by_radius_across_cells() {
+        let mut grid = SpatialGrid::new(8.0);
+        // same cell, next cell over and out of reach
+        grid.insert(entity(1), Vec2::new(1.0, 1.0));
+        grid.insert(entity(2), Vec2::new(9.0, 1.0));
+        grid.insert(entity(3), Vec2::new(-4.0, 1.0));
+        grid.insert(entity(4), Vec2::new(30.0, 1.0));
+        let center = Vec2::new(3.0, 1.0);
+
+        assert_eq!(found(&grid, center, 6.0), [entity(1), entity(2)]);
+        assert_eq!(found(&grid, center, 7.0), [entity(1), entity(2), entity(3)]);
+        // the cell is in range, the entity in it isn't
+        assert_eq!(found(&grid, center, 5.9), [entity(1)]);
+
+        let (_, distance) = grid
+            .within(center, 6.0)
+            .find(|(e, _)| *e == entity(2))
+            .unwrap();
+        assert!((distance - 6.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn moves_between_cells() {
+        let mut grid = SpatialGrid::new(8.0);
+        grid.insert(entity(1), Vec2::new(1.0, 1.0));
+        grid.insert(entity(1), Vec2::new(2.0, 1.0));
+        assert_eq!(grid.len(), 1);
+        assert_eq!(grid.position(entity(1)), Some(Vec2::new(2.0, 1.0)));
+
+        grid.insert(entity(1), Vec2::new(20.0, 1.0));
+        assert_eq!(grid.len(), 1);
+        assert!(found(&grid, Vec2::new(1.0, 1.0), 2.0).is_empty());
+        assert_eq!(found(&grid, Vec2::new(20.0, 1.0), 1.0), [entity(1)]);
+        // the old cell is gone once empty
+        assert!(!grid.cells.contains_key(&IVec2::ZERO));
+
+        grid.remove(entity(1));
+        assert!(grid.is_empty());
+        assert!(grid.cells.is_empty());
+    }
+
+    #[test]
+    fn nearest_passing_the_filter() {
+        let mut grid = SpatialGrid::new(8.0);
+        grid.insert(entity(1), Vec2::new(1.0, 0.0));
+        grid.insert(entity(2), Vec2::new(3.0, 0.0));
+        grid.insert(entity(3), Vec2::new(12.0, 0.0));
+
+        let nearest = |filter: fn(Entity) -> bool| grid.nearest(Vec2::ZERO, 10.0, filter);
+        assert_eq!(nearest(|_| true).map(|(e, _)| e), Some(entity(1)));
+        assert_eq!(nearest(|e| e != entity(1)).map(|(e, _)| e), Some(entity(2)));
+        // out of the radius
+        assert_eq!(nearest(|e| e == entity(3)), None);
+    }
+}
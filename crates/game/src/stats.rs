@@ -0,0 +1,138 @@
+//! Derives [`ComputedAttributes`] from [`Attributes`], [`Level`] and [`StatModifiers`]
+//! using the [`StatFormulas`] table, and regenerates mp at the derived rate.
+
+use super::*;
+use combat::AttackRateTimer;
+
+pub fn plugin(app: &mut App) {
+    app.add_systems(
+        Update,
+        (
+            derive_attributes.run_if(resource_exists::<StatFormulas>),
+            regen_mana.after(derive_attributes),
+        ),
+    );
+}
+
+/// The dead don't regenerate
+fn regen_mana(time: Res<Time>, mut query: Query<&mut ComputedAttributes, Without<Dead>>) {
+    for mut computed in &mut query {
+        let mana = &computed.mana;
+        if mana.mp >= mana.max_mp || computed.mp_regen <= 0.0 {
+            continue;
+        }
+        let mp = (mana.mp + computed.mp_regen * time.delta_secs()).min(mana.max_mp);
+        computed.mana.mp = mp;
+    }
+}
+
+pub(crate) fn derive_attributes(
+    formulas: Res<StatFormulas>,
+    mut query: Query<(
+        Ref<Attributes>,
+        Ref<Level>,
+        Ref<StatModifiers>,
+        Option<Ref<StatOverrides>>,
+        &mut ComputedAttributes,
+        Has<Enemy>,
+        Option<&mut AttackRateTimer>,
+        Option<&mut Player>,
+    )>,
+) {
+    // a hot reloaded table touches everyone
+    let reloaded = formulas.is_changed();
+
+    for (attribs, level, modifiers, overrides, mut computed, is_enemy, attack_timer, player) in
+        &mut query
+    {
+        let dirty = attribs.is_changed()
+            || level.is_changed()
+            || modifiers.is_changed()
+            || overrides.as_ref().is_some_and(|o| o.is_changed());
+        if !reloaded && !dirty {
+            continue;
+        }
+
+        let set = if is_enemy {
+            &formulas.enemy
+        } else {
+            &formulas.player
+        };
+        let spawned = computed.is_added();
+
+        *computed = compute(
+            set,
+            &attribs,
+            **level,
+            &modifiers,
+            overrides.as_deref(),
+            &computed,
+        );
+
+        if spawned {
+            computed.health.hp = computed.health.max_hp;
+            computed.mana.mp = computed.mana.max_mp;
+        }
+
+        if let Some(mut attack_timer) = attack_timer {
+            attack_timer.set_rate(computed.attack_rate);
+        }
+
+        // sprinting or crouching players keep their gait at the new speed
+        if let Some(mut player) = player {
+            player.update_speed(computed.move_speed);
+        }
+    }
+}
+
+/// Derived stats of one entity. Current hp/mp and the weapon range are kept from `previous`.
+pub fn compute(
+    set: &FormulaSet,
+    attribs: &Attributes,
+    level: u32,
+    modifiers: &StatModifiers,
+    overrides: Option<&StatOverrides>,
+    previous: &ComputedAttributes,
+) -> ComputedAttributes {
+    // equipment and statuses may raise the base attributes the formulas read
+    let mut attribs = *attribs;
+    for stat in Stat::BASE {
+        if let Some(value) = attribs.get_mut(stat) {
+            *value = modifiers.apply(stat, *value as f32).round() as i32;
+        }
+    }
+
+    let derive = |stat: Stat, formula: &Formula| {
+        let base = overrides
+            .and_then(|o| o.0.get(&stat).copied())
+            .unwrap_or_else(|| formula.eval(&attribs, level));
+        modifiers.apply(stat, base)
+    };
+
+    let max_hp = derive(Stat::MaxHp, &set.max_hp).max(1.0);
+    let max_mp = derive(Stat::MaxMp, &set.max_mp).max(0.0);
+
+    ComputedAttributes {
+        attack: derive(Stat::Attack, &set.attack),
+        defence: derive(Stat::Defence, &set.defence),
+        magic_defence: derive(Stat::MagicDefence, &set.magic_defence),
+        magic_attack: derive(Stat::MagicAttack, &set.magic_attack),
+        hit_rate: derive(Stat::HitRate, &set.hit_rate),
+        dodge_rate: derive(Stat::DodgeRate, &set.dodge_rate),
+        crit_rate: derive(Stat::CritRate, &set.crit_rate),
+        attack_rate: derive(Stat::AttackRate, &set.attack_rate),
+        attack_range: previous.attack_range,
+        move_speed: derive(Stat::MoveSpeed, &set.move_speed),
+        weight_limit: derive(Stat::WeightLimit, &set.weight_limit).max(0.0),
+        mp_regen: derive(Stat::MpRegen, &set.mp_regen).max(0.0),
+
+        health: Health {
+            max_hp,
+            hp: previous.health.hp.min(max_hp),
+        },
+        mana: Mana {
+            max_mp,
+            mp: previous.mana.mp.min(max_mp),
+        },
+    }
+}
@@ -0,0 +1,257 @@
+//! Status effects: timed statuses from the [`StatusDb`] with stat modifiers, hp ticks and
+//! lockouts. Modifiers go through [`StatModifiers`] under [`StatusId::source`] so `stats`
+//! derives them into [`ComputedAttributes`], lockouts are merged into [`Lockouts`] for control,
+//! AI, combat and skills to respect.
+
+use super::*;
+use combat::CombatRng;
+use event::types::{ApplyStatus, DamageResult, StatusEnded};
+use rand::Rng;
+
+pub fn plugin(app: &mut App) {
+    app.register_type::<StatusEffects>()
+        .register_type::<Lockouts>()
+        .add_observer(clear_on_death)
+        .add_systems(
+            Update,
+            // a hit breaks statuses applied before it, not the freeze it applies itself
+            (break_on_hit, apply_statuses, tick_statuses, update_lockouts)
+                .chain()
+                .after(skills::finish_casts)
+                .run_if(in_state(Screen::Gameplay).and(resource_exists::<StatusDb>)),
+        );
+}
+
+/// Puts the modifiers of `status` back on, once per stack
+fn sync_modifiers(modifiers: &mut StatModifiers, def: &StatusDef, status: Option<&ActiveStatus>) {
+    let Some(status) = status else {
+        return;
+    };
+    let source = status.id.source();
+    modifiers.remove_source(&source);
+
+    let stacks = status.stacks as f32;
+    for (stat, kind) in &def.modifiers {
+        let kind = match *kind {
+            ModifierKind::Flat(value) => ModifierKind::Flat(value * stacks),
+            ModifierKind::Percent(value) => ModifierKind::Percent(value * stacks),
+        };
+        modifiers.add(StatModifier {
+            source: source.clone(),
+            stat: *stat,
+            kind,
+        });
+    }
+}
+
+fn apply_statuses(
+    status_db: Res<StatusDb>,
+    rng: Option<ResMut<CombatRng>>,
+    mut requests: EventReader<ApplyStatus>,
+    mut targets: Query<(&mut StatusEffects, &mut StatModifiers), Without<Dead>>,
+) {
+    let Some(mut rng) = rng else {
+        return;
+    };
+
+    for request in requests.read() {
+        let Ok((mut effects, mut modifiers)) = targets.get_mut(request.target) else {
+            continue;
+        };
+        let Some(def) = status_db.get(request.status) else {
+            warn!(
+                "no status with id {} in the status database",
+                *request.status
+            );
+            continue;
+        };
+        if effects.is_immune(request.status) || rng.0.r#gen::<f32>() >= request.chance {
+            continue;
+        }
+
+        let duration = request.duration.unwrap_or(def.duration);
+        if !effects.apply(request.status, def, duration, request.source) {
+            continue;
+        }
+
+        sync_modifiers(&mut modifiers, def, effects.get(request.status));
+        info!("{} got {}", request.target, def.name);
+    }
+}
+
+/// Hp after `times` ticks of `tick` at `stacks`, ticks wear down to 1 hp at worst
+fn tick_hp(health: Health, tick: StatusTick, stacks: u32, times: u32) -> f32 {
+    let amount = (tick.hp + tick.max_hp * health.max_hp) * (stacks * times) as f32;
+    (health.hp + amount)
+        .max(health.hp.min(1.0))
+        .min(health.max_hp)
+}
+
+/// Runs the hp ticks, ends the statuses that ran out and forgets old immunities
+fn tick_statuses(
+    time: Res<Time>,
+    status_db: Res<StatusDb>,
+    mut targets: Query<
+        (
+            Entity,
+            &mut StatusEffects,
+            &mut StatModifiers,
+            &mut ComputedAttributes,
+        ),
+        Without<Dead>,
+    >,
+    mut ended: EventWriter<StatusEnded>,
+) {
+    for (entity, mut effects, mut modifiers, mut attribs) in &mut targets {
+        if effects.active.is_empty() && effects.immune.is_empty() {
+            continue;
+        }
+        let effects = effects.as_mut();
+
+        effects.advance(time.delta());
+        for status in &mut effects.active {
+            let (Some(timer), Some(def)) = (status.tick.as_mut(), status_db.get(status.id)) else {
+                continue;
+            };
+            let Some(tick) = def.tick else {
+                continue;
+            };
+
+            let ticks = timer.tick(time.delta()).times_finished_this_tick();
+            if ticks == 0 {
+                continue;
+            }
+            attribs.health.hp = tick_hp(attribs.health, tick, status.stacks, ticks);
+        }
+
+        for status in effects.take_where(|status| status.timer.finished()) {
+            modifiers.remove_source(&status.id.source());
+            if let Some(def) = status_db.get(status.id) {
+                effects.make_immune(status.id, def.immunity);
+                info!("{entity} is no longer under {}", def.name);
+            }
+            ended.write(StatusEnded {
+                entity,
+                status: status.id,
+            });
+        }
+    }
+}
+
+/// Landed hits end the statuses that break on hit, freeze mostly
+fn break_on_hit(
+    status_db: Res<StatusDb>,
+    mut damage_results: EventReader<DamageResult>,
+    mut targets: Query<(&mut StatusEffects, &mut StatModifiers)>,
+    mut ended: EventWriter<StatusEnded>,
+) {
+    for hit in damage_results.read() {
+        if hit.amount <= 0.0 {
+            continue;
+        }
+        let Ok((mut effects, mut modifiers)) = targets.get_mut(hit.target) else {
+            continue;
+        };
+
+        let breaks =
+            |status: &ActiveStatus| status_db.get(status.id).is_some_and(|def| def.break_on_hit);
+        if !effects.active.iter().any(breaks) {
+            continue;
+        }
+
+        for status in effects.take_where(breaks) {
+            modifiers.remove_source(&status.id.source());
+            ended.write(StatusEnded {
+                entity: hit.target,
+                status: status.id,
+            });
+        }
+    }
+}
+
+/// An overweight [`Burden`] locks out like a status does
+fn update_lockouts(
+    status_db: Res<StatusDb>,
+    mut targets: Query<
+        (&StatusEffects, Option<&Burden>, &mut Lockouts),
+        Or<(Changed<StatusEffects>, Changed<Burden>)>,
+    >,
+) {
+    for (effects, burden, mut lockouts) in &mut targets {
+        let burden = burden.copied().unwrap_or_default();
+        let merged = effects
+            .active
+            .iter()
+            .filter_map(|status| status_db.get(status.id))
+            .fold(burden.lockouts(), |merged, def| merged.merge(def.lockouts));
+
+        if *lockouts != merged {
+            *lockouts = merged;
+        }
+    }
+}
+
+/// The dead lose every status, immunities included, their [`Burden`] stays
+fn clear_on_death(
+    on: Trigger<OnAdd, Dead>,
+    mut targets: Query<(
+        &mut StatusEffects,
+        &mut StatModifiers,
+        &mut Lockouts,
+        Option<&Burden>,
+    )>,
+    mut ended: EventWriter<StatusEnded>,
+) {
+    let Ok((mut effects, mut modifiers, mut lockouts, burden)) = targets.get_mut(on.target())
+    else {
+        return;
+    };
+
+    for status in effects.active.drain(..) {
+        modifiers.remove_source(&status.id.source());
+        ended.write(StatusEnded {
+            entity: on.target(),
+            status: status.id,
+        });
+    }
+    effects.immune.clear();
+    *lockouts = burden.copied().unwrap_or_default().lockouts();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tick(hp: f32, max_hp: f32) -> StatusTick {
+        StatusTick {
+            interval: 1.0,
+            hp,
+            max_hp,
+        }
+    }
+
+    fn health(hp: f32) -> Health {
+        Health { max_hp: 100.0, hp }
+    }
+
+    #[test]
+    fn ticks_scale_with_stacks_and_count() {
+        assert_eq!(tick_hp(health(80.0), tick(-5.0, 0.0), 1, 1), 75.0);
+        assert_eq!(tick_hp(health(80.0), tick(-5.0, 0.0), 2, 3), 50.0);
+        // flat and a fraction of max hp together
+        assert_eq!(tick_hp(health(80.0), tick(-5.0, -0.05), 1, 2), 60.0);
+    }
+
+    #[test]
+    fn ticks_never_kill() {
+        assert_eq!(tick_hp(health(8.0), tick(-5.0, 0.0), 2, 1), 1.0);
+        // already below 1 hp, it stays where it is
+        assert_eq!(tick_hp(health(0.5), tick(-5.0, 0.0), 1, 1), 0.5);
+    }
+
+    #[test]
+    fn heals_stop_at_max_hp() {
+        assert_eq!(tick_hp(health(90.0), tick(5.0, 0.0), 1, 1), 95.0);
+        assert_eq!(tick_hp(health(90.0), tick(0.0, 0.1), 1, 3), 100.0);
+    }
+}
@@ -1,13 +1,17 @@
 use super::*;
+use std::{collections::HashMap, error::Error};
 
-#[derive(Component, Debug, Reflect, Copy, Clone)]
+/// Where [`StatFormulas::read`] finds the formula table when the asset server isn't around
+pub const STAT_FORMULAS_PATH: &str = "assets/stats.ron";
+
+#[derive(Component, Debug, Reflect, Serialize, Deserialize, Copy, Clone)]
 #[reflect(Component)]
 pub struct Attributes {
-    strength: i32,
-    dexterity: i32,
-    intelligence: i32,
-    vitality: i32,
-    luck: i32,
+    pub strength: i32,
+    pub dexterity: i32,
+    pub intelligence: i32,
+    pub vitality: i32,
+    pub luck: i32,
 }
 impl Default for Attributes {
     fn default() -> Self {
@@ -21,12 +25,217 @@ impl Default for Attributes {
     }
 }
 
-#[derive(Component, Debug, Reflect, Copy, Clone)]
+impl Attributes {
+    pub fn get(&self, stat: Stat) -> Option<i32> {
+        match stat {
+            Stat::Strength => Some(self.strength),
+            Stat::Dexterity => Some(self.dexterity),
+            Stat::Intelligence => Some(self.intelligence),
+            Stat::Vitality => Some(self.vitality),
+            Stat::Luck => Some(self.luck),
+            _ => None,
+        }
+    }
+
+    pub fn get_mut(&mut self, stat: Stat) -> Option<&mut i32> {
+        match stat {
+            Stat::Strength => Some(&mut self.strength),
+            Stat::Dexterity => Some(&mut self.dexterity),
+            Stat::Intelligence => Some(&mut self.intelligence),
+            Stat::Vitality => Some(&mut self.vitality),
+            Stat::Luck => Some(&mut self.luck),
+            _ => None,
+        }
+    }
+}
+
+/// Base level of a character or monster
+#[derive(Component, Debug, Reflect, Serialize, Deserialize, Copy, Clone, Deref, DerefMut)]
+#[reflect(Component)]
+pub struct Level(pub u32);
+
+impl Default for Level {
+    fn default() -> Self {
+        Self(1)
+    }
+}
+
+/// Everything a modifier can change: the base attributes and the values derived from them
+#[derive(Reflect, Serialize, Deserialize, Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Stat {
+    Strength,
+    Dexterity,
+    Intelligence,
+    Vitality,
+    Luck,
+    Attack,
+    MagicAttack,
+    Defence,
+    MagicDefence,
+    HitRate,
+    DodgeRate,
+    CritRate,
+    AttackRate,
+    MaxHp,
+    MaxMp,
+    MoveSpeed,
+    WeightLimit,
+    MpRegen,
+}
+
+impl Stat {
+    /// The base attributes, raised with stat points
+    pub const BASE: [Stat; 5] = [
+        Stat::Strength,
+        Stat::Dexterity,
+        Stat::Intelligence,
+        Stat::Vitality,
+        Stat::Luck,
+    ];
+}
+
+#[derive(Reflect, Serialize, Deserialize, Debug, Copy, Clone, PartialEq)]
+pub enum ModifierKind {
+    /// Added to the stat
+    Flat(f32),
+    /// Fraction of the stat, `0.1` is +10%. Applied after every flat bonus.
+    Percent(f32),
+}
+
+/// A single bonus or malus from equipment, a status effect or anything else
+#[derive(Reflect, Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct StatModifier {
+    /// Who applied it, so it can be taken off again, e.g. "equip:weapon" or "status:blessing"
+    pub source: String,
+    pub stat: Stat,
+    pub kind: ModifierKind,
+}
+
+impl StatModifier {
+    pub fn flat(source: impl Into<String>, stat: Stat, value: f32) -> Self {
+        Self {
+            source: source.into(),
+            stat,
+            kind: ModifierKind::Flat(value),
+        }
+    }
+
+    pub fn percent(source: impl Into<String>, stat: Stat, value: f32) -> Self {
+        Self {
+            source: source.into(),
+            stat,
+            kind: ModifierKind::Percent(value),
+        }
+    }
+}
+
+/// Every modifier currently applied to an entity, [`ComputedAttributes`] are derived again
+/// whenever this changes
+#[derive(Component, Reflect, Serialize, Deserialize, Debug, Default, Clone)]
+#[reflect(Component)]
+pub struct StatModifiers(pub Vec<StatModifier>);
+
+impl StatModifiers {
+    pub fn add(&mut self, modifier: StatModifier) {
+        self.0.push(modifier);
+    }
+
+    pub fn remove_source(&mut self, source: &str) {
+        self.0.retain(|m| m.source != source);
+    }
+
+    /// `value` with every modifier of `stat` applied, flat bonuses first
+    pub fn apply(&self, stat: Stat, value: f32) -> f32 {
+        let (flat, percent) =
+            self.0
+                .iter()
+                .filter(|m| m.stat == stat)
+                .fold((0.0, 0.0), |(flat, percent), m| match m.kind {
+                    ModifierKind::Flat(v) => (flat + v, percent),
+                    ModifierKind::Percent(v) => (flat, percent + v),
+                });
+        (value + flat) * (1.0 + percent)
+    }
+}
+
+/// Fixed values used instead of the [`Formula`] of some derived stats, e.g. the move speed of a
+/// monster. Modifiers still apply on top.
+#[derive(Component, Reflect, Serialize, Deserialize, Debug, Default, Clone)]
+#[reflect(Component)]
+pub struct StatOverrides(pub HashMap<Stat, f32>);
+
+/// How a derived stat grows with level and base attributes:
+/// `base + per_level * level + strength * STR + ...`, clamped to `min`/`max`
+#[derive(Reflect, Serialize, Deserialize, Debug, Default, Clone)]
+#[serde(default)]
+pub struct Formula {
+    pub base: f32,
+    pub per_level: f32,
+    pub strength: f32,
+    pub dexterity: f32,
+    pub intelligence: f32,
+    pub vitality: f32,
+    pub luck: f32,
+    pub min: Option<f32>,
+    pub max: Option<f32>,
+}
+
+impl Formula {
+    pub fn eval(&self, attribs: &Attributes, level: u32) -> f32 {
+        let value = self.base
+            + self.per_level * level as f32
+            + self.strength * attribs.strength as f32
+            + self.dexterity * attribs.dexterity as f32
+            + self.intelligence * attribs.intelligence as f32
+            + self.vitality * attribs.vitality as f32
+            + self.luck * attribs.luck as f32;
+        value
+            .max(self.min.unwrap_or(f32::MIN))
+            .min(self.max.unwrap_or(f32::MAX))
+    }
+}
+
+/// One [`Formula`] per derived stat
+#[derive(Reflect, Serialize, Deserialize, Debug, Clone)]
+pub struct FormulaSet {
+    pub attack: Formula,
+    pub magic_attack: Formula,
+    pub defence: Formula,
+    pub magic_defence: Formula,
+    pub hit_rate: Formula,
+    pub dodge_rate: Formula,
+    pub crit_rate: Formula,
+    /// Seconds between two auto attacks
+    pub attack_rate: Formula,
+    pub max_hp: Formula,
+    pub max_mp: Formula,
+    pub move_speed: Formula,
+    /// Weight an [`Inventory`] can hold
+    #[serde(default)]
+    pub weight_limit: Formula,
+    /// Mp restored per second
+    #[serde(default)]
+    pub mp_regen: Formula,
+}
+
+/// Designer tunable formulas turning [`Attributes`] and [`Level`] into [`ComputedAttributes`]
+#[derive(Asset, Resource, Reflect, Serialize, Deserialize, Debug, Clone)]
+#[reflect(Resource)]
+pub struct StatFormulas {
+    pub player: FormulaSet,
+    pub enemy: FormulaSet,
+}
+
+impl StatFormulas {
+    /// Reads the formulas straight from disk, for apps without an [`AssetServer`]
+    pub fn read() -> Result<Self, Box<dyn Error>> {
+        read_ron(STAT_FORMULAS_PATH)
+    }
+}
+
+#[derive(Component, Debug, Reflect, Serialize, Deserialize, Copy, Clone)]
 #[reflect(Component)]
 pub struct ComputedAttributes {
-    // todo: maybe these shouldn't be pub if there are multiple
-    // sources of what affects their value.
-    // Example: `attack` might be a combination of strength and dexterity
     pub attack: f32,
     pub defence: f32,
     pub magic_defence: f32,
@@ -37,11 +246,36 @@ pub struct ComputedAttributes {
     pub attack_rate: f32,
     pub attack_range: f32,
     pub move_speed: f32,
+    pub weight_limit: f32,
+    /// Mp restored per second
+    pub mp_regen: f32,
 
     pub health: Health,
     pub mana: Mana,
 }
 
+impl ComputedAttributes {
+    /// The derived value of `stat`, None for base attributes
+    pub fn get(&self, stat: Stat) -> Option<f32> {
+        match stat {
+            Stat::Attack => Some(self.attack),
+            Stat::MagicAttack => Some(self.magic_attack),
+            Stat::Defence => Some(self.defence),
+            Stat::MagicDefence => Some(self.magic_defence),
+            Stat::HitRate => Some(self.hit_rate),
+            Stat::DodgeRate => Some(self.dodge_rate),
+            Stat::CritRate => Some(self.crit_rate),
+            Stat::AttackRate => Some(self.attack_rate),
+            Stat::MaxHp => Some(self.health.max_hp),
+            Stat::MaxMp => Some(self.mana.max_mp),
+            Stat::MoveSpeed => Some(self.move_speed),
+            Stat::WeightLimit => Some(self.weight_limit),
+            Stat::MpRegen => Some(self.mp_regen),
+            _ => None,
+        }
+    }
+}
+
 impl Default for ComputedAttributes {
     fn default() -> Self {
         ComputedAttributes {
@@ -55,6 +289,8 @@ impl Default for ComputedAttributes {
             attack_rate: 1.0,
             attack_range: 1.0,
             move_speed: 10.0,
+            weight_limit: 0.0,
+            mp_regen: 0.0,
 
             health: Health::new(1.0),
             mana: Mana::new(1.0),
@@ -62,7 +298,7 @@ impl Default for ComputedAttributes {
     }
 }
 
-#[derive(Debug, Reflect, Default, Copy, Clone)]
+#[derive(Debug, Reflect, Serialize, Deserialize, Default, Copy, Clone)]
 pub struct Health {
     pub max_hp: f32,
     pub hp: f32,
@@ -77,7 +313,7 @@ impl Health {
     }
 }
 
-#[derive(Debug, Reflect, Default, Copy, Clone)]
+#[derive(Debug, Reflect, Serialize, Deserialize, Default, Copy, Clone)]
 pub struct Mana {
     pub max_mp: f32,
     pub mp: f32,
@@ -0,0 +1,226 @@
+use super::*;
+use std::{collections::HashMap, error::Error};
+
+/// Where [`DamageTables::read`] finds the tables when the asset server isn't around
+pub const DAMAGE_TABLES_PATH: &str = "assets/damage.ron";
+
+/// Element of the attacks of an entity, the weapon's for players. Neutral without one.
+#[derive(Component, Reflect, Serialize, Deserialize, Debug, Default, Clone, Copy, Deref)]
+#[reflect(Component)]
+pub struct AttackElement(pub Element);
+
+/// How strongly an entity is of its [`Element`], 1 to 4. Higher levels resist their own
+/// element more and take more from the opposite one.
+#[derive(Component, Reflect, Serialize, Deserialize, Debug, Clone, Copy, Deref)]
+#[reflect(Component)]
+pub struct ElementLevel(pub u8);
+
+impl Default for ElementLevel {
+    fn default() -> Self {
+        Self(1)
+    }
+}
+
+/// Kind of weapon a player swings, sets the damage against each [`BodySize`]
+#[derive(
+    Component, Reflect, Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq, Hash,
+)]
+#[reflect(Component)]
+pub enum WeaponType {
+    #[default]
+    Fist,
+    Dagger,
+    OneHandSword,
+    TwoHandSword,
+    Spear,
+    Axe,
+    Mace,
+    Staff,
+    Bow,
+    Knuckle,
+    Instrument,
+    Whip,
+    Book,
+    Katar,
+}
+
+/// Extra damage against one race, e.g. from a card
+#[derive(Reflect, Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct RaceBonus {
+    /// Who gave it, so it can be taken off again, e.g. "card:weapon:0"
+    pub source: String,
+    pub race: Race,
+    /// Fraction of the damage, `0.2` is +20%
+    pub bonus: f32,
+}
+
+/// Every race bonus of an entity, they add up
+#[derive(Component, Reflect, Serialize, Deserialize, Debug, Default, Clone)]
+#[reflect(Component)]
+pub struct RaceBonuses(pub Vec<RaceBonus>);
+
+impl RaceBonuses {
+    pub fn add(&mut self, source: impl Into<String>, race: Race, bonus: f32) {
+        self.0.push(RaceBonus {
+            source: source.into(),
+            race,
+            bonus,
+        });
+    }
+
+    pub fn remove_source(&mut self, source: &str) {
+        self.0.retain(|b| b.source != source);
+    }
+
+    /// Damage multiplier against `race`
+    pub fn against(&self, race: Race) -> f32 {
+        1.0 + self
+            .0
+            .iter()
+            .filter(|b| b.race == race)
+            .map(|b| b.bonus)
+            .sum::<f32>()
+    }
+}
+
+/// Damage in percent against each [`BodySize`]
+#[derive(Reflect, Serialize, Deserialize, Debug, Clone, Copy)]
+pub struct SizeRow {
+    pub small: f32,
+    pub medium: f32,
+    pub large: f32,
+}
+
+/// Ragnarok style damage modifiers, see `assets/damage.ron`. Every value is in percent,
+/// 100 is normal damage and anything missing counts as 100.
+#[derive(Asset, Resource, Reflect, Serialize, Deserialize, Debug, Clone)]
+#[reflect(Resource)]
+pub struct DamageTables {
+    /// One matrix per defence element level: attack element to one value per defence element,
+    /// in the order of [`Element`]
+    pub elements: Vec<HashMap<Element, Vec<f32>>>,
+    /// Weapon type to the damage against each size
+    pub sizes: HashMap<WeaponType, SizeRow>,
+}
+
+impl DamageTables {
+    /// Reads the tables straight from disk, for apps without an [`AssetServer`]
+    pub fn read() -> Result<Self, Box<dyn Error>> {
+        read_ron(DAMAGE_TABLES_PATH)
+    }
+
+    /// Damage multiplier of an `attack` element hit on a `defence` element of `level`.
+    /// 0 for immune, negative values are clamped by the damage code.
+    pub fn element(&self, attack: Element, defence: Element, level: u8) -> f32 {
+        let level = (level.max(1) as usize).min(self.elements.len());
+        self.elements
+            .get(level.wrapping_sub(1))
+            .and_then(|matrix| matrix.get(&attack))
+            .and_then(|row| row.get(defence as usize))
+            .map_or(1.0, |percent| percent / 100.0)
+    }
+
+    /// Damage multiplier of a `weapon` against a target of `size`
+    pub fn size(&self, weapon: WeaponType, size: BodySize) -> f32 {
+        self.sizes.get(&weapon).map_or(1.0, |row| {
+            let percent = match size {
+                BodySize::Small => row.small,
+                BodySize::Medium => row.medium,
+                BodySize::Large => row.large,
+            };
+            percent / 100.0
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A row of 100s but against undead
+    fn row(undead: f32) -> Vec<f32> {
+        let mut row = vec![100.0; 10];
+        row[Element::Undead as usize] = undead;
+        row
+    }
+
+    fn tables() -> DamageTables {
+        let elements = [125.0, 150.0, 175.0, 200.0]
+            .into_iter()
+            .map(|fire| HashMap::from([(Element::Fire, row(fire)), (Element::Shadow, row(-25.0))]))
+            .collect();
+        let sizes = HashMap::from([(
+            WeaponType::Dagger,
+            SizeRow {
+                small: 100.0,
+                medium: 75.0,
+                large: 50.0,
+            },
+        )]);
+        DamageTables { elements, sizes }
+    }
+
+    fn assert_near(value: f32, expected: f32) {
+        assert!((value - expected).abs() < 1e-6, "{value} isn't {expected}");
+    }
+
+    #[test]
+    fn element_per_level() {
+        let tables = tables();
+        for (level, expected) in [(1, 1.25), (2, 1.5), (3, 1.75), (4, 2.0)] {
+            assert_near(
+                tables.element(Element::Fire, Element::Undead, level),
+                expected,
+            );
+        }
+        assert_near(tables.element(Element::Fire, Element::Water, 2), 1.0);
+    }
+
+    #[test]
+    fn element_level_out_of_range() {
+        let tables = tables();
+        assert_near(tables.element(Element::Fire, Element::Undead, 0), 1.25);
+        assert_near(tables.element(Element::Fire, Element::Undead, 9), 2.0);
+    }
+
+    #[test]
+    fn element_missing_counts_as_normal() {
+        assert_near(tables().element(Element::Holy, Element::Undead, 1), 1.0);
+        let empty = DamageTables {
+            elements: vec![],
+            sizes: HashMap::new(),
+        };
+        assert_near(empty.element(Element::Fire, Element::Undead, 1), 1.0);
+    }
+
+    #[test]
+    fn element_keeps_negative_values() {
+        // the damage code clamps these to 0
+        assert_near(tables().element(Element::Shadow, Element::Undead, 3), -0.25);
+    }
+
+    #[test]
+    fn size_rows() {
+        let tables = tables();
+        assert_near(tables.size(WeaponType::Dagger, BodySize::Small), 1.0);
+        assert_near(tables.size(WeaponType::Dagger, BodySize::Medium), 0.75);
+        assert_near(tables.size(WeaponType::Dagger, BodySize::Large), 0.5);
+        assert_near(tables.size(WeaponType::Bow, BodySize::Large), 1.0);
+    }
+
+    #[test]
+    fn race_bonuses_add_up() {
+        let mut bonuses = RaceBonuses::default();
+        assert_near(bonuses.against(Race::Demon), 1.0);
+
+        bonuses.add("card:weapon:0", Race::Demon, 0.2);
+        bonuses.add("card:weapon:1", Race::Demon, 0.1);
+        bonuses.add("card:weapon:2", Race::Undead, 0.15);
+        assert_near(bonuses.against(Race::Demon), 1.3);
+        assert_near(bonuses.against(Race::Undead), 1.15);
+        assert_near(bonuses.against(Race::Brute), 1.0);
+
+        bonuses.remove_source("card:weapon:0");
+        assert_near(bonuses.against(Race::Demon), 1.1);
+    }
+}
@@ -0,0 +1,16 @@
+use super::*;
+
+/// Health reached zero. Dead entities are left out of movement, AI and combat until they respawn.
+#[derive(Component, Reflect, Debug, Default, Clone, Copy)]
+#[reflect(Component)]
+pub struct Dead;
+
+/// Body of a dead enemy fading out, despawned when the timer is done
+#[derive(Component, Reflect, Deref, DerefMut, Debug)]
+#[reflect(Component)]
+pub struct Corpse(pub Timer);
+
+/// Where a player comes back after dying
+#[derive(Component, Reflect, Deref, Debug, Clone, Copy)]
+#[reflect(Component)]
+pub struct SavePoint(pub Vec3);
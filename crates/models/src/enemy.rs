@@ -1,4 +1,5 @@
 use super::*;
+use std::collections::HashMap;
 
 #[derive(Component, Reflect, Clone)]
 #[reflect(Component)]
@@ -44,3 +45,79 @@ impl Default for Enemy {
 //     WallJump,
 //     KnockBack,
 // }
+
+/// Where an enemy was spawned, it comes back here after dying
+#[derive(Component, Reflect, Deref, Debug, Clone, Copy)]
+#[reflect(Component)]
+pub struct SpawnPoint(pub Transform);
+
+/// What an enemy is up to, driven by `enemy::behaviours`
+#[derive(Component, Reflect, Debug, Default, Clone, Copy, PartialEq)]
+#[reflect(Component)]
+pub enum AiState {
+    #[default]
+    Idle,
+    /// Strolling to a point around its [`SpawnPoint`]
+    Wander(Vec3),
+    /// Running after [`Enemy::target_entity`]
+    Chase,
+    /// Standing in reach of its target, swinging
+    Attack,
+    /// Strayed too far from home, walks back and ignores everyone on the way
+    Leash,
+    /// Low on hp, runs away from its target
+    Flee,
+}
+
+/// Whether a monster picks fights or waits to be hit
+#[derive(Reflect, Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Temper {
+    #[default]
+    Passive,
+    Aggressive,
+}
+
+/// Tuning and timers of an enemy's [`AiState`]
+#[derive(Component, Reflect, Debug, Clone)]
+#[reflect(Component)]
+pub struct Ai {
+    pub temper: Temper,
+    /// Counts down the current idle pause or flight
+    pub timer: Timer,
+    pub wander_radius: f32,
+    /// Farthest it chases away from its [`SpawnPoint`]
+    pub leash_radius: f32,
+    /// Fraction of max hp under which it flees, 0 never
+    pub flee_below: f32,
+}
+
+/// How badly an enemy wants to fight everyone it met, the highest threat becomes its target
+#[derive(Component, Reflect, Debug, Default, Clone)]
+#[reflect(Component)]
+pub struct ThreatTable(pub HashMap<Entity, f32>);
+
+impl ThreatTable {
+    pub fn add(&mut self, entity: Entity, amount: f32) {
+        *self.0.entry(entity).or_default() += amount;
+    }
+
+    pub fn get(&self, entity: Entity) -> f32 {
+        self.0.get(&entity).copied().unwrap_or(0.0)
+    }
+
+    pub fn contains(&self, entity: Entity) -> bool {
+        self.0.contains_key(&entity)
+    }
+
+    /// Whoever holds the most threat
+    pub fn top(&self) -> Option<(Entity, f32)> {
+        self.0
+            .iter()
+            .map(|(entity, threat)| (*entity, *threat))
+            .max_by(|(_, a), (_, b)| a.total_cmp(b))
+    }
+
+    pub fn clear(&mut self) {
+        self.0.clear();
+    }
+}
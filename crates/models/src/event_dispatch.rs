@@ -10,12 +10,16 @@ pub fn plugin(app: &mut App) {
         .add_event::<OnClearModals>()
         .add_event::<OnPauseToggle>()
         .add_event::<OnMuteToggle>()
+        .add_event::<OnStatusWindowToggle>()
+        .add_event::<OnSkillTreeToggle>()
         .add_event::<OnFovIncrement>()
         .add_event::<OnCamCursorToggle>()
         .add_event::<OnDebugUiToggle>()
         .add_event::<OnDiagnosticsToggle>()
         .add_observer(pause)
         .add_observer(mute)
+        .add_observer(status_window)
+        .add_observer(skill_tree)
         .add_observer(back);
 }
 
@@ -40,6 +44,10 @@ pub struct OnPauseToggle;
 #[derive(Event)]
 pub struct OnMuteToggle;
 #[derive(Event)]
+pub struct OnStatusWindowToggle;
+#[derive(Event)]
+pub struct OnSkillTreeToggle;
+#[derive(Event)]
 pub struct OnDiagnosticsToggle;
 #[derive(Event)]
 pub struct OnDebugUiToggle;
@@ -81,3 +89,9 @@ fn pause(_: Trigger<Started<Pause>>, mut commands: Commands) {
 fn mute(_: Trigger<Started<Mute>>, mut commands: Commands) {
     commands.trigger(OnMuteToggle);
 }
+fn status_window(_: Trigger<Started<ToggleStatus>>, mut commands: Commands) {
+    commands.trigger(OnStatusWindowToggle);
+}
+fn skill_tree(_: Trigger<Started<ToggleSkills>>, mut commands: Commands) {
+    commands.trigger(OnSkillTreeToggle);
+}
@@ -96,6 +96,43 @@ pub struct Rotate;
 #[input_action(output = bool)]
 pub struct Attack;
 
+/// Held while clicking an enemy to attack it from where the player stands
+#[derive(Debug, InputAction)]
+#[input_action(output = bool)]
+pub struct ForceAttack;
+
+/// Hotbar actions with the slot each one uses
+macro_rules! hotbar_actions {
+  ( $( $name:ident => $slot:literal ),* ) => {
+        $(
+            #[derive(Debug, InputAction)]
+            #[input_action(output = bool)]
+            pub struct $name;
+
+            impl HotbarAction for $name {
+                const SLOT: usize = $slot;
+            }
+        )*
+    };
+}
+
+/// An input action using one slot of the [`Hotbar`]
+pub trait HotbarAction: InputAction {
+    const SLOT: usize;
+}
+
+hotbar_actions!(
+    Hotbar1 => 0,
+    Hotbar2 => 1,
+    Hotbar3 => 2,
+    Hotbar4 => 3,
+    Hotbar5 => 4,
+    Hotbar6 => 5,
+    Hotbar7 => 6,
+    Hotbar8 => 7,
+    Hotbar9 => 8
+);
+
 #[derive(Debug, InputAction)]
 #[input_action(output = bool)]
 pub struct Jump;
@@ -120,6 +157,16 @@ pub struct Pause;
 #[input_action(output = bool)]
 pub struct Mute;
 
+/// Opens and closes the character status window
+#[derive(Debug, InputAction)]
+#[input_action(output = bool)]
+pub struct ToggleStatus;
+
+/// Opens and closes the skill tree window
+#[derive(Debug, InputAction)]
+#[input_action(output = bool)]
+pub struct ToggleSkills;
+
 #[derive(InputContext, Component, Clone, Copy)]
 #[input_context(priority = 1)]
 pub struct ModalCtx;
@@ -170,6 +217,8 @@ fn bind_gameplay(
 
     actions.bind::<Pause>().to(KeyCode::KeyP);
     actions.bind::<Mute>().to(KeyCode::KeyM);
+    actions.bind::<ToggleStatus>().to(KeyCode::KeyC);
+    actions.bind::<ToggleSkills>().to(KeyCode::KeyK);
     actions
         .bind::<Escape>()
         .to((KeyCode::Escape, GamepadButton::Select));
@@ -188,6 +237,19 @@ fn bind_gameplay(
     actions
         .bind::<Attack>()
         .to((MouseButton::Left, GamepadButton::RightTrigger2));
+    actions
+        .bind::<ForceAttack>()
+        .to((KeyCode::ControlRight, GamepadButton::LeftTrigger2));
+
+    actions.bind::<Hotbar1>().to(KeyCode::Digit1);
+    actions.bind::<Hotbar2>().to(KeyCode::Digit2);
+    actions.bind::<Hotbar3>().to(KeyCode::Digit3);
+    actions.bind::<Hotbar4>().to(KeyCode::Digit4);
+    actions.bind::<Hotbar5>().to(KeyCode::Digit5);
+    actions.bind::<Hotbar6>().to(KeyCode::Digit6);
+    actions.bind::<Hotbar7>().to(KeyCode::Digit7);
+    actions.bind::<Hotbar8>().to(KeyCode::Digit8);
+    actions.bind::<Hotbar9>().to(KeyCode::Digit9);
 }
 
 fn bind_modal(
@@ -0,0 +1,353 @@
+use super::*;
+use std::{collections::HashMap, error::Error};
+
+/// Where [`ItemDb::read`] finds the database when the asset server isn't around
+pub const ITEM_DB_PATH: &str = "assets/items.ron";
+
+/// Id of an item in the [`ItemDb`]
+#[derive(
+    Reflect, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Deref,
+)]
+pub struct ItemId(pub u32);
+
+/// Inventories are sorted in this order
+#[derive(Reflect, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ItemKind {
+    /// Used up from the inventory for its effects
+    Usable,
+    Weapon,
+    Armor,
+    /// Monster loot and crafting material, only good for selling
+    Etc,
+}
+
+#[derive(Reflect, Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum ItemEffect {
+    /// Restores flat hp and mp
+    Heal {
+        #[serde(default)]
+        hp: f32,
+        #[serde(default)]
+        mp: f32,
+    },
+    /// Puts a status on the user, for its own duration unless `duration` is set
+    Status {
+        status: u32,
+        #[serde(default)]
+        duration: Option<f32>,
+    },
+}
+
+#[derive(Reflect, Serialize, Deserialize, Debug, Clone)]
+pub struct ItemDef {
+    pub name: String,
+    pub kind: ItemKind,
+    /// Weight of a single item, counted against [`ComputedAttributes::weight_limit`]
+    pub weight: u32,
+    /// Most items a slot holds, 1 for items that don't stack
+    #[serde(default = "stack")]
+    pub stack: u32,
+    /// Zeny it costs at a shop, npcs buy it back for half
+    pub price: u32,
+    /// Image under `assets/`, the name is shown without one
+    #[serde(default)]
+    pub icon: Option<String>,
+    /// Applied on use, only [`ItemKind::Usable`] items can be used
+    #[serde(default)]
+    pub effects: Vec<ItemEffect>,
+}
+
+fn stack() -> u32 {
+    1
+}
+
+/// Every item of the game by id, see `assets/items.ron`
+#[derive(Asset, Resource, Reflect, Serialize, Deserialize, Debug, Clone)]
+#[reflect(Resource)]
+pub struct ItemDb {
+    pub items: HashMap<u32, ItemDef>,
+}
+
+impl ItemDb {
+    /// Reads the database straight from disk, for apps without an [`AssetServer`]
+    pub fn read() -> Result<Self, Box<dyn Error>> {
+        read_ron(ITEM_DB_PATH)
+    }
+
+    pub fn get(&self, id: ItemId) -> Option<&ItemDef> {
+        self.items.get(&id.0)
+    }
+}
+
+/// Some amount of one item in an inventory slot
+#[derive(Reflect, Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct ItemStack {
+    pub item: ItemId,
+    pub amount: u32,
+}
+
+/// Items carried by a character, one stack per slot
+#[derive(Component, Reflect, Serialize, Deserialize, Debug, Default, Clone)]
+#[reflect(Component)]
+#[require(Burden)]
+pub struct Inventory {
+    pub slots: Vec<ItemStack>,
+}
+
+impl Inventory {
+    pub const MAX_SLOTS: usize = 100;
+
+    pub fn count(&self, item: ItemId) -> u32 {
+        self.slots
+            .iter()
+            .filter(|slot| slot.item == item)
+            .map(|slot| slot.amount)
+            .sum()
+    }
+
+    /// Total weight, items missing from the database weigh nothing
+    pub fn weight(&self, item_db: &ItemDb) -> u32 {
+        self.slots
+            .iter()
+            .filter_map(|slot| Some(item_db.get(slot.item)?.weight.saturating_mul(slot.amount)))
+            .fold(0, u32::saturating_add)
+    }
+
+    /// Whether `amount` more of `item` fit in the slots left, `stack` at most per slot
+    pub fn fits(&self, item: ItemId, amount: u32, stack: u32) -> bool {
+        let stack = stack.max(1);
+        let room: u32 = self
+            .slots
+            .iter()
+            .filter(|slot| slot.item == item)
+            .map(|slot| stack.saturating_sub(slot.amount))
+            .sum();
+        let new_slots = amount.saturating_sub(room).div_ceil(stack) as usize;
+        self.slots.len() + new_slots <= Self::MAX_SLOTS
+    }
+
+    /// Fills the stacks of `item` first and opens new slots for the rest, check [`Self::fits`]
+    /// before
+    pub fn add(&mut self, item: ItemId, mut amount: u32, stack: u32) {
+        let stack = stack.max(1);
+        for slot in self.slots.iter_mut().filter(|slot| slot.item == item) {
+            let moved = amount.min(stack.saturating_sub(slot.amount));
+            slot.amount += moved;
+            amount -= moved;
+        }
+        while amount > 0 {
+            let moved = amount.min(stack);
+            self.slots.push(ItemStack {
+                item,
+                amount: moved,
+            });
+            amount -= moved;
+        }
+    }
+
+    /// Takes `amount` of `item` from the last stacks first, false and nothing taken when there
+    /// aren't enough
+    pub fn remove(&mut self, item: ItemId, mut amount: u32) -> bool {
+        if self.count(item) < amount {
+            return false;
+        }
+        for slot in self.slots.iter_mut().rev().filter(|slot| slot.item == item) {
+            let taken = amount.min(slot.amount);
+            slot.amount -= taken;
+            amount -= taken;
+        }
+        self.slots.retain(|slot| slot.amount > 0);
+        true
+    }
+
+    /// By [`ItemKind`] then id, full stacks before partial ones
+    pub fn sort(&mut self, item_db: &ItemDb) {
+        self.slots.sort_by_key(|slot| {
+            let kind = item_db.get(slot.item).map(|def| def.kind);
+            (kind, slot.item, std::cmp::Reverse(slot.amount))
+        });
+    }
+}
+
+/// How loaded a character is, its inventory weight against its
+/// [`ComputedAttributes::weight_limit`]
+#[derive(Component, Reflect, Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq)]
+#[reflect(Component)]
+pub enum Burden {
+    #[default]
+    Light,
+    /// Moves slower
+    Heavy,
+    /// Moves slower and can't attack or cast
+    Overweight,
+}
+
+impl Burden {
+    /// Source of the [`StatModifier`]s of the burden
+    pub const SOURCE: &str = "burden";
+
+    pub fn of(weight: u32, limit: f32, cfg: &InventoryConfig) -> Self {
+        let load = weight as f32 / limit.max(1.0);
+        if load >= cfg.overweight {
+            Burden::Overweight
+        } else if load >= cfg.heavy {
+            Burden::Heavy
+        } else {
+            Burden::Light
+        }
+    }
+
+    pub fn lockouts(self) -> Lockouts {
+        Lockouts {
+            attack: self == Burden::Overweight,
+            cast: self == Burden::Overweight,
+            ..default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const POTION: ItemId = ItemId(501);
+    const JELLOPY: ItemId = ItemId(909);
+    const KNIFE: ItemId = ItemId(1201);
+
+    fn item(name: &str, kind: ItemKind, weight: u32, stack: u32) -> ItemDef {
+        ItemDef {
+            name: name.into(),
+            kind,
+            weight,
+            stack,
+            price: 0,
+            icon: None,
+            effects: vec![],
+        }
+    }
+
+    fn item_db() -> ItemDb {
+        ItemDb {
+            items: HashMap::from([
+                (*POTION, item("Red Potion", ItemKind::Usable, 7, 10)),
+                (*JELLOPY, item("Jellopy", ItemKind::Etc, 1, 10)),
+                (*KNIFE, item("Knife", ItemKind::Weapon, 40, 1)),
+            ]),
+        }
+    }
+
+    fn amounts(inventory: &Inventory) -> Vec<(u32, u32)> {
+        inventory
+            .slots
+            .iter()
+            .map(|slot| (*slot.item, slot.amount))
+            .collect()
+    }
+
+    #[test]
+    fn add_fills_partial_stacks_first() {
+        let mut inventory = Inventory::default();
+        inventory.add(POTION, 4, 10);
+        inventory.add(JELLOPY, 1, 10);
+        inventory.add(POTION, 15, 10);
+        assert_eq!(amounts(&inventory), [(501, 10), (909, 1), (501, 9)]);
+        assert_eq!(inventory.count(POTION), 19);
+    }
+
+    #[test]
+    fn unstackable_items_take_a_slot_each() {
+        let mut inventory = Inventory::default();
+        inventory.add(KNIFE, 3, 1);
+        assert_eq!(inventory.slots.len(), 3);
+    }
+
+    #[test]
+    fn fits_counts_free_slots() {
+        let mut inventory = Inventory::default();
+        inventory.add(KNIFE, Inventory::MAX_SLOTS as u32 - 1, 1);
+        inventory.add(POTION, 5, 10);
+        assert_eq!(inventory.slots.len(), Inventory::MAX_SLOTS);
+
+        // the potion stack still has room, nothing else does
+        assert!(inventory.fits(POTION, 5, 10));
+        assert!(!inventory.fits(POTION, 6, 10));
+        assert!(!inventory.fits(KNIFE, 1, 1));
+        assert!(!inventory.fits(JELLOPY, 1, 10));
+    }
+
+    #[test]
+    fn remove_takes_from_the_last_stacks() {
+        let mut inventory = Inventory::default();
+        inventory.add(POTION, 25, 10);
+        assert!(inventory.remove(POTION, 7));
+        assert_eq!(amounts(&inventory), [(501, 10), (501, 8)]);
+        assert!(inventory.remove(POTION, 18));
+        assert!(inventory.slots.is_empty());
+    }
+
+    #[test]
+    fn remove_short_leaves_everything() {
+        let mut inventory = Inventory::default();
+        inventory.add(POTION, 12, 10);
+        inventory.add(JELLOPY, 3, 10);
+        let before = inventory.slots.clone();
+
+        assert!(!inventory.remove(POTION, 13));
+        assert!(!inventory.remove(KNIFE, 1));
+        assert_eq!(inventory.slots, before);
+    }
+
+    #[test]
+    fn sort_by_kind_then_id() {
+        let item_db = item_db();
+        let mut inventory = Inventory::default();
+        inventory.add(JELLOPY, 3, 10);
+        inventory.add(KNIFE, 1, 1);
+        inventory.add(POTION, 2, 10);
+        inventory.slots.push(ItemStack {
+            item: POTION,
+            amount: 10,
+        });
+
+        inventory.sort(&item_db);
+        assert_eq!(
+            amounts(&inventory),
+            [(501, 10), (501, 2), (1201, 1), (909, 3)]
+        );
+    }
+
+    #[test]
+    fn weight_saturates() {
+        let item_db = item_db();
+        let mut inventory = Inventory::default();
+        inventory.add(POTION, 3, 10);
+        inventory.add(KNIFE, 1, 1);
+        assert_eq!(inventory.weight(&item_db), 61);
+
+        inventory.slots.push(ItemStack {
+            item: KNIFE,
+            amount: u32::MAX,
+        });
+        assert_eq!(inventory.weight(&item_db), u32::MAX);
+    }
+
+    #[test]
+    fn burden_thresholds() {
+        let cfg = InventoryConfig {
+            heavy: 0.5,
+            overweight: 0.9,
+            move_speed: -0.25,
+        };
+        assert_eq!(Burden::of(0, 1000.0, &cfg), Burden::Light);
+        assert_eq!(Burden::of(499, 1000.0, &cfg), Burden::Light);
+        assert_eq!(Burden::of(500, 1000.0, &cfg), Burden::Heavy);
+        assert_eq!(Burden::of(899, 1000.0, &cfg), Burden::Heavy);
+        assert_eq!(Burden::of(900, 1000.0, &cfg), Burden::Overweight);
+        assert_eq!(Burden::of(2000, 1000.0, &cfg), Burden::Overweight);
+        // no weight limit yet, anything carried is too much
+        assert_eq!(Burden::of(1, 0.0, &cfg), Burden::Overweight);
+
+        assert!(Burden::Overweight.lockouts().attack);
+        assert_eq!(Burden::Heavy.lockouts(), Lockouts::default());
+    }
+}
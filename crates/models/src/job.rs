@@ -0,0 +1,160 @@
+use super::*;
+use std::{collections::HashMap, error::Error};
+
+/// Where [`JobDb::read`] finds the database when the asset server isn't around
+pub const JOB_DB_PATH: &str = "assets/jobs.ron";
+
+/// Class of a character, see `assets/jobs.ron` for what each one gets
+#[derive(
+    Component,
+    Reflect,
+    Serialize,
+    Deserialize,
+    Debug,
+    Default,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+)]
+#[reflect(Component)]
+pub enum Job {
+    #[default]
+    Novice,
+    // first classes
+    Swordsman,
+    Mage,
+    Archer,
+    Acolyte,
+    Merchant,
+    Thief,
+    // second classes
+    Knight,
+    Wizard,
+    Hunter,
+    Priest,
+    Blacksmith,
+    Assassin,
+}
+
+impl Job {
+    /// Source of the [`StatModifier`]s of the job
+    pub const SOURCE: &str = "job";
+}
+
+/// A skill in the tree of a job
+#[derive(Reflect, Serialize, Deserialize, Debug, Clone)]
+pub struct TreeSkill {
+    pub skill: u32,
+    /// Column and row in the skill tree window
+    pub at: (u32, u32),
+    /// Skills and the level they need before this one can be learned
+    #[serde(default)]
+    pub requires: Vec<(u32, u32)>,
+}
+
+/// The job a character has to be, and its job level, to change into another
+#[derive(Reflect, Serialize, Deserialize, Debug, Clone, Copy)]
+pub struct JobChange {
+    pub from: Job,
+    pub job_level: u32,
+}
+
+#[derive(Reflect, Serialize, Deserialize, Debug, Clone)]
+pub struct JobDef {
+    pub name: String,
+    /// None for jobs nobody changes into
+    #[serde(default)]
+    pub change: Option<JobChange>,
+    pub max_job_level: u32,
+    /// Max hp and mp on top of the stat formulas, per base level
+    #[serde(default)]
+    pub hp_per_level: f32,
+    #[serde(default)]
+    pub mp_per_level: f32,
+    /// A point of the attribute for every listed job level reached
+    #[serde(default)]
+    pub bonuses: Vec<(u32, Stat)>,
+    /// Weapons it can equip, bare hands always work
+    #[serde(default)]
+    pub weapons: Vec<WeaponType>,
+    /// Skill tree, the trees of the jobs it changed from can be learned as well
+    #[serde(default)]
+    pub skills: Vec<TreeSkill>,
+}
+
+impl JobDef {
+    pub fn can_equip(&self, weapon: WeaponType) -> bool {
+        weapon == WeaponType::Fist || self.weapons.contains(&weapon)
+    }
+
+    /// Modifiers of a character of this job at the given levels
+    pub fn modifiers(&self, level: u32, job_level: u32) -> Vec<StatModifier> {
+        let mut bonuses: HashMap<Stat, f32> = HashMap::new();
+        for (_, stat) in self.bonuses.iter().filter(|(at, _)| *at <= job_level) {
+            *bonuses.entry(*stat).or_default() += 1.0;
+        }
+        bonuses.insert(Stat::MaxHp, self.hp_per_level * level as f32);
+        bonuses.insert(Stat::MaxMp, self.mp_per_level * level as f32);
+
+        bonuses
+            .into_iter()
+            .filter(|(_, value)| *value != 0.0)
+            .map(|(stat, value)| StatModifier::flat(Job::SOURCE, stat, value))
+            .collect()
+    }
+}
+
+/// Every job of the game, see `assets/jobs.ron`
+#[derive(Asset, Resource, Reflect, Serialize, Deserialize, Debug, Clone)]
+#[reflect(Resource)]
+pub struct JobDb {
+    pub jobs: HashMap<Job, JobDef>,
+}
+
+impl JobDb {
+    /// Reads the database straight from disk, for apps without an [`AssetServer`]
+    pub fn read() -> Result<Self, Box<dyn Error>> {
+        read_ron(JOB_DB_PATH)
+    }
+
+    pub fn get(&self, job: Job) -> Option<&JobDef> {
+        self.jobs.get(&job)
+    }
+
+    /// `job` and every job it changed from, the first one first
+    pub fn lineage(&self, job: Job) -> Vec<Job> {
+        let mut lineage = vec![job];
+        while let Some(change) = lineage
+            .last()
+            .and_then(|job| self.get(*job))
+            .and_then(|def| def.change)
+            // a loop in the database stops at the first repeat
+            .filter(|change| !lineage.contains(&change.from))
+        {
+            lineage.push(change.from);
+        }
+        lineage.reverse();
+        lineage
+    }
+
+    /// Where `skill` sits in the trees a character of `job` can learn from
+    pub fn tree_skill(&self, job: Job, skill: SkillId) -> Option<&TreeSkill> {
+        self.lineage(job)
+            .into_iter()
+            .filter_map(|job| self.get(job))
+            .flat_map(|def| &def.skills)
+            .find(|tree_skill| tree_skill.skill == *skill)
+    }
+
+    /// Jobs a character of `job` can change into
+    pub fn next(&self, job: Job) -> impl Iterator<Item = (Job, &JobDef)> {
+        self.jobs
+            .iter()
+            .filter(move |(_, def)| def.change.is_some_and(|c| c.from == job))
+            .map(|(job, def)| (*job, def))
+    }
+}
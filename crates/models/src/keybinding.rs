@@ -22,6 +22,7 @@ pub struct Keybind {
     pub crouch: Vec<Input>,
     pub sprint: Vec<Input>,
     pub attack: Vec<Input>,
+    pub force_attack: Vec<Input>,
 }
 impl Keybind {
     pub fn clear(&mut self) {
@@ -34,6 +35,7 @@ impl Keybind {
         self.crouch.clear();
         self.sprint.clear();
         self.attack.clear();
+        self.force_attack.clear();
     }
 }
 
@@ -49,6 +51,7 @@ impl Default for Keybind {
             crouch: vec![KeyCode::ControlLeft.into()],
             sprint: vec![KeyCode::ShiftLeft.into()],
             attack: vec![MouseButton::Left.into()],
+            force_attack: vec![KeyCode::ControlRight.into()],
         }
     }
 }
@@ -9,7 +9,17 @@ pub mod keybinding;
 pub mod palette;
 pub mod player;
 pub mod enemy;
+pub mod monster;
+pub mod map;
+pub mod navigation;
+pub mod skill;
+pub mod status;
 pub mod attributes;
+pub mod progression;
+pub mod job;
+pub mod item;
+pub mod damage_table;
+pub mod death;
 pub mod pre_load;
 pub mod primitives;
 pub mod settings;
@@ -21,7 +31,17 @@ pub use keybinding::*;
 pub use palette::*;
 pub use player::*;
 pub use enemy::*;
+pub use monster::*;
+pub use map::*;
+pub use navigation::*;
+pub use skill::*;
+pub use status::*;
 pub use attributes::*;
+pub use progression::*;
+pub use job::*;
+pub use item::*;
+pub use damage_table::*;
+pub use death::*;
 pub use pre_load::*;
 pub use primitives::*;
 pub use settings::*;
@@ -51,6 +71,10 @@ pub enum Screen {
     Settings,
     // Here the menu is drawn and waiting for player interaction
     Title,
+    // Entering the world: credentials, then a server, then a character
+    Login,
+    ServerSelect,
+    CharacterSelect,
     // During this State the actual game logic is executed
     Gameplay,
 }
@@ -0,0 +1,45 @@
+use super::*;
+use std::error::Error;
+
+/// Where [`MapData::read`] finds the map when the asset server isn't around
+pub const MAP_PATH: &str = "assets/maps/field.map.ron";
+
+/// Area on the ground, in XZ coordinates
+#[derive(Reflect, Serialize, Deserialize, Debug, Clone)]
+pub enum SpawnArea {
+    Rect { center: Vec2, half_size: Vec2 },
+    Circle { center: Vec2, radius: f32 },
+}
+
+#[derive(Reflect, Serialize, Deserialize, Debug, Clone)]
+pub struct SpawnEntry {
+    /// Id in the [`MonsterDb`]
+    pub monster: u32,
+    /// How many of them the zone keeps alive
+    pub population: u32,
+    /// Seconds between a death and the replacement showing up
+    pub respawn: f32,
+}
+
+/// Part of a map kept populated with monsters
+#[derive(Reflect, Serialize, Deserialize, Debug, Clone)]
+pub struct SpawnZoneDef {
+    pub name: String,
+    pub area: SpawnArea,
+    pub entries: Vec<SpawnEntry>,
+}
+
+/// Everything about a map that isn't geometry
+#[derive(Asset, Resource, Reflect, Serialize, Deserialize, Debug, Clone)]
+#[reflect(Resource)]
+pub struct MapData {
+    pub name: String,
+    pub spawn_zones: Vec<SpawnZoneDef>,
+}
+
+impl MapData {
+    /// Reads the map straight from disk, for apps without an [`AssetServer`]
+    pub fn read() -> Result<Self, Box<dyn Error>> {
+        read_ron(MAP_PATH)
+    }
+}
@@ -0,0 +1,124 @@
+use super::*;
+use std::{collections::HashMap, error::Error};
+
+/// Where [`MonsterDb::read`] finds the database when the asset server isn't around
+pub const MONSTER_DB_PATH: &str = "assets/monsters.ron";
+
+/// Id of a monster in the [`MonsterDb`], also marks which monster an [`Enemy`] is
+#[derive(
+    Component, Reflect, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash, Deref,
+)]
+#[reflect(Component)]
+pub struct MonsterId(pub u32);
+
+#[derive(
+    Component, Reflect, Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq, Hash,
+)]
+#[reflect(Component)]
+pub enum Element {
+    #[default]
+    Neutral,
+    Water,
+    Earth,
+    Fire,
+    Wind,
+    Poison,
+    Holy,
+    Shadow,
+    Ghost,
+    Undead,
+}
+
+#[derive(
+    Component, Reflect, Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq, Hash,
+)]
+#[reflect(Component)]
+pub enum BodySize {
+    Small,
+    #[default]
+    Medium,
+    Large,
+}
+
+#[derive(
+    Component, Reflect, Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq, Hash,
+)]
+#[reflect(Component)]
+pub enum Race {
+    #[default]
+    Formless,
+    Undead,
+    Brute,
+    Plant,
+    Insect,
+    Fish,
+    Demon,
+    DemiHuman,
+    Angel,
+    Dragon,
+}
+
+#[derive(Reflect, Serialize, Deserialize, Debug, Clone)]
+pub struct DropEntry {
+    pub item: u32,
+    /// 0..1
+    pub chance: f32,
+}
+
+#[derive(Reflect, Serialize, Deserialize, Debug, Clone)]
+pub struct MonsterDef {
+    pub name: String,
+    /// glTF file under `assets/`
+    pub model: String,
+    pub level: u32,
+    pub attributes: Attributes,
+    pub aggro_radius: f32,
+    pub move_speed: f32,
+    pub attack_range: f32,
+    /// Seconds between two attacks
+    pub attack_rate: f32,
+    pub element: Element,
+    /// 1 to 4, see [`ElementLevel`]
+    #[serde(default = "element_level")]
+    pub element_level: u8,
+    pub size: BodySize,
+    pub race: Race,
+    /// Passive monsters only fight back
+    #[serde(default)]
+    pub temper: Temper,
+    /// Fraction of max hp under which it runs away, 0 never
+    #[serde(default)]
+    pub flee_below: f32,
+    /// Status ids it never gets
+    #[serde(default)]
+    pub status_resists: Vec<u32>,
+    /// Experience the killer gets
+    #[serde(default)]
+    pub base_exp: u64,
+    #[serde(default)]
+    pub job_exp: u64,
+    #[serde(default)]
+    pub drops: Vec<DropEntry>,
+}
+
+fn element_level() -> u8 {
+    1
+}
+
+/// Every monster of the game by id, see `assets/monsters.ron`
+#[derive(Asset, Resource, Reflect, Serialize, Deserialize, Debug, Clone)]
+#[reflect(Resource)]
+pub struct MonsterDb {
+    pub monsters: HashMap<u32, MonsterDef>,
+}
+
+impl MonsterDb {
+    /// Reads the database straight from disk, for apps without an [`AssetServer`]
+    pub fn read() -> Result<Self, Box<dyn Error>> {
+        read_ron(MONSTER_DB_PATH)
+    }
+
+    pub fn get(&self, id: MonsterId) -> Option<&MonsterDef> {
+        self.monsters.get(&id.0)
+    }
+}
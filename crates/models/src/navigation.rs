@@ -0,0 +1,13 @@
+use super::*;
+
+/// Path an entity is walking along, see `game::navigation`
+#[derive(Component, Reflect, Debug, Default, Clone)]
+#[reflect(Component)]
+pub struct NavPath {
+    /// Corners left to walk, the last one is the goal
+    pub waypoints: Vec<Vec3>,
+    /// Where the path leads, a goal moving away from it asks for a new search
+    pub goal: Option<Vec3>,
+    /// Seconds until the next search is allowed
+    pub cooldown: f32,
+}
@@ -5,6 +5,7 @@ pub const WHITEISH: Color = Color::srgb(0.925, 0.925, 0.925);
 pub const SAND_YELLOW: Color = Color::srgb(205. / 255., 170. / 255., 109. / 255.);
 
 pub const GREEN: Color = Color::srgb(0.286, 0.878, 0.373);
+pub const RED: Color = Color::srgb(0.878, 0.286, 0.286);
 pub const DIM_BLUE: Color = Color::srgb(0.186, 0.328, 0.573);
 pub const LIGHT_BLUE: Color = Color::srgb(0.286, 0.478, 0.773);
 
@@ -5,17 +5,36 @@ use std::collections::HashMap;
 #[reflect(Component)]
 pub struct Player {
     pub id: Entity,
+    /// Current ground speed, the derived move speed times [`Self::speed_factor`]
     pub speed: f32,
+    /// 1 walking, above while sprinting and below while crouching
+    pub speed_factor: f32,
     pub animation_state: AnimationState,
     pub animations: HashMap<String, AnimationNodeIndex>,
     pub target_position: Option<Vec3>,
 }
 
+impl Player {
+    /// Height the Tnua walk basis keeps the player's collider above the ground
+    pub const FLOAT_HEIGHT: f32 = 0.5;
+
+    /// Changes the gait, the speed follows from the derived move speed
+    pub fn set_speed_factor(&mut self, factor: f32, move_speed: f32) {
+        self.speed_factor = factor;
+        self.update_speed(move_speed);
+    }
+
+    pub fn update_speed(&mut self, move_speed: f32) {
+        self.speed = (move_speed * self.speed_factor).max(0.0);
+    }
+}
+
 impl Default for Player {
     fn default() -> Self {
         Self {
             id: Entity::PLACEHOLDER,
             speed: 1.0,
+            speed_factor: 1.0,
             animation_state: AnimationState::StandIdle,
             animations: HashMap::new(),
             target_position: None,
@@ -23,7 +42,27 @@ impl Default for Player {
     }
 }
 
-#[derive(Component, Reflect, Default, Clone)]
+/// The [`Player`] controlled by this client. Every other [`Player`] is somebody else's character
+/// replicated from the server.
+#[derive(Component, Reflect, Debug, Default, Clone, Copy)]
+#[reflect(Component)]
+pub struct LocalPlayer;
+
+/// Enemy the player keeps attacking until it dies or the player walks away, set by clicking it
+#[derive(Component, Reflect, Debug, Clone, Copy)]
+#[reflect(Component)]
+pub struct AttackTarget {
+    pub entity: Entity,
+    /// Force attack: swing from where the player stands instead of walking into range
+    pub in_place: bool,
+}
+
+/// The [`AnimationPlayer`] inside the model of a [`Player`], known once the scene is spawned
+#[derive(Component, Reflect, Debug, Clone, Copy)]
+#[reflect(Component)]
+pub struct PlayerAnimator(pub Entity);
+
+#[derive(Component, Reflect, Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
 #[reflect(Component)]
 pub enum AnimationState {
     #[default]
@@ -41,4 +80,28 @@ pub enum AnimationState {
     WallSlide,
     WallJump,
     KnockBack,
+    /// Hit-stunned, see `combat::HitStun`
+    Flinch,
+    Dead,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn speed_follows_the_move_speed() {
+        let mut player = Player::default();
+        player.set_speed_factor(1.5, 10.0);
+        assert_eq!(player.speed, 15.0);
+
+        // slowed to a halt and back, still sprinting
+        player.update_speed(0.0);
+        assert_eq!(player.speed, 0.0);
+        player.update_speed(8.0);
+        assert_eq!(player.speed, 12.0);
+
+        player.set_speed_factor(1.0, 8.0);
+        assert_eq!(player.speed, 8.0);
+    }
 }
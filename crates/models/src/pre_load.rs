@@ -1,5 +1,9 @@
 use bevy::prelude::*;
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use std::{error::Error, fs, path::Path};
+
+/// Where [`Config::read`] finds the config when the asset server isn't around
+pub const CONFIG_PATH: &str = "assets/config.ron";
 
 #[derive(Clone, Debug, Serialize, Deserialize, Reflect, Asset, Resource)]
 #[reflect(Resource)]
@@ -11,6 +15,25 @@ pub struct Config {
     pub credits: Credits,
     pub settings: SettingsPreloaded,
     pub timers: Timers,
+    pub net: NetConfig,
+    pub combat: CombatConfig,
+    pub respawn: RespawnConfig,
+    pub ai: AiConfig,
+    pub threat: ThreatConfig,
+    pub inventory: InventoryConfig,
+}
+
+impl Config {
+    /// Reads the config straight from disk, for apps without an [`AssetServer`]
+    pub fn read() -> Result<Self, Box<dyn Error>> {
+        read_ron(CONFIG_PATH)
+    }
+}
+
+/// Reads any RON asset straight from disk, for apps without an [`AssetServer`]
+pub fn read_ron<T: DeserializeOwned>(path: impl AsRef<Path>) -> Result<T, Box<dyn Error>> {
+    let content = fs::read_to_string(path)?;
+    Ok(ron::from_str(&content)?)
 }
 
 #[derive(Resource, Debug, Clone, Serialize, Deserialize, Reflect)]
@@ -50,6 +73,19 @@ pub struct PlayerConfig {
     pub hitbox: Hitbox,
     pub zoom: (f32, f32),
     pub fov: f32,
+    /// Skill ids put on the hotbar of a new character, they still have to be learned
+    #[serde(default)]
+    pub hotbar: Vec<u32>,
+    pub camera_shake: CameraShakeConfig,
+}
+
+/// Shake of the camera when the local player gets hit, heavy hits shake twice as hard
+#[derive(Clone, Debug, Serialize, Deserialize, Reflect)]
+pub struct CameraShakeConfig {
+    /// Largest offset in units
+    pub strength: f32,
+    /// Seconds it takes to settle
+    pub duration: f32,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize, Reflect)]
@@ -62,7 +98,6 @@ pub struct Hitbox {
 pub struct Movement {
     pub actions_in_air: u8,
     pub dash_distance: f32,
-    pub speed: f32,
     pub sprint_factor: f32,
     pub crouch_factor: f32,
     pub idle_to_run_threshold: f32,
@@ -88,3 +123,96 @@ pub struct Timers {
     pub step: f32,
     pub jump: f32,
 }
+
+#[derive(Clone, Debug, Serialize, Deserialize, Reflect)]
+pub struct NetConfig {
+    /// How fast prediction errors are blended in, 1/s
+    pub correction_smoothing: f32,
+    /// Errors smaller than this are ignored
+    pub min_correction: f32,
+    /// Errors bigger than this are snapped instead of blended
+    pub snap_distance: f32,
+    /// How far in the past remote entities are rendered, seconds
+    pub interpolation_delay: f32,
+    /// How long remote entities keep moving when snapshots stop arriving, seconds
+    pub max_extrapolation: f32,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, Reflect)]
+pub struct CombatConfig {
+    /// Chance to hit when hit and dodge rates are equal, every point of difference moves it by 1%
+    pub base_hit_chance: f32,
+    pub min_hit_chance: f32,
+    pub max_hit_chance: f32,
+    /// Critical hits ignore dodge and defence and deal this many times the attack
+    pub crit_multiplier: f32,
+    /// Damage is scaled by a random factor in this range
+    pub variance: (f32, f32),
+    /// Defence needed to halve the damage
+    pub defence_scale: f32,
+    /// Fixed seed for reproducible rolls, random when unset
+    pub seed: Option<u64>,
+    pub reactions: HitReactionConfig,
+}
+
+/// How the targets of landed hits react
+#[derive(Clone, Debug, Serialize, Deserialize, Reflect)]
+pub struct HitReactionConfig {
+    /// Seconds a landed hit keeps the target from moving
+    pub hit_stun: f32,
+    /// Hits taking at least this fraction of the max hp are heavy and knock the target back
+    pub heavy_hit: f32,
+    /// Seconds a heavy hit or a knockback keeps the target from moving
+    pub heavy_hit_stun: f32,
+    /// Speed a heavy hit shoves its target away at, units/s
+    pub heavy_knockback: f32,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, Reflect)]
+pub struct RespawnConfig {
+    /// Where new characters come back after dying until they pick another save point
+    pub save_point: Vec3,
+    /// Seconds a dead enemy stays around while fading out
+    pub corpse_fade: f32,
+    /// Fraction of the experience to the next level lost on death
+    pub exp_penalty: f32,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, Reflect)]
+pub struct AiConfig {
+    /// Seconds an enemy stands around before wandering off, picked in this range
+    pub idle_time: (f32, f32),
+    /// How far from its spawn point an enemy wanders
+    pub wander_radius: f32,
+    /// How far from its spawn point an enemy chases before giving up
+    pub leash_radius: f32,
+    /// Seconds a fleeing enemy runs before heading home
+    pub flee_time: f32,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, Reflect)]
+pub struct ThreatConfig {
+    /// Threat of every attack on an enemy, misses included
+    pub hit: f32,
+    /// Threat per point of damage dealt
+    pub damage: f32,
+    /// Threat per point healed on someone an enemy is fighting
+    pub heal: f32,
+    /// Threat per second of standing closest to an aggressive enemy
+    pub proximity: f32,
+    /// Fraction of the threat lost per second
+    pub decay: f32,
+    /// A new target needs this many times the threat of the current one to pull aggro
+    pub switch_ratio: f32,
+}
+
+/// Penalties of carrying too much, loads are fractions of the weight limit
+#[derive(Clone, Debug, Serialize, Deserialize, Reflect)]
+pub struct InventoryConfig {
+    /// Load from which a character moves slower
+    pub heavy: f32,
+    /// Load from which a character can't attack or cast either
+    pub overweight: f32,
+    /// Fraction of the move speed lost when heavy or overweight, `-0.25` is -25%
+    pub move_speed: f32,
+}
@@ -44,7 +44,25 @@ declare_markers!(
     DiagnosticsLabel,
     DebugUiLabel,
     SaveSettingsLabel,
-    FovLabel
+    FovLabel,
+    TextInputDisplay,
+    // login
+    UsernameInput,
+    PasswordInput,
+    LoginErrorLabel,
+    // death
+    DeathDialog,
+    // skills
+    CastBar,
+    CastBarFill,
+    CastBarLabel,
+    // status window
+    StatusWindow,
+    StatusConfirmDialog,
+    // skill tree
+    SkillTreeWindow,
+    SkillTooltip,
+    SkillDragGhost
 );
 
 macro_rules! timers {
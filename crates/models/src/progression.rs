@@ -0,0 +1,200 @@
+use super::*;
+use std::error::Error;
+
+/// Where [`LevelTable::read`] finds the table when the asset server isn't around
+pub const LEVEL_TABLE_PATH: &str = "assets/levels.ron";
+
+/// The two ladders a character climbs: base levels raise the stats, job levels the skills
+#[derive(Reflect, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ExpKind {
+    Base,
+    Job,
+}
+
+/// Job level of a character, see [`Level`] for the base level
+#[derive(
+    Component, Debug, Reflect, Serialize, Deserialize, Copy, Clone, PartialEq, Deref, DerefMut,
+)]
+#[reflect(Component)]
+pub struct JobLevel(pub u32);
+
+impl Default for JobLevel {
+    fn default() -> Self {
+        Self(1)
+    }
+}
+
+/// Experience gathered towards the next base and job level
+#[derive(Component, Reflect, Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq)]
+#[reflect(Component)]
+pub struct Experience {
+    pub base: u64,
+    pub job: u64,
+}
+
+impl Experience {
+    pub fn get(&self, kind: ExpKind) -> u64 {
+        match kind {
+            ExpKind::Base => self.base,
+            ExpKind::Job => self.job,
+        }
+    }
+
+    pub fn get_mut(&mut self, kind: ExpKind) -> &mut u64 {
+        match kind {
+            ExpKind::Base => &mut self.base,
+            ExpKind::Job => &mut self.job,
+        }
+    }
+}
+
+/// Unspent points to raise [`Attributes`] with, granted on base levels
+#[derive(
+    Component, Reflect, Serialize, Deserialize, Debug, Default, Clone, Copy, Deref, DerefMut,
+)]
+#[reflect(Component)]
+pub struct StatPoints(pub u32);
+
+/// Unspent points to learn skills with, granted on job levels
+#[derive(
+    Component, Reflect, Serialize, Deserialize, Debug, Default, Clone, Copy, Deref, DerefMut,
+)]
+#[reflect(Component)]
+pub struct SkillPoints(pub u32);
+
+/// Experience and points of one ladder
+#[derive(Reflect, Serialize, Deserialize, Debug, Clone)]
+pub struct Progression {
+    /// Experience needed for the next level, the first entry takes level 1 to 2.
+    /// The max level is one past the last entry.
+    pub exp: Vec<u64>,
+    /// Points a new character starts with
+    #[serde(default)]
+    pub start_points: u32,
+    /// Points granted on reaching a level: `points + level / points_every`
+    pub points: u32,
+    /// 0 grants the same `points` on every level
+    #[serde(default)]
+    pub points_every: u32,
+}
+
+impl Progression {
+    pub fn max_level(&self) -> u32 {
+        self.exp.len() as u32 + 1
+    }
+
+    /// Experience needed to get from `level` to the next one, None at the max level
+    pub fn needed(&self, level: u32) -> Option<u64> {
+        let index = level.checked_sub(1)?;
+        self.exp.get(index as usize).copied()
+    }
+
+    /// Points granted on reaching `level`
+    pub fn points_at(&self, level: u32) -> u32 {
+        self.points + level.checked_div(self.points_every).unwrap_or(0)
+    }
+}
+
+/// What raising a base attribute with [`StatPoints`] costs
+#[derive(Reflect, Serialize, Deserialize, Debug, Clone)]
+pub struct StatCosts {
+    /// Points to raise an attribute of 1
+    pub cost: u32,
+    /// The cost goes up by one every this many points of the attribute, 0 never
+    pub cost_every: u32,
+    /// Attributes can't be raised past it
+    pub max: i32,
+}
+
+impl StatCosts {
+    /// Points to raise an attribute of `value` by one
+    pub fn cost(&self, value: i32) -> u32 {
+        let raised = (value.max(1) - 1) as u32;
+        self.cost + raised.checked_div(self.cost_every).unwrap_or(0)
+    }
+
+    /// Points to raise an attribute of `value` by `by`, None past the max
+    pub fn raise_cost(&self, value: i32, by: u32) -> Option<u32> {
+        if value + by as i32 > self.max {
+            return None;
+        }
+        Some((value..value + by as i32).map(|v| self.cost(v)).sum())
+    }
+}
+
+/// Level thresholds and points of base and job levels, see `assets/levels.ron`
+#[derive(Asset, Resource, Reflect, Serialize, Deserialize, Debug, Clone)]
+#[reflect(Resource)]
+pub struct LevelTable {
+    pub base: Progression,
+    pub job: Progression,
+    pub stats: StatCosts,
+}
+
+impl LevelTable {
+    /// Reads the table straight from disk, for apps without an [`AssetServer`]
+    pub fn read() -> Result<Self, Box<dyn Error>> {
+        read_ron(LEVEL_TABLE_PATH)
+    }
+
+    pub fn get(&self, kind: ExpKind) -> &Progression {
+        match kind {
+            ExpKind::Base => &self.base,
+            ExpKind::Job => &self.job,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn progression() -> Progression {
+        Progression {
+            exp: vec![10, 20, 30],
+            start_points: 0,
+            points: 3,
+            points_every: 5,
+        }
+    }
+
+    #[test]
+    fn needed_until_the_max_level() {
+        let progression = progression();
+        assert_eq!(progression.max_level(), 4);
+        assert_eq!(progression.needed(1), Some(10));
+        assert_eq!(progression.needed(3), Some(30));
+        assert_eq!(progression.needed(4), None);
+        assert_eq!(progression.needed(0), None);
+    }
+
+    #[test]
+    fn points_grow_with_the_level() {
+        let progression = progression();
+        assert_eq!(progression.points_at(4), 3);
+        assert_eq!(progression.points_at(5), 4);
+        assert_eq!(progression.points_at(12), 5);
+        let flat = Progression {
+            points_every: 0,
+            ..progression
+        };
+        assert_eq!(flat.points_at(50), 3);
+    }
+
+    #[test]
+    fn stat_cost_curve() {
+        let costs = StatCosts {
+            cost: 2,
+            cost_every: 10,
+            max: 99,
+        };
+        assert_eq!(costs.cost(1), 2);
+        assert_eq!(costs.cost(10), 2);
+        assert_eq!(costs.cost(11), 3);
+        assert_eq!(costs.cost(98), 11);
+        // 9 and 10 cost 2, 11 costs 3
+        assert_eq!(costs.raise_cost(9, 3), Some(7));
+        assert_eq!(costs.raise_cost(98, 1), Some(11));
+        assert_eq!(costs.raise_cost(98, 2), None);
+    }
+}
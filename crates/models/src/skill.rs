@@ -0,0 +1,324 @@
+use super::*;
+use std::{collections::HashMap, error::Error, time::Duration};
+
+/// Where [`SkillDb::read`] finds the database when the asset server isn't around
+pub const SKILL_DB_PATH: &str = "assets/skills.ron";
+
+/// Id of a skill in the [`SkillDb`]
+#[derive(Reflect, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash, Deref)]
+pub struct SkillId(pub u32);
+
+#[derive(Reflect, Serialize, Deserialize, Debug, Copy, Clone, PartialEq, Eq)]
+pub enum DamageKind {
+    /// Attack against defence, can miss and crit
+    Physical,
+    /// Magic attack against magic defence, always hits and never crits
+    Magical,
+}
+
+/// What a skill is aimed at
+#[derive(Reflect, Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum SkillTarget {
+    /// The caster
+    SelfCast,
+    /// One enemy
+    Single,
+    /// Every enemy around a point on the ground
+    GroundArea { radius: f32 },
+}
+
+#[derive(Reflect, Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum SkillEffect {
+    /// A hit of `multiplier` times the caster's attack or magic attack, through the usual rolls.
+    /// Of the caster's [`AttackElement`] unless `element` is set.
+    Damage {
+        kind: DamageKind,
+        multiplier: f32,
+        #[serde(default)]
+        element: Option<Element>,
+    },
+    /// Restores `base` plus `magic_attack` times the caster's magic attack
+    Heal { base: f32, magic_attack: f32 },
+    /// Applies a status with a `chance` in 0..1, for its own duration unless `duration` is set
+    Status {
+        status: u32,
+        chance: f32,
+        #[serde(default)]
+        duration: Option<f32>,
+    },
+    /// Shoves the target away from the caster at `force` units/s, stunning it meanwhile
+    Knockback { force: f32 },
+}
+
+/// What every skill level past the first adds
+#[derive(Reflect, Serialize, Deserialize, Debug, Default, Clone, Copy)]
+#[serde(default)]
+pub struct LevelScaling {
+    pub mp_cost: f32,
+    pub cast_time: f32,
+    /// Added to the multiplier of damage effects
+    pub multiplier: f32,
+    /// Added to the base of heal effects
+    pub heal: f32,
+    /// Added to the chance of status effects
+    pub chance: f32,
+}
+
+#[derive(Reflect, Serialize, Deserialize, Debug, Clone)]
+pub struct SkillDef {
+    pub name: String,
+    /// Levels a player can learn, the values below are the ones of level 1
+    #[serde(default = "max_level")]
+    pub max_level: u32,
+    #[serde(default)]
+    pub per_level: LevelScaling,
+    pub mp_cost: f32,
+    /// Seconds of casting before the skill goes off, 0 for instant
+    pub cast_time: f32,
+    /// Seconds before this skill can be used again
+    pub cooldown: f32,
+    /// Seconds before any skill can be used again
+    pub after_cast_delay: f32,
+    pub range: f32,
+    pub target: SkillTarget,
+    pub effects: Vec<SkillEffect>,
+    /// Whether getting hit or moving breaks the cast
+    #[serde(default = "interruptible")]
+    pub interruptible: bool,
+}
+
+fn interruptible() -> bool {
+    true
+}
+
+fn max_level() -> u32 {
+    1
+}
+
+impl SkillDef {
+    /// The skill at `level`, with [`SkillDef::per_level`] added for every level past the first
+    pub fn at_level(&self, level: u32) -> SkillDef {
+        let ups = (level.clamp(1, self.max_level.max(1)) - 1) as f32;
+        let scaling = self.per_level;
+
+        let mut def = self.clone();
+        def.mp_cost = (def.mp_cost + scaling.mp_cost * ups).max(0.0);
+        def.cast_time = (def.cast_time + scaling.cast_time * ups).max(0.0);
+        for effect in &mut def.effects {
+            match effect {
+                SkillEffect::Damage { multiplier, .. } => *multiplier += scaling.multiplier * ups,
+                SkillEffect::Heal { base, .. } => *base += scaling.heal * ups,
+                SkillEffect::Status { chance, .. } => {
+                    *chance = (*chance + scaling.chance * ups).min(1.0)
+                }
+                SkillEffect::Knockback { .. } => {}
+            }
+        }
+        def
+    }
+}
+
+/// Every skill of the game by id, see `assets/skills.ron`
+#[derive(Asset, Resource, Reflect, Serialize, Deserialize, Debug, Clone)]
+#[reflect(Resource)]
+pub struct SkillDb {
+    pub skills: HashMap<u32, SkillDef>,
+}
+
+impl SkillDb {
+    /// Reads the database straight from disk, for apps without an [`AssetServer`]
+    pub fn read() -> Result<Self, Box<dyn Error>> {
+        read_ron(SKILL_DB_PATH)
+    }
+
+    pub fn get(&self, id: SkillId) -> Option<&SkillDef> {
+        self.skills.get(&id.0)
+    }
+}
+
+/// Where a skill goes off
+#[derive(Reflect, Debug, Clone, Copy, PartialEq)]
+pub enum CastTarget {
+    Caster,
+    Entity(Entity),
+    Ground(Vec3),
+}
+
+/// A skill being cast, the cast bar shows its progress
+#[derive(Component, Reflect, Debug, Clone)]
+#[reflect(Component)]
+pub struct Casting {
+    pub skill: SkillId,
+    /// Level it is cast at
+    pub level: u32,
+    pub target: CastTarget,
+    pub timer: Timer,
+    /// Where the caster stood when it started, walking away breaks the cast
+    pub origin: Vec3,
+}
+
+/// Cooldown of every skill used lately and the after-cast delay shared by all of them
+#[derive(Component, Reflect, Debug, Default, Clone)]
+#[reflect(Component)]
+pub struct SkillCooldowns {
+    pub skills: HashMap<SkillId, Timer>,
+    pub after_cast_delay: Timer,
+}
+
+impl SkillCooldowns {
+    /// Neither the skill nor the after-cast delay is running
+    pub fn ready(&self, skill: SkillId) -> bool {
+        self.after_cast_delay.finished()
+            && self.skills.get(&skill).is_none_or(|timer| timer.finished())
+    }
+
+    pub fn tick(&mut self, delta: Duration) {
+        self.after_cast_delay.tick(delta);
+        for timer in self.skills.values_mut() {
+            timer.tick(delta);
+        }
+    }
+
+    pub fn start(&mut self, skill: SkillId, def: &SkillDef) {
+        self.skills
+            .insert(skill, Timer::from_seconds(def.cooldown, TimerMode::Once));
+        self.after_cast_delay = Timer::from_seconds(def.after_cast_delay, TimerMode::Once);
+    }
+}
+
+/// Level of every skill a player learned, casting needs at least level 1
+#[derive(Component, Reflect, Debug, Default, Clone)]
+#[reflect(Component)]
+pub struct LearnedSkills(pub HashMap<SkillId, u32>);
+
+impl LearnedSkills {
+    /// 0 when not learned
+    pub fn level(&self, skill: SkillId) -> u32 {
+        self.0.get(&skill).copied().unwrap_or_default()
+    }
+}
+
+/// Number of skill slots on the hotbar, bound to the keys 1 to 9
+pub const HOTBAR_SLOTS: usize = 9;
+
+/// Skills on the player's hotbar
+#[derive(Component, Reflect, Debug, Default, Clone)]
+#[reflect(Component)]
+pub struct Hotbar(pub [Option<SkillId>; HOTBAR_SLOTS]);
+
+impl Hotbar {
+    /// Fills the first slots with `skills`
+    pub fn new(skills: impl IntoIterator<Item = u32>) -> Self {
+        let mut slots = [None; HOTBAR_SLOTS];
+        for (slot, skill) in slots.iter_mut().zip(skills) {
+            *slot = Some(SkillId(skill));
+        }
+        Self(slots)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn skill(max_level: u32) -> SkillDef {
+        SkillDef {
+            name: "Bash".into(),
+            max_level,
+            per_level: LevelScaling {
+                mp_cost: -2.0,
+                cast_time: 0.5,
+                multiplier: 0.3,
+                heal: 10.0,
+                chance: 0.2,
+            },
+            mp_cost: 5.0,
+            cast_time: 1.0,
+            cooldown: 2.0,
+            after_cast_delay: 0.5,
+            range: 2.0,
+            target: SkillTarget::Single,
+            effects: vec![
+                SkillEffect::Damage {
+                    kind: DamageKind::Physical,
+                    multiplier: 1.0,
+                    element: None,
+                },
+                SkillEffect::Heal {
+                    base: 20.0,
+                    magic_attack: 1.0,
+                },
+                SkillEffect::Status {
+                    status: 0,
+                    chance: 0.5,
+                    duration: None,
+                },
+            ],
+            interruptible: true,
+        }
+    }
+
+    fn secs(secs: f32) -> Duration {
+        Duration::from_secs_f32(secs)
+    }
+
+    #[test]
+    fn level_one_is_the_base() {
+        let def = skill(5).at_level(1);
+        assert_eq!(def.mp_cost, 5.0);
+        assert_eq!(def.cast_time, 1.0);
+        assert_eq!(def.effects, skill(5).effects);
+    }
+
+    #[test]
+    fn every_level_adds_the_scaling() {
+        let def = skill(5).at_level(3);
+        assert_eq!(def.mp_cost, 1.0);
+        assert_eq!(def.cast_time, 2.0);
+        let SkillEffect::Damage { multiplier, .. } = def.effects[0] else {
+            panic!("expected damage");
+        };
+        assert!((multiplier - 1.6).abs() < 1e-5);
+        let SkillEffect::Heal { base, .. } = def.effects[1] else {
+            panic!("expected a heal");
+        };
+        assert_eq!(base, 40.0);
+    }
+
+    #[test]
+    fn scaling_stays_in_bounds() {
+        let def = skill(5).at_level(5);
+        // 5 - 2 * 4 would be negative
+        assert_eq!(def.mp_cost, 0.0);
+        let SkillEffect::Status { chance, .. } = def.effects[2] else {
+            panic!("expected a status");
+        };
+        assert_eq!(chance, 1.0);
+    }
+
+    #[test]
+    fn level_clamped_to_the_max() {
+        assert_eq!(skill(3).at_level(10).cast_time, 2.0);
+        assert_eq!(skill(3).at_level(0).cast_time, 1.0);
+    }
+
+    #[test]
+    fn ready_once_the_cooldowns_expire() {
+        let id = SkillId(1);
+        let other = SkillId(2);
+        let mut cooldowns = SkillCooldowns::default();
+        assert!(cooldowns.ready(id));
+
+        cooldowns.start(id, &skill(1));
+        assert!(!cooldowns.ready(id));
+        // the after-cast delay holds back every skill
+        assert!(!cooldowns.ready(other));
+
+        cooldowns.tick(secs(0.5));
+        assert!(cooldowns.ready(other));
+        assert!(!cooldowns.ready(id));
+
+        cooldowns.tick(secs(1.5));
+        assert!(cooldowns.ready(id));
+    }
+}
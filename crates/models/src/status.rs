@@ -0,0 +1,296 @@
+use super::*;
+use std::{collections::HashMap, error::Error, time::Duration};
+
+/// Where [`StatusDb::read`] finds the database when the asset server isn't around
+pub const STATUS_DB_PATH: &str = "assets/statuses.ron";
+
+/// Id of a status effect in the [`StatusDb`]
+#[derive(Reflect, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash, Deref)]
+pub struct StatusId(pub u32);
+
+impl StatusId {
+    /// Source of the [`StatModifier`]s of this status
+    pub fn source(&self) -> String {
+        format!("status:{}", self.0)
+    }
+}
+
+/// What happens when a status lands on someone who has it already
+#[derive(Reflect, Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Stacking {
+    /// Starts the duration over
+    #[default]
+    Refresh,
+    /// Adds a stack up to `max` and starts the duration over, modifiers and ticks scale with
+    /// the stacks
+    Stack { max: u32 },
+    /// The running one stays as it is
+    Ignore,
+}
+
+/// Hp lost or restored every `interval` seconds, damage never kills
+#[derive(Reflect, Serialize, Deserialize, Debug, Clone, Copy)]
+pub struct StatusTick {
+    pub interval: f32,
+    /// Flat hp per tick, negative hurts
+    #[serde(default)]
+    pub hp: f32,
+    /// Fraction of max hp per tick, negative hurts
+    #[serde(default)]
+    pub max_hp: f32,
+}
+
+/// What the statuses of an entity keep it from doing, control, AI, combat and skills check it
+#[derive(
+    Component, Reflect, Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq,
+)]
+#[reflect(Component)]
+#[serde(default)]
+pub struct Lockouts {
+    pub movement: bool,
+    pub attack: bool,
+    pub cast: bool,
+}
+
+impl Lockouts {
+    pub fn merge(self, other: Self) -> Self {
+        Self {
+            movement: self.movement || other.movement,
+            attack: self.attack || other.attack,
+            cast: self.cast || other.cast,
+        }
+    }
+}
+
+#[derive(Reflect, Serialize, Deserialize, Debug, Clone)]
+pub struct StatusDef {
+    pub name: String,
+    /// Seconds it lasts unless the source says otherwise
+    pub duration: f32,
+    #[serde(default)]
+    pub stacking: Stacking,
+    /// Applied once per stack while it lasts
+    #[serde(default)]
+    pub modifiers: Vec<(Stat, ModifierKind)>,
+    #[serde(default)]
+    pub tick: Option<StatusTick>,
+    #[serde(default)]
+    pub lockouts: Lockouts,
+    /// Taking damage ends it, e.g. freeze
+    #[serde(default)]
+    pub break_on_hit: bool,
+    /// Seconds the target can't get it again once it ends
+    #[serde(default)]
+    pub immunity: f32,
+}
+
+/// Every status effect of the game by id, see `assets/statuses.ron`
+#[derive(Asset, Resource, Reflect, Serialize, Deserialize, Debug, Clone)]
+#[reflect(Resource)]
+pub struct StatusDb {
+    pub statuses: HashMap<u32, StatusDef>,
+}
+
+impl StatusDb {
+    /// Reads the database straight from disk, for apps without an [`AssetServer`]
+    pub fn read() -> Result<Self, Box<dyn Error>> {
+        read_ron(STATUS_DB_PATH)
+    }
+
+    pub fn get(&self, id: StatusId) -> Option<&StatusDef> {
+        self.statuses.get(&id.0)
+    }
+}
+
+/// One status running on an entity
+#[derive(Reflect, Debug, Clone)]
+pub struct ActiveStatus {
+    pub id: StatusId,
+    pub stacks: u32,
+    pub timer: Timer,
+    pub tick: Option<Timer>,
+    /// Whoever applied it last
+    pub source: Entity,
+}
+
+/// Statuses running on an entity and the ones it can't get
+#[derive(Component, Reflect, Debug, Default, Clone)]
+#[reflect(Component)]
+#[require(Lockouts)]
+pub struct StatusEffects {
+    pub active: Vec<ActiveStatus>,
+    /// Ended lately, immune until the timer runs out
+    pub immune: HashMap<StatusId, Timer>,
+    /// Never gets these, e.g. poison monsters and poison
+    pub resists: Vec<StatusId>,
+}
+
+impl StatusEffects {
+    pub fn resisting(resists: impl IntoIterator<Item = u32>) -> Self {
+        Self {
+            resists: resists.into_iter().map(StatusId).collect(),
+            ..default()
+        }
+    }
+
+    pub fn get(&self, id: StatusId) -> Option<&ActiveStatus> {
+        self.active.iter().find(|status| status.id == id)
+    }
+
+    pub fn is_immune(&self, id: StatusId) -> bool {
+        self.resists.contains(&id) || self.immune.contains_key(&id)
+    }
+
+    /// Puts the status on, or stacks and refreshes the running one as `def` says. False when
+    /// the running one is left alone, immunities are up to the caller.
+    pub fn apply(&mut self, id: StatusId, def: &StatusDef, duration: f32, source: Entity) -> bool {
+        let timer = Timer::from_seconds(duration, TimerMode::Once);
+        match self.active.iter_mut().find(|status| status.id == id) {
+            Some(_) if def.stacking == Stacking::Ignore => return false,
+            Some(status) => {
+                if let Stacking::Stack { max } = def.stacking {
+                    status.stacks = (status.stacks + 1).min(max);
+                }
+                status.timer = timer;
+                status.source = source;
+            }
+            None => self.active.push(ActiveStatus {
+                id,
+                stacks: 1,
+                timer,
+                tick: def
+                    .tick
+                    .map(|tick| Timer::from_seconds(tick.interval, TimerMode::Repeating)),
+                source,
+            }),
+        }
+        true
+    }
+
+    /// Runs the durations down and forgets the immunities that ran out
+    pub fn advance(&mut self, delta: Duration) {
+        self.immune.retain(|_, timer| !timer.tick(delta).finished());
+        for status in &mut self.active {
+            status.timer.tick(delta);
+        }
+    }
+
+    /// Takes out the statuses `ends` picks
+    pub fn take_where(&mut self, ends: impl FnMut(&ActiveStatus) -> bool) -> Vec<ActiveStatus> {
+        let (taken, kept) = std::mem::take(&mut self.active).into_iter().partition(ends);
+        self.active = kept;
+        taken
+    }
+
+    /// Can't get `id` again for `seconds`
+    pub fn make_immune(&mut self, id: StatusId, seconds: f32) {
+        if seconds > 0.0 {
+            let timer = Timer::from_seconds(seconds, TimerMode::Once);
+            self.immune.insert(id, timer);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const POISON: StatusId = StatusId(1);
+
+    fn status(stacking: Stacking) -> StatusDef {
+        StatusDef {
+            name: "Poison".into(),
+            duration: 10.0,
+            stacking,
+            modifiers: vec![],
+            tick: Some(StatusTick {
+                interval: 1.0,
+                hp: -5.0,
+                max_hp: 0.0,
+            }),
+            lockouts: Lockouts::default(),
+            break_on_hit: false,
+            immunity: 0.0,
+        }
+    }
+
+    fn secs(secs: f32) -> Duration {
+        Duration::from_secs_f32(secs)
+    }
+
+    /// Applies it again after `elapsed` seconds
+    fn reapply(effects: &mut StatusEffects, def: &StatusDef, elapsed: f32) -> bool {
+        effects.advance(secs(elapsed));
+        effects.apply(POISON, def, def.duration, Entity::PLACEHOLDER)
+    }
+
+    fn remaining(effects: &StatusEffects) -> f32 {
+        effects.get(POISON).unwrap().timer.remaining_secs()
+    }
+
+    #[test]
+    fn refresh_starts_over() {
+        let def = status(Stacking::Refresh);
+        let mut effects = StatusEffects::default();
+        assert!(effects.apply(POISON, &def, 10.0, Entity::PLACEHOLDER));
+        assert!(effects.get(POISON).unwrap().tick.is_some());
+
+        assert!(reapply(&mut effects, &def, 4.0));
+        assert_eq!(effects.active.len(), 1);
+        assert_eq!(effects.get(POISON).unwrap().stacks, 1);
+        assert!((remaining(&effects) - 10.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn stacks_up_to_max() {
+        let def = status(Stacking::Stack { max: 3 });
+        let mut effects = StatusEffects::default();
+        for _ in 0..5 {
+            assert!(effects.apply(POISON, &def, 10.0, Entity::PLACEHOLDER));
+        }
+        assert_eq!(effects.active.len(), 1);
+        assert_eq!(effects.get(POISON).unwrap().stacks, 3);
+    }
+
+    #[test]
+    fn ignore_keeps_the_running_one() {
+        let def = status(Stacking::Ignore);
+        let mut effects = StatusEffects::default();
+        assert!(effects.apply(POISON, &def, 10.0, Entity::PLACEHOLDER));
+        assert!(!reapply(&mut effects, &def, 4.0));
+        assert!((remaining(&effects) - 6.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn take_where_ends_only_the_picked() {
+        let def = status(Stacking::Refresh);
+        let mut effects = StatusEffects::default();
+        effects.apply(POISON, &def, 2.0, Entity::PLACEHOLDER);
+        effects.apply(StatusId(2), &def, 5.0, Entity::PLACEHOLDER);
+
+        effects.advance(secs(3.0));
+        let ended = effects.take_where(|status| status.timer.finished());
+        assert_eq!(ended.len(), 1);
+        assert_eq!(ended[0].id, POISON);
+        assert!(effects.get(POISON).is_none());
+        assert!(effects.get(StatusId(2)).is_some());
+    }
+
+    #[test]
+    fn immunity_window() {
+        let mut effects = StatusEffects::resisting([7]);
+        assert!(effects.is_immune(StatusId(7)));
+
+        effects.make_immune(POISON, 0.0);
+        assert!(!effects.is_immune(POISON));
+
+        effects.make_immune(POISON, 2.0);
+        assert!(effects.is_immune(POISON));
+        effects.advance(secs(1.5));
+        assert!(effects.is_immune(POISON));
+        effects.advance(secs(1.0));
+        assert!(!effects.is_immune(POISON));
+        // resists never run out
+        assert!(effects.is_immune(StatusId(7)));
+    }
+}
@@ -0,0 +1,127 @@
+//! Account checks the server runs before letting a client into the world.
+//!
+//! [`LocalServer`] asks an [`AuthService`] whether credentials are valid and which characters
+//! an account owns. [`MockAuth`] keeps a few accounts in memory so the login flow can be
+//! exercised without a backend.
+use super::*;
+
+/// Server-side identity of an account
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct AccountId(pub u64);
+
+pub trait AuthService: Send + Sync + 'static {
+    fn authenticate(&self, username: &str, password: &str) -> Result<AccountId, AuthError>;
+    /// Characters the account owns on `server`
+    fn characters(&self, account: AccountId, server: u32) -> Vec<CharacterInfo>;
+}
+
+#[derive(Debug, Clone)]
+struct MockAccount {
+    username: String,
+    password: String,
+    characters: Vec<CharacterInfo>,
+}
+
+/// In-memory accounts, every character is available on every server
+#[derive(Debug, Clone)]
+pub struct MockAuth {
+    accounts: Vec<MockAccount>,
+}
+
+impl MockAuth {
+    pub fn empty() -> Self {
+        Self { accounts: vec![] }
+    }
+
+    pub fn with_account(
+        mut self,
+        username: impl Into<String>,
+        password: impl Into<String>,
+        characters: Vec<CharacterInfo>,
+    ) -> Self {
+        self.accounts.push(MockAccount {
+            username: username.into(),
+            password: password.into(),
+            characters,
+        });
+        self
+    }
+}
+
+impl Default for MockAuth {
+    fn default() -> Self {
+        let character = |id, name: &str, job: &str, level| CharacterInfo {
+            id,
+            name: name.into(),
+            job: job.into(),
+            level,
+        };
+
+        Self::empty()
+            .with_account("guest", "guest", vec![character(1, "Guest", "Novice", 1)])
+            .with_account(
+                "admin",
+                "admin",
+                vec![
+                    character(2, "Poring Slayer", "Swordsman", 12),
+                    character(3, "Apprentice", "Mage", 5),
+                ],
+            )
+    }
+}
+
+impl AuthService for MockAuth {
+    fn authenticate(&self, username: &str, password: &str) -> Result<AccountId, AuthError> {
+        self.accounts
+            .iter()
+            .position(|a| a.username == username.trim() && a.password == password)
+            .map(|i| AccountId(i as u64))
+            .ok_or(AuthError::InvalidCredentials)
+    }
+
+    fn characters(&self, account: AccountId, _server: u32) -> Vec<CharacterInfo> {
+        self.accounts
+            .get(account.0 as usize)
+            .map(|a| a.characters.clone())
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn known_accounts_log_in() {
+        let auth = MockAuth::default();
+        let guest = auth.authenticate("guest", "guest").unwrap();
+        let admin = auth.authenticate(" admin ", "admin").unwrap();
+        assert_ne!(guest, admin);
+
+        let names: Vec<_> = auth
+            .characters(admin, 1)
+            .into_iter()
+            .map(|c| c.name)
+            .collect();
+        assert_eq!(names, ["Poring Slayer", "Apprentice"]);
+    }
+
+    #[test]
+    fn bad_credentials_are_rejected() {
+        let auth = MockAuth::default();
+        for (username, password) in [("guest", "wrong"), ("nobody", "guest"), ("", "")] {
+            assert_eq!(
+                auth.authenticate(username, password),
+                Err(AuthError::InvalidCredentials)
+            );
+        }
+        // only the username is trimmed
+        assert!(auth.authenticate("guest", " guest").is_err());
+    }
+
+    #[test]
+    fn unknown_accounts_have_no_characters() {
+        assert!(MockAuth::default().characters(AccountId(99), 1).is_empty());
+        assert!(MockAuth::empty().authenticate("guest", "guest").is_err());
+    }
+}
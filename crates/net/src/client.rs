@@ -0,0 +1,100 @@
+use super::*;
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<ServerClock>()
+        .add_event::<ToServer>()
+        .add_event::<FromServer>()
+        .configure_sets(PreUpdate, NetSet::Receive)
+        .configure_sets(PostUpdate, NetSet::Send)
+        .add_systems(
+            PreUpdate,
+            receive
+                .in_set(NetSet::Receive)
+                .run_if(resource_exists::<NetClient>),
+        )
+        .add_systems(
+            PostUpdate,
+            send.in_set(NetSet::Send)
+                .run_if(resource_exists::<NetClient>),
+        );
+}
+
+/// Incoming messages are read before [`Update`] so gameplay sees them in the same frame,
+/// outgoing ones are flushed after it so everything written during the frame goes out together.
+#[derive(SystemSet, Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum NetSet {
+    Receive,
+    Send,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Reflect)]
+pub enum ConnectionState {
+    #[default]
+    Disconnected,
+    /// Logged in, picking a server and a character
+    Authenticated,
+    InWorld(NetId),
+}
+
+#[derive(Resource)]
+pub struct NetClient {
+    transport: Box<dyn Transport>,
+    pub state: ConnectionState,
+}
+
+impl NetClient {
+    pub fn new(transport: impl Transport) -> Self {
+        Self {
+            transport: Box::new(transport),
+            state: ConnectionState::Disconnected,
+        }
+    }
+
+    /// [`NetId`] of the character controlled by this client
+    pub fn player(&self) -> Option<NetId> {
+        match self.state {
+            ConnectionState::InWorld(id) => Some(id),
+            _ => None,
+        }
+    }
+}
+
+/// Write this event to send a message to the server
+#[derive(Event, Debug, Clone, Deref)]
+pub struct ToServer(pub ClientMessage);
+
+/// Emitted for every message received from the server
+#[derive(Event, Debug, Clone, Deref)]
+pub struct FromServer(pub ServerMessage);
+
+fn receive(mut client: ResMut<NetClient>, mut incoming: EventWriter<FromServer>) {
+    while let Some(packet) = client.transport.recv() {
+        let msg = match decode::<ServerMessage>(&packet) {
+            Ok(msg) => msg,
+            Err(e) => {
+                warn!("dropping server packet: {e}");
+                continue;
+            }
+        };
+
+        match msg {
+            ServerMessage::AuthAccepted { .. } => {
+                client.state = ConnectionState::Authenticated;
+            }
+            ServerMessage::EnteredWorld { player } => {
+                client.state = ConnectionState::InWorld(player);
+            }
+            _ => {}
+        }
+
+        incoming.write(FromServer(msg));
+    }
+}
+
+fn send(mut client: ResMut<NetClient>, mut outgoing: EventReader<ToServer>) {
+    for ToServer(msg) in outgoing.read() {
+        if let Err(e) = encode(msg).and_then(|packet| client.transport.send(packet)) {
+            error!("failed to send {msg:?}: {e}");
+        }
+    }
+}
@@ -0,0 +1,186 @@
+//! Snapshot interpolation for entities simulated by the server.
+//!
+//! Remote entities are rendered a fixed delay in the past, so there are (usually) two buffered
+//! snapshots around the render time to blend between. When packets are late and the buffer runs
+//! dry, motion is extrapolated from the last two snapshots for a short while and then held.
+use super::*;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Snapshot {
+    pub time: f64,
+    pub position: Vec3,
+    pub rotation: Quat,
+    pub animation: AnimationState,
+}
+
+/// Marks an entity driven by server snapshots instead of local simulation
+#[derive(Component, Debug, Default)]
+pub struct Interpolated {
+    buffer: VecDeque<Snapshot>,
+}
+
+impl Interpolated {
+    pub const CAPACITY: usize = 32;
+
+    pub fn push(&mut self, snapshot: Snapshot) {
+        // late packets are useless once a newer snapshot arrived
+        if let Some(last) = self.buffer.back()
+            && last.time >= snapshot.time
+        {
+            return;
+        }
+
+        if self.buffer.len() == Self::CAPACITY {
+            self.buffer.pop_front();
+        }
+        self.buffer.push_back(snapshot);
+    }
+
+    /// Forgets snapshots that can no longer be blended from, keeping the last one before `time`
+    pub fn prune(&mut self, time: f64) {
+        while self.buffer.len() > 2 && self.buffer[1].time <= time {
+            self.buffer.pop_front();
+        }
+    }
+
+    /// State of the entity at `time`. Extrapolates at most `max_extrapolation` seconds past
+    /// the newest snapshot.
+    pub fn sample(&self, time: f64, max_extrapolation: f64) -> Option<Snapshot> {
+        let first = self.buffer.front()?;
+        let last = self.buffer.back()?;
+
+        if time <= first.time {
+            return Some(first.clone());
+        }
+
+        if time >= last.time {
+            let Some(prev) = self.buffer.iter().rev().nth(1) else {
+                return Some(last.clone());
+            };
+            let ahead = (time - last.time).min(max_extrapolation);
+            let velocity = (last.position - prev.position) / (last.time - prev.time) as f32;
+            return Some(Snapshot {
+                time,
+                position: last.position + velocity * ahead as f32,
+                ..last.clone()
+            });
+        }
+
+        let (from, to) = self
+            .buffer
+            .iter()
+            .zip(self.buffer.iter().skip(1))
+            .find(|(_, to)| to.time >= time)?;
+        let t = ((time - from.time) / (to.time - from.time)) as f32;
+
+        Some(Snapshot {
+            time,
+            position: from.position.lerp(to.position, t),
+            rotation: from.rotation.slerp(to.rotation, t),
+            animation: from.animation.clone(),
+        })
+    }
+}
+
+/// Estimates the server clock from snapshot timestamps
+#[derive(Resource, Debug, Default)]
+pub struct ServerClock {
+    /// server time - local time
+    offset: Option<f64>,
+}
+
+impl ServerClock {
+    /// How much a new observation moves the estimate, smooths out jitter
+    const SMOOTHING: f64 = 0.1;
+
+    pub fn observe(&mut self, server_time: f64, local_time: f64) {
+        let offset = server_time - local_time;
+        self.offset = Some(match self.offset {
+            // a packet arriving early means the previous ones were delayed, catch up right away
+            Some(current) if offset > current => offset,
+            Some(current) => current + (offset - current) * Self::SMOOTHING,
+            None => offset,
+        });
+    }
+
+    /// Server time corresponding to `local_time`, if any snapshot arrived yet
+    pub fn now(&self, local_time: f64) -> Option<f64> {
+        self.offset.map(|offset| local_time + offset)
+    }
+
+    pub fn reset(&mut self) {
+        self.offset = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot(time: f64, x: f32) -> Snapshot {
+        Snapshot {
+            time,
+            position: Vec3::new(x, 0.0, 0.0),
+            rotation: Quat::IDENTITY,
+            animation: AnimationState::default(),
+        }
+    }
+
+    /// Moving 10 units/s along x, a snapshot per second
+    fn buffer() -> Interpolated {
+        let mut buffer = Interpolated::default();
+        buffer.push(snapshot(0.0, 0.0));
+        buffer.push(snapshot(1.0, 10.0));
+        buffer
+    }
+
+    #[test]
+    fn empty_buffer_has_nothing() {
+        assert_eq!(Interpolated::default().sample(1.0, 0.25), None);
+    }
+
+    #[test]
+    fn blends_between_snapshots() {
+        let sample = buffer().sample(0.5, 0.25).unwrap();
+        assert_eq!(sample.time, 0.5);
+        assert!((sample.position.x - 5.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn holds_the_first_snapshot_before_it() {
+        assert_eq!(buffer().sample(-1.0, 0.25), Some(snapshot(0.0, 0.0)));
+    }
+
+    #[test]
+    fn extrapolation_is_clamped() {
+        let buffer = buffer();
+        let close = buffer.sample(1.1, 0.25).unwrap();
+        assert!((close.position.x - 11.0).abs() < 1e-4);
+        // way past the newest snapshot it stops `max_extrapolation` ahead
+        let far = buffer.sample(3.0, 0.25).unwrap();
+        assert!((far.position.x - 12.5).abs() < 1e-4);
+    }
+
+    #[test]
+    fn a_single_snapshot_is_held() {
+        let mut buffer = Interpolated::default();
+        buffer.push(snapshot(1.0, 4.0));
+        assert_eq!(buffer.sample(2.0, 0.25).unwrap().position.x, 4.0);
+    }
+
+    #[test]
+    fn late_snapshots_are_dropped() {
+        let mut buffer = buffer();
+        buffer.push(snapshot(0.5, 100.0));
+        assert!((buffer.sample(0.5, 0.25).unwrap().position.x - 5.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn prune_keeps_what_can_be_blended() {
+        let mut buffer = buffer();
+        buffer.push(snapshot(2.0, 20.0));
+        buffer.prune(1.5);
+        assert!((buffer.sample(1.5, 0.25).unwrap().position.x - 15.0).abs() < 1e-5);
+        assert_eq!(buffer.sample(0.0, 0.25), Some(snapshot(1.0, 10.0)));
+    }
+}
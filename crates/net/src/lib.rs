@@ -0,0 +1,54 @@
+//! Client side of the network layer
+//!
+//! Everything that goes over the wire is a [`ClientMessage`] or a [`ServerMessage`] wrapped in a
+//! versioned envelope (see [`encode`] and [`decode`]). Gameplay code never touches the transport:
+//! it writes [`ToServer`] events and reads [`FromServer`] events, the plugin does the rest.
+//!
+//! ```text
+//! ┌────────┐ ToServer  ┌─────────┐ bytes ┌───────────┐ bytes ┌─────────────┐
+//! │Gameplay├──────────►│NetClient├──────►│ Transport ├──────►│ LocalServer │
+//! │systems │◄──────────┤         │◄──────┤(Loopback) │◄──────┤ or backend  │
+//! └────────┘ FromServer└─────────┘       └───────────┘       └─────────────┘
+//! ```
+//!
+//! Until there is a real backend, [`local_server_plugin`] runs a [`LocalServer`] in-process and
+//! connects the client to it through a [`Loopback`] transport. The dedicated server runs the
+//! same [`LocalServer`] on a [`UdpHost`] through [`host_plugin`], with a session per client.
+use bevy::prelude::*;
+use models::*;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{Arc, Mutex},
+};
+use thiserror::Error;
+
+mod auth;
+mod client;
+mod interpolation;
+mod prediction;
+mod protocol;
+mod server;
+mod transport;
+
+pub use auth::*;
+pub use client::*;
+pub use interpolation::*;
+pub use prediction::*;
+pub use protocol::*;
+pub use server::*;
+pub use transport::*;
+
+pub fn plugin(app: &mut App) {
+    app.add_plugins(client::plugin);
+}
+
+/// Spawns an in-process [`LocalServer`] and connects [`NetClient`] to it
+pub fn local_server_plugin(app: &mut App) {
+    app.add_plugins(server::plugin);
+}
+
+/// Ticks the [`LocalServer`] inserted by the app, for servers clients connect to from outside
+pub fn host_plugin(app: &mut App) {
+    app.add_plugins(server::host_plugin);
+}
@@ -0,0 +1,178 @@
+//! Client-side prediction for the locally controlled character.
+//!
+//! Inputs are applied locally right away and remembered together with their sequence number.
+//! When the server acknowledges an input with an authoritative position, everything up to that
+//! input is forgotten and the inputs the server has not seen yet are replayed on top of it.
+//! The difference between that and where the character actually is becomes a correction that
+//! is blended in over a few frames instead of snapping the [`Transform`].
+use super::*;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PredictedInput {
+    pub seq: u32,
+    pub velocity: Vec3,
+    pub dt: f32,
+}
+
+impl PredictedInput {
+    pub fn displacement(&self) -> Vec3 {
+        Vec3::new(self.velocity.x, 0.0, self.velocity.z) * self.dt
+    }
+}
+
+/// Inputs sent to the server that were not acknowledged yet
+#[derive(Component, Debug, Default)]
+pub struct PredictionHistory {
+    next_seq: u32,
+    last_velocity: Vec3,
+    pending: VecDeque<PredictedInput>,
+}
+
+impl PredictionHistory {
+    /// Hard cap on remembered inputs, in case the server stops acknowledging them
+    pub const MAX_PENDING: usize = 256;
+
+    /// Whether an input with this velocity has to be sent at all.
+    /// Standing still is only worth one message.
+    pub fn should_send(&self, velocity: Vec3) -> bool {
+        velocity != Vec3::ZERO || self.last_velocity != Vec3::ZERO
+    }
+
+    /// Tags the input with the next sequence number and remembers it for reconciliation
+    pub fn record(&mut self, velocity: Vec3, dt: f32) -> PredictedInput {
+        let input = PredictedInput {
+            seq: self.next_seq,
+            velocity,
+            dt,
+        };
+        self.next_seq = self.next_seq.wrapping_add(1);
+        self.last_velocity = velocity;
+
+        if self.pending.len() == Self::MAX_PENDING {
+            self.pending.pop_front();
+        }
+        self.pending.push_back(input);
+        input
+    }
+
+    /// Drops every input up to and including `ack` and returns the position the character should
+    /// be at: the authoritative one with the remaining inputs replayed on top
+    pub fn reconcile(&mut self, ack: u32, authoritative: Vec3) -> Vec3 {
+        // wrapping comparison, sequence numbers overflow on long sessions
+        while let Some(input) = self.pending.front()
+            && (ack.wrapping_sub(input.seq) as i32) >= 0
+        {
+            self.pending.pop_front();
+        }
+
+        self.pending
+            .iter()
+            .fold(authoritative, |pos, input| pos + input.displacement())
+    }
+
+    pub fn pending(&self) -> usize {
+        self.pending.len()
+    }
+}
+
+/// Error between the predicted and the authoritative position that is still to be applied
+#[derive(Component, Debug, Default, Deref, DerefMut)]
+pub struct PositionCorrection(pub Vec3);
+
+impl PositionCorrection {
+    /// Returns the part of the correction to apply this frame and keeps the rest.
+    /// `smoothing` is the rate in 1/s, higher values converge faster.
+    pub fn step(&mut self, smoothing: f32, dt: f32) -> Vec3 {
+        let t = 1.0 - (-smoothing * dt).exp();
+        let step = self.0 * t;
+        self.0 -= step;
+        if self.0.length_squared() < 1e-6 {
+            self.0 = Vec3::ZERO;
+        }
+        step
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DT: f32 = 0.5;
+
+    /// History of three inputs of 1 unit each along x
+    fn history() -> PredictionHistory {
+        let mut history = PredictionHistory::default();
+        for _ in 0..3 {
+            history.record(Vec3::new(2.0, 0.0, 0.0), DT);
+        }
+        history
+    }
+
+    #[test]
+    fn acked_inputs_are_dropped() {
+        let mut history = history();
+        let position = history.reconcile(0, Vec3::new(1.0, 0.0, 0.0));
+        assert_eq!(history.pending(), 2);
+        // the server agrees, so replaying the rest lands where we predicted
+        assert_eq!(position, Vec3::new(3.0, 0.0, 0.0));
+
+        let position = history.reconcile(2, Vec3::new(3.0, 0.0, 0.0));
+        assert_eq!(position, Vec3::new(3.0, 0.0, 0.0));
+        assert_eq!(history.pending(), 0);
+    }
+
+    #[test]
+    fn misprediction_replays_pending_inputs() {
+        let mut history = history();
+        // the server stopped us at a wall after the first input
+        let position = history.reconcile(0, Vec3::new(0.5, 0.0, 2.0));
+        assert_eq!(position, Vec3::new(2.5, 0.0, 2.0));
+        assert_eq!(history.pending(), 2);
+    }
+
+    #[test]
+    fn old_acks_change_nothing() {
+        let mut history = history();
+        history.reconcile(1, Vec3::ZERO);
+        history.reconcile(0, Vec3::ZERO);
+        assert_eq!(history.pending(), 1);
+    }
+
+    #[test]
+    fn sequence_numbers_wrap() {
+        let mut history = PredictionHistory {
+            next_seq: u32::MAX,
+            ..default()
+        };
+        let first = history.record(Vec3::X, DT);
+        let second = history.record(Vec3::X, DT);
+        assert_eq!((first.seq, second.seq), (u32::MAX, 0));
+
+        history.reconcile(u32::MAX, Vec3::ZERO);
+        assert_eq!(history.pending(), 1);
+        history.reconcile(0, Vec3::ZERO);
+        assert_eq!(history.pending(), 0);
+    }
+
+    #[test]
+    fn history_is_capped() {
+        let mut history = PredictionHistory::default();
+        for _ in 0..PredictionHistory::MAX_PENDING + 10 {
+            history.record(Vec3::X, DT);
+        }
+        assert_eq!(history.pending(), PredictionHistory::MAX_PENDING);
+    }
+
+    #[test]
+    fn correction_is_blended_in() {
+        let mut correction = PositionCorrection(Vec3::new(1.0, 0.0, 0.0));
+        let step = correction.step(10.0, 0.1);
+        assert!(step.x > 0.0 && step.x < 1.0);
+        assert!((step.x + correction.x - 1.0).abs() < 1e-6);
+
+        for _ in 0..100 {
+            correction.step(10.0, 0.1);
+        }
+        assert_eq!(correction.0, Vec3::ZERO);
+    }
+}
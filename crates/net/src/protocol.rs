@@ -0,0 +1,242 @@
+//! Wire protocol shared by the client and the server.
+//!
+//! Bump [`PROTOCOL_VERSION`] whenever a message changes shape, peers with a different
+//! version are rejected by [`decode`] instead of misreading each other.
+use super::*;
+use serde::de::DeserializeOwned;
+
+pub const PROTOCOL_VERSION: u16 = 9;
+
+/// Server-assigned identity of a replicated entity. Local [`Entity`] ids are meaningless to
+/// the other side, so everything on the wire refers to entities by [`NetId`].
+#[derive(Component, Reflect, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[reflect(Component)]
+pub struct NetId(pub u64);
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EntityKind {
+    Player,
+    Enemy,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct EntitySnapshot {
+    pub id: NetId,
+    pub position: Vec3,
+    pub rotation: Quat,
+    pub animation: AnimationState,
+}
+
+/// A world server the account can play on
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ServerInfo {
+    pub id: u32,
+    pub name: String,
+    pub population: u32,
+}
+
+/// A character of the account, as listed on the character select screen
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct CharacterInfo {
+    pub id: u32,
+    pub name: String,
+    pub job: String,
+    pub level: u32,
+}
+
+/// Why the server refused a step of the login flow
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Error)]
+pub enum AuthError {
+    #[error("Wrong username or password")]
+    InvalidCredentials,
+    #[error("Not logged in")]
+    NotAuthenticated,
+    #[error("Server is not available")]
+    UnknownServer,
+    #[error("Character not found")]
+    UnknownCharacter,
+}
+
+/// Messages sent by the client
+///
+/// Entering the world goes through `Login` -> `SelectServer` -> `SelectCharacter` ->
+/// `EnterWorld`, every step has to be accepted before the next one.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum ClientMessage {
+    Login {
+        username: String,
+        password: String,
+    },
+    SelectServer {
+        server: u32,
+    },
+    SelectCharacter {
+        character: u32,
+    },
+    EnterWorld,
+    Logout,
+    /// Desired planar velocity of the controlled character over `dt` seconds.
+    /// `seq` is echoed back in [`ServerMessage::PlayerState`] once the input is processed.
+    MoveIntent {
+        seq: u32,
+        velocity: Vec3,
+        dt: f32,
+        animation: AnimationState,
+    },
+    AttackIntent {
+        target: NetId,
+    },
+    /// The dead character comes back at the save point the server keeps for it
+    Respawn,
+}
+
+/// Messages sent by the server
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub enum ServerMessage {
+    AuthAccepted {
+        servers: Vec<ServerInfo>,
+    },
+    AuthRejected {
+        reason: AuthError,
+    },
+    CharacterList {
+        characters: Vec<CharacterInfo>,
+    },
+    CharacterSelected {
+        character: u32,
+    },
+    EnteredWorld {
+        player: NetId,
+    },
+    EntitySpawned {
+        id: NetId,
+        kind: EntityKind,
+        position: Vec3,
+    },
+    EntityDespawned {
+        id: NetId,
+    },
+    EntityMoved {
+        id: NetId,
+        position: Vec3,
+    },
+    /// Authoritative position of the client's own character after processing
+    /// every [`ClientMessage::MoveIntent`] up to and including `ack`
+    PlayerState {
+        ack: u32,
+        position: Vec3,
+    },
+    /// State of every replicated entity at server `time`, in seconds
+    Snapshot {
+        time: f64,
+        entities: Vec<EntitySnapshot>,
+    },
+    AttributesUpdated {
+        id: NetId,
+        attributes: ComputedAttributes,
+    },
+}
+
+/// Possible errors that can be produced while sending or receiving messages
+#[non_exhaustive]
+#[derive(Debug, Error)]
+pub enum NetError {
+    /// A [RON Error](ron::Error) while serializing
+    #[error("Could not encode message: {0}")]
+    Encode(#[from] ron::Error),
+    /// A [RON Error](ron::error::SpannedError) while parsing
+    #[error("Could not decode message: {0}")]
+    Decode(#[from] ron::error::SpannedError),
+    #[error("Protocol version mismatch: expected {expected}, got {found}")]
+    VersionMismatch { expected: u16, found: u16 },
+    #[error("Transport is closed")]
+    Closed,
+    #[error("Transport failed: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+#[derive(Serialize, Deserialize)]
+struct Envelope<T> {
+    version: u16,
+    message: T,
+}
+
+/// Only the version, so it can be checked before the payload is parsed
+#[derive(Deserialize)]
+struct Header {
+    version: u16,
+}
+
+pub fn encode<T: Serialize>(message: &T) -> Result<Vec<u8>, NetError> {
+    let envelope = Envelope {
+        version: PROTOCOL_VERSION,
+        message,
+    };
+    Ok(ron::to_string(&envelope)?.into_bytes())
+}
+
+pub fn decode<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, NetError> {
+    let Header { version } = ron::de::from_bytes(bytes)?;
+    if version != PROTOCOL_VERSION {
+        return Err(NetError::VersionMismatch {
+            expected: PROTOCOL_VERSION,
+            found: version,
+        });
+    }
+
+    let envelope: Envelope<T> = ron::de::from_bytes(bytes)?;
+    Ok(envelope.message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn client_message_round_trip() {
+        let msg = ClientMessage::MoveIntent {
+            seq: 42,
+            velocity: Vec3::new(1.0, 0.0, -2.5),
+            dt: 0.016,
+            animation: AnimationState::default(),
+        };
+        let bytes = encode(&msg).unwrap();
+        assert_eq!(decode::<ClientMessage>(&bytes).unwrap(), msg);
+    }
+
+    #[test]
+    fn server_message_round_trip() {
+        let msg = ServerMessage::PlayerState {
+            ack: 7,
+            position: Vec3::new(3.0, 1.0, 4.0),
+        };
+        let bytes = encode(&msg).unwrap();
+        let ServerMessage::PlayerState { ack, position } = decode(&bytes).unwrap() else {
+            panic!("decoded into another message");
+        };
+        assert_eq!((ack, position), (7, Vec3::new(3.0, 1.0, 4.0)));
+    }
+
+    #[test]
+    fn other_versions_are_rejected() {
+        let envelope = Envelope {
+            version: PROTOCOL_VERSION + 1,
+            message: ClientMessage::EnterWorld,
+        };
+        let bytes = ron::to_string(&envelope).unwrap().into_bytes();
+
+        let Err(NetError::VersionMismatch { expected, found }) = decode::<ClientMessage>(&bytes)
+        else {
+            panic!("a newer peer was not rejected");
+        };
+        assert_eq!((expected, found), (PROTOCOL_VERSION, PROTOCOL_VERSION + 1));
+    }
+
+    #[test]
+    fn garbage_does_not_decode() {
+        assert!(matches!(
+            decode::<ClientMessage>(b"not a packet"),
+            Err(NetError::Decode(_))
+        ));
+    }
+}
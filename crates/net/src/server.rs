@@ -0,0 +1,751 @@
+//! Local stand-in for the game server.
+//!
+//! Speaks the same protocol as the real backend would, but keeps its state in memory and is
+//! driven by [`LocalServer::tick`]. Good enough to run the client without a backend and to
+//! exercise the whole message flow from tests.
+use super::*;
+use std::collections::HashSet;
+
+pub(super) fn plugin(app: &mut App) {
+    let (client, server) = Loopback::pair();
+    app.insert_resource(NetClient::new(client))
+        .insert_resource(LocalServer::new(server))
+        .add_plugins(host_plugin);
+}
+
+/// Runs the [`LocalServer`] the app inserted, whatever transport it listens on
+pub(super) fn host_plugin(app: &mut App) {
+    app.add_systems(FixedUpdate, tick_local_server).add_systems(
+        Update,
+        sync_save_point.run_if(resource_exists::<Config>.and(resource_changed::<Config>)),
+    );
+}
+
+fn tick_local_server(time: Res<Time>, mut server: ResMut<LocalServer>) {
+    server.tick(time.delta_secs());
+}
+
+/// The config is an asset on the client, it shows up after the server started
+fn sync_save_point(cfg: Res<Config>, mut server: ResMut<LocalServer>) {
+    server.save_point = cfg.respawn.save_point;
+}
+
+/// Longest step a single move input may cover, so a client can't teleport by lying about `dt`
+const MAX_INPUT_DT: f32 = 0.25;
+/// Seconds between two world snapshots
+const SNAPSHOT_INTERVAL: f64 = 0.05;
+/// Where characters enter the world and come back after dying, until the config says otherwise
+const DEFAULT_SAVE_POINT: Vec3 = Vec3::new(0.0, 5.0, 0.0);
+/// Reach past the attack range for the colliders of both sides and a client slightly ahead of
+/// the server, the host's combat checks the exact range
+const ATTACK_RANGE_SLACK: f32 = 2.0;
+
+#[derive(Debug, Clone)]
+pub struct ServerEntity {
+    pub kind: EntityKind,
+    pub position: Vec3,
+    pub rotation: Quat,
+    pub velocity: Vec3,
+    pub animation: AnimationState,
+    pub attributes: ComputedAttributes,
+}
+
+impl ServerEntity {
+    fn face(&mut self, direction: Vec3) {
+        let direction = direction.with_y(0.0);
+        if direction != Vec3::ZERO {
+            self.rotation = Transform::IDENTITY.looking_to(direction, Vec3::Y).rotation;
+        }
+    }
+}
+
+/// An attack a client asked for that passed the checks of the server, see
+/// [`LocalServer::take_attacks`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AcceptedAttack {
+    pub attacker: NetId,
+    pub target: NetId,
+}
+
+/// Why a [`ClientMessage::AttackIntent`] was ignored
+#[derive(Error, Debug)]
+enum AttackRejected {
+    #[error("no such target")]
+    NoTarget,
+    #[error("can't attack itself")]
+    Itself,
+    #[error("players only attack enemies")]
+    Friendly,
+    #[error("the dead don't fight")]
+    Dead,
+    #[error("out of range")]
+    OutOfRange,
+    #[error("faster than the attack rate")]
+    TooFast,
+}
+
+/// Progress of a client through the login flow, one per [`Peer`] that logged in
+#[derive(Debug, Default)]
+struct Session {
+    account: Option<AccountId>,
+    server: Option<u32>,
+    character: Option<CharacterInfo>,
+    /// Entity of the character once it entered the world
+    player: Option<NetId>,
+    /// Last processed move input, acknowledged on the next tick
+    ack: Option<u32>,
+    /// Server time of the last accepted attack
+    last_attack: Option<f64>,
+    /// Accepted attack the host didn't take yet
+    attack: Option<AcceptedAttack>,
+    /// Asked to come back at the save point, the host didn't take it yet
+    respawn: bool,
+}
+
+#[derive(Resource)]
+pub struct LocalServer {
+    transport: Box<dyn Listener>,
+    auth: Box<dyn AuthService>,
+    servers: Vec<ServerInfo>,
+    next_id: u64,
+    /// Only a successful login opens a session, anything else from an unknown peer is refused
+    sessions: HashMap<Peer, Session>,
+    entities: HashMap<NetId, ServerEntity>,
+    /// Entities whose attributes changed since the last snapshot
+    changed: HashSet<NetId>,
+    /// Seconds since the server started, stamped on snapshots
+    time: f64,
+    last_snapshot: f64,
+    /// Where characters enter the world and respawn, clients don't get a say
+    pub save_point: Vec3,
+}
+
+impl LocalServer {
+    pub fn new(transport: impl Listener) -> Self {
+        Self {
+            transport: Box::new(transport),
+            auth: Box::new(MockAuth::default()),
+            servers: vec![ServerInfo {
+                id: 1,
+                name: "Local".into(),
+                population: 0,
+            }],
+            next_id: 0,
+            sessions: HashMap::new(),
+            entities: HashMap::new(),
+            changed: HashSet::new(),
+            time: 0.0,
+            last_snapshot: 0.0,
+            save_point: DEFAULT_SAVE_POINT,
+        }
+    }
+
+    pub fn with_auth(mut self, auth: impl AuthService) -> Self {
+        self.auth = Box::new(auth);
+        self
+    }
+
+    pub fn entity(&self, id: NetId) -> Option<&ServerEntity> {
+        self.entities.get(&id)
+    }
+
+    pub fn entities(&self) -> impl Iterator<Item = (NetId, &ServerEntity)> {
+        self.entities.iter().map(|(id, entity)| (*id, entity))
+    }
+
+    /// Adds an entity to the world and announces it to the clients in it
+    pub fn spawn(
+        &mut self,
+        kind: EntityKind,
+        position: Vec3,
+        attributes: ComputedAttributes,
+    ) -> NetId {
+        let id = self.insert(kind, position, attributes);
+        self.broadcast(ServerMessage::EntitySpawned { id, kind, position });
+        id
+    }
+
+    /// Adds an entity to the world without telling anyone
+    fn insert(
+        &mut self,
+        kind: EntityKind,
+        position: Vec3,
+        attributes: ComputedAttributes,
+    ) -> NetId {
+        self.next_id += 1;
+        let id = NetId(self.next_id);
+        self.entities.insert(
+            id,
+            ServerEntity {
+                kind,
+                position,
+                rotation: Quat::IDENTITY,
+                velocity: Vec3::ZERO,
+                animation: AnimationState::default(),
+                attributes,
+            },
+        );
+        id
+    }
+
+    /// Moves an entity the host simulates, the clients see it with the next snapshot
+    pub fn place(&mut self, id: NetId, position: Vec3, rotation: Quat) {
+        if let Some(entity) = self.entities.get_mut(&id) {
+            entity.position = position;
+            entity.rotation = rotation;
+        }
+    }
+
+    /// Attributes the host computed after hits, heals and level ups, sent along with the next
+    /// snapshot
+    pub fn set_attributes(&mut self, id: NetId, attributes: ComputedAttributes) {
+        if let Some(entity) = self.entities.get_mut(&id) {
+            entity.attributes = attributes;
+            self.changed.insert(id);
+        }
+    }
+
+    /// Players that asked to respawn since the last call, the server already put them back at
+    /// the save point
+    pub fn take_respawns(&mut self) -> Vec<NetId> {
+        self.sessions
+            .values_mut()
+            .filter_map(|session| {
+                let respawn = std::mem::take(&mut session.respawn);
+                session.player.filter(|_| respawn)
+            })
+            .collect()
+    }
+
+    /// Attacks accepted since the last call, the host resolves them through its combat so
+    /// every hit is rolled on the server
+    pub fn take_attacks(&mut self) -> Vec<AcceptedAttack> {
+        self.sessions
+            .values_mut()
+            .filter_map(|session| session.attack.take())
+            .collect()
+    }
+
+    pub fn despawn(&mut self, id: NetId) {
+        if self.entities.remove(&id).is_some() {
+            self.broadcast(ServerMessage::EntityDespawned { id });
+        }
+    }
+
+    /// Processes everything the clients sent since the last tick and advances the simulation
+    pub fn tick(&mut self, dt: f32) {
+        self.time += dt as f64;
+
+        while let Some((peer, packet)) = self.transport.recv_from() {
+            match decode::<ClientMessage>(&packet) {
+                Ok(msg) => self.handle(peer, msg),
+                Err(e) => warn!("local server: dropping packet of {peer:?}: {e}"),
+            }
+        }
+
+        let mut moved = vec![];
+        for (id, entity) in self.entities.iter_mut() {
+            if entity.velocity != Vec3::ZERO {
+                entity.position += entity.velocity * dt;
+                let velocity = entity.velocity;
+                entity.face(velocity);
+                moved.push(ServerMessage::EntityMoved {
+                    id: *id,
+                    position: entity.position,
+                });
+            }
+        }
+        for msg in moved {
+            self.broadcast(msg);
+        }
+
+        let mut acks = vec![];
+        for (peer, session) in self.sessions.iter_mut() {
+            if let Some(ack) = session.ack.take()
+                && let Some(player) = session.player.and_then(|id| self.entities.get(&id))
+            {
+                let position = player.position;
+                acks.push((*peer, ServerMessage::PlayerState { ack, position }));
+            }
+        }
+        for (peer, msg) in acks {
+            self.send(peer, msg);
+        }
+
+        if self.time - self.last_snapshot >= SNAPSHOT_INTERVAL {
+            self.last_snapshot = self.time;
+            self.send_snapshot();
+        }
+    }
+
+    fn send_snapshot(&mut self) {
+        let entities = self
+            .entities
+            .iter()
+            .map(|(id, e)| EntitySnapshot {
+                id: *id,
+                position: e.position,
+                rotation: e.rotation,
+                animation: e.animation.clone(),
+            })
+            .collect();
+
+        self.broadcast(ServerMessage::Snapshot {
+            time: self.time,
+            entities,
+        });
+
+        let changed: Vec<_> = self
+            .changed
+            .drain()
+            .filter_map(|id| self.entities.get(&id).map(|e| (id, e.attributes)))
+            .collect();
+        for (id, attributes) in changed {
+            self.broadcast(ServerMessage::AttributesUpdated { id, attributes });
+        }
+    }
+
+    fn handle(&mut self, peer: Peer, msg: ClientMessage) {
+        if let ClientMessage::Login { username, password } = msg {
+            let account = match self.auth.authenticate(&username, &password) {
+                Ok(account) => account,
+                Err(reason) => {
+                    self.send(peer, ServerMessage::AuthRejected { reason });
+                    return;
+                }
+            };
+
+            self.leave_world(peer);
+            let session = Session {
+                account: Some(account),
+                ..default()
+            };
+            self.sessions.insert(peer, session);
+            let servers = self.servers.clone();
+            self.send(peer, ServerMessage::AuthAccepted { servers });
+            info!("local server: '{username}' logged in from {peer:?}");
+            return;
+        }
+
+        // someone else's datagram can't get into a session it didn't log in to
+        let Some(session) = self.sessions.get_mut(&peer) else {
+            return self.reject(peer, AuthError::NotAuthenticated);
+        };
+
+        match msg {
+            ClientMessage::Login { .. } => unreachable!("logging in doesn't need a session"),
+            ClientMessage::SelectServer { server } => {
+                let Some(account) = session.account else {
+                    return self.reject(peer, AuthError::NotAuthenticated);
+                };
+                if !self.servers.iter().any(|s| s.id == server) {
+                    return self.reject(peer, AuthError::UnknownServer);
+                }
+
+                session.server = Some(server);
+                let characters = self.auth.characters(account, server);
+                self.send(peer, ServerMessage::CharacterList { characters });
+            }
+            ClientMessage::SelectCharacter { character } => {
+                let (Some(account), Some(server)) = (session.account, session.server) else {
+                    return self.reject(peer, AuthError::NotAuthenticated);
+                };
+                let Some(info) = self
+                    .auth
+                    .characters(account, server)
+                    .into_iter()
+                    .find(|c| c.id == character)
+                else {
+                    return self.reject(peer, AuthError::UnknownCharacter);
+                };
+
+                session.character = Some(info);
+                self.send(peer, ServerMessage::CharacterSelected { character });
+            }
+            ClientMessage::EnterWorld => {
+                let Some(character) = session.character.clone() else {
+                    return self.reject(peer, AuthError::UnknownCharacter);
+                };
+                self.leave_world(peer);
+
+                // let the client know about everything that already exists
+                let existing: Vec<_> = self
+                    .entities
+                    .iter()
+                    .map(|(id, e)| ServerMessage::EntitySpawned {
+                        id: *id,
+                        kind: e.kind,
+                        position: e.position,
+                    })
+                    .collect();
+
+                // the client has to know its own id before the spawn, or it replicates itself
+                let kind = EntityKind::Player;
+                let position = self.save_point;
+                let player = self.insert(kind, position, ComputedAttributes::default());
+                if let Some(session) = self.sessions.get_mut(&peer) {
+                    session.player = Some(player);
+                }
+                self.send(peer, ServerMessage::EnteredWorld { player });
+                self.broadcast(ServerMessage::EntitySpawned {
+                    id: player,
+                    kind,
+                    position,
+                });
+                for msg in existing {
+                    self.send(peer, msg);
+                }
+                info!(
+                    "local server: '{}' entered the world as {player:?}",
+                    character.name
+                );
+            }
+            ClientMessage::Logout => {
+                self.leave_world(peer);
+                self.sessions.remove(&peer);
+            }
+            ClientMessage::MoveIntent {
+                seq,
+                velocity,
+                dt,
+                animation,
+            } => {
+                let Some(entity) = session.player.and_then(|id| self.entities.get_mut(&id)) else {
+                    return;
+                };
+                // inputs are replayed one by one, so the client's prediction and the server
+                // agree as long as nothing blocked the character on either side
+                let dt = dt.clamp(0.0, MAX_INPUT_DT);
+                entity.position += Vec3::new(velocity.x, 0.0, velocity.z) * dt;
+                entity.face(velocity);
+                // purely cosmetic, so the owning client is trusted with it
+                entity.animation = animation;
+                session.ack = Some(seq);
+            }
+            ClientMessage::AttackIntent { target } => {
+                let Some(attacker) = session.player else {
+                    return;
+                };
+                // clients keep asking while they're in range, the attack rate sorts them out
+                let last_attack = session.last_attack;
+                if let Err(e) = self.check_attack(attacker, target, last_attack) {
+                    debug!("local server: {attacker:?} can't attack {target:?}: {e}");
+                    return;
+                }
+                if let Some(session) = self.sessions.get_mut(&peer) {
+                    session.last_attack = Some(self.time);
+                    session.attack = Some(AcceptedAttack { attacker, target });
+                }
+            }
+            ClientMessage::Respawn => {
+                let Some(entity) = session.player.and_then(|id| self.entities.get_mut(&id)) else {
+                    return;
+                };
+                session.respawn = true;
+                entity.position = self.save_point;
+                entity.velocity = Vec3::ZERO;
+                entity.attributes.health.hp = entity.attributes.health.max_hp;
+                entity.attributes.mana.mp = entity.attributes.mana.max_mp;
+            }
+        }
+    }
+
+    fn check_attack(
+        &self,
+        attacker: NetId,
+        target: NetId,
+        last_attack: Option<f64>,
+    ) -> Result<(), AttackRejected> {
+        if attacker == target {
+            return Err(AttackRejected::Itself);
+        }
+        let attacker = self.entities.get(&attacker).ok_or(AttackRejected::Dead)?;
+        let target = self.entities.get(&target).ok_or(AttackRejected::NoTarget)?;
+        if target.kind != EntityKind::Enemy {
+            return Err(AttackRejected::Friendly);
+        }
+        if attacker.attributes.health.hp <= 0.0 || target.attributes.health.hp <= 0.0 {
+            return Err(AttackRejected::Dead);
+        }
+
+        let reach = attacker.attributes.attack_range + ATTACK_RANGE_SLACK;
+        if attacker.position.xz().distance(target.position.xz()) > reach {
+            return Err(AttackRejected::OutOfRange);
+        }
+        let rate = attacker.attributes.attack_rate as f64;
+        if last_attack.is_some_and(|last| self.time - last < rate) {
+            return Err(AttackRejected::TooFast);
+        }
+        Ok(())
+    }
+
+    fn leave_world(&mut self, peer: Peer) {
+        let player = self
+            .sessions
+            .get_mut(&peer)
+            .and_then(|session| session.player.take());
+        if let Some(player) = player {
+            self.despawn(player);
+        }
+    }
+
+    fn reject(&mut self, peer: Peer, reason: AuthError) {
+        self.send(peer, ServerMessage::AuthRejected { reason });
+    }
+
+    /// Tells every client in the world
+    fn broadcast(&mut self, msg: ServerMessage) {
+        let peers: Vec<Peer> = self
+            .sessions
+            .iter()
+            .filter(|(_, session)| session.player.is_some())
+            .map(|(peer, _)| *peer)
+            .collect();
+        for peer in peers {
+            self.send(peer, msg.clone());
+        }
+    }
+
+    fn send(&mut self, peer: Peer, msg: ServerMessage) {
+        if let Err(e) = encode(&msg).and_then(|packet| self.transport.send_to(peer, packet)) {
+            error!("local server: failed to send {msg:?} to {peer:?}: {e}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{thread, time::Duration};
+
+    /// Sends `msg` to the server and returns the first thing it answered
+    fn answer(
+        server: &mut LocalServer,
+        client: &mut impl Transport,
+        msg: ClientMessage,
+    ) -> ServerMessage {
+        client.send(encode(&msg).unwrap()).unwrap();
+        // datagrams take a moment even on localhost
+        for _ in 0..500 {
+            server.tick(0.0);
+            if let Some(packet) = client.recv() {
+                return decode(&packet).unwrap();
+            }
+            thread::sleep(Duration::from_millis(1));
+        }
+        panic!("no answer");
+    }
+
+    fn login(username: &str, password: &str) -> ClientMessage {
+        ClientMessage::Login {
+            username: username.into(),
+            password: password.into(),
+        }
+    }
+
+    #[test]
+    fn server_login_flow() {
+        let (mut client, server) = Loopback::pair();
+        let mut server = LocalServer::new(server);
+
+        let rejected = answer(&mut server, &mut client, login("guest", "nope"));
+        assert!(matches!(
+            rejected,
+            ServerMessage::AuthRejected {
+                reason: AuthError::InvalidCredentials
+            }
+        ));
+
+        // nothing past the login is allowed before it succeeds
+        let early = answer(
+            &mut server,
+            &mut client,
+            ClientMessage::SelectServer { server: 1 },
+        );
+        assert!(matches!(
+            early,
+            ServerMessage::AuthRejected {
+                reason: AuthError::NotAuthenticated
+            }
+        ));
+
+        let accepted = answer(&mut server, &mut client, login("guest", "guest"));
+        assert!(matches!(accepted, ServerMessage::AuthAccepted { .. }));
+
+        let ServerMessage::CharacterList { characters } = answer(
+            &mut server,
+            &mut client,
+            ClientMessage::SelectServer { server: 1 },
+        ) else {
+            panic!("no character list");
+        };
+        assert_eq!(characters.len(), 1);
+    }
+
+    #[test]
+    fn sessions_are_kept_per_client() {
+        let host = UdpHost::bind("127.0.0.1:0").unwrap();
+        let address = host.local_addr().unwrap();
+        let mut server = LocalServer::new(host);
+        let mut alice = Udp::connect(address).unwrap();
+        let mut mallory = Udp::connect(address).unwrap();
+
+        let accepted = answer(&mut server, &mut alice, login("guest", "guest"));
+        assert!(matches!(accepted, ServerMessage::AuthAccepted { .. }));
+
+        // picking up where someone else logged in doesn't work
+        let select = ClientMessage::SelectServer { server: 1 };
+        let refused = answer(&mut server, &mut mallory, select.clone());
+        assert!(matches!(
+            refused,
+            ServerMessage::AuthRejected {
+                reason: AuthError::NotAuthenticated
+            }
+        ));
+
+        let listed = answer(&mut server, &mut alice, select);
+        assert!(matches!(listed, ServerMessage::CharacterList { .. }));
+    }
+
+    /// Logs in as the guest and picks its first character
+    fn select_character(server: &mut LocalServer, client: &mut Loopback) {
+        answer(server, client, login("guest", "guest"));
+        let ServerMessage::CharacterList { characters } =
+            answer(server, client, ClientMessage::SelectServer { server: 1 })
+        else {
+            panic!("no character list");
+        };
+        let character = characters[0].id;
+        answer(server, client, ClientMessage::SelectCharacter { character });
+    }
+
+    #[test]
+    fn own_player_known_before_its_spawn() {
+        let (mut client, server) = Loopback::pair();
+        let mut server = LocalServer::new(server);
+        select_character(&mut server, &mut client);
+
+        let ServerMessage::EnteredWorld { player } =
+            answer(&mut server, &mut client, ClientMessage::EnterWorld)
+        else {
+            panic!("not in the world");
+        };
+        let spawned = decode::<ServerMessage>(&client.recv().expect("a spawn")).unwrap();
+        assert!(matches!(
+            spawned,
+            ServerMessage::EntitySpawned { id, kind: EntityKind::Player, .. } if id == player
+        ));
+    }
+
+    /// Enters the world as the guest, everything the server announced is read already
+    fn enter_world(server: &mut LocalServer, client: &mut Loopback) -> NetId {
+        select_character(server, client);
+        let ServerMessage::EnteredWorld { player } =
+            answer(server, client, ClientMessage::EnterWorld)
+        else {
+            panic!("not in the world");
+        };
+        while client.recv().is_some() {}
+        player
+    }
+
+    fn attack(server: &mut LocalServer, client: &mut Loopback, target: NetId, dt: f32) {
+        let msg = ClientMessage::AttackIntent { target };
+        client.send(encode(&msg).unwrap()).unwrap();
+        server.tick(dt);
+    }
+
+    #[test]
+    fn attacks_are_checked() {
+        let (mut client, server) = Loopback::pair();
+        let mut server = LocalServer::new(server);
+        let player = enter_world(&mut server, &mut client);
+
+        let alive = ComputedAttributes {
+            health: Health::new(10.0),
+            ..default()
+        };
+        let mut dead = alive;
+        dead.health.hp = 0.0;
+        let at = |x: f32| DEFAULT_SAVE_POINT + Vec3::X * x;
+        let near = server.spawn(EntityKind::Enemy, at(1.0), alive);
+        let far = server.spawn(EntityKind::Enemy, at(50.0), alive);
+        let corpse = server.spawn(EntityKind::Enemy, at(1.0), dead);
+
+        for target in [player, far, corpse, NetId(999)] {
+            attack(&mut server, &mut client, target, 0.0);
+            assert!(server.take_attacks().is_empty(), "attacked {target:?}");
+        }
+
+        attack(&mut server, &mut client, near, 0.0);
+        let hit = AcceptedAttack {
+            attacker: player,
+            target: near,
+        };
+        assert_eq!(server.take_attacks(), [hit]);
+    }
+
+    #[test]
+    fn attacks_keep_to_the_attack_rate() {
+        let (mut client, server) = Loopback::pair();
+        let mut server = LocalServer::new(server);
+        enter_world(&mut server, &mut client);
+        let attributes = ComputedAttributes {
+            health: Health::new(10.0),
+            ..default()
+        };
+        let enemy = server.spawn(EntityKind::Enemy, DEFAULT_SAVE_POINT, attributes);
+
+        attack(&mut server, &mut client, enemy, 0.0);
+        assert_eq!(server.take_attacks().len(), 1);
+        // one attack a second by default
+        attack(&mut server, &mut client, enemy, 0.5);
+        assert!(server.take_attacks().is_empty());
+        attack(&mut server, &mut client, enemy, 0.5);
+        assert_eq!(server.take_attacks().len(), 1);
+    }
+
+    #[test]
+    fn attributes_go_out_with_the_snapshot() {
+        let (mut client, server) = Loopback::pair();
+        let mut server = LocalServer::new(server);
+        enter_world(&mut server, &mut client);
+        let enemy = server.spawn(EntityKind::Enemy, Vec3::ZERO, default());
+        server.tick(SNAPSHOT_INTERVAL as f32);
+        while client.recv().is_some() {}
+
+        let hurt = ComputedAttributes {
+            health: Health::new(10.0),
+            ..default()
+        };
+        server.set_attributes(enemy, hurt);
+        server.tick(0.0);
+        assert_eq!(client.recv(), None);
+
+        server.tick(SNAPSHOT_INTERVAL as f32);
+        let messages: Vec<ServerMessage> = std::iter::from_fn(|| client.recv())
+            .map(|packet| decode(&packet).unwrap())
+            .collect();
+        assert!(matches!(messages[0], ServerMessage::Snapshot { .. }));
+        let ServerMessage::AttributesUpdated { id, attributes } = messages[1] else {
+            panic!("no attributes after the snapshot");
+        };
+        assert_eq!(id, enemy);
+        assert_eq!(attributes.health.max_hp, 10.0);
+    }
+
+    #[test]
+    fn respawns_are_handed_to_the_host() {
+        let (mut client, server) = Loopback::pair();
+        let mut server = LocalServer::new(server);
+        let player = enter_world(&mut server, &mut client);
+        assert!(server.take_respawns().is_empty());
+
+        client
+            .send(encode(&ClientMessage::Respawn).unwrap())
+            .unwrap();
+        server.tick(0.0);
+        assert_eq!(server.take_respawns(), [player]);
+        assert!(server.take_respawns().is_empty());
+    }
+}
@@ -0,0 +1,218 @@
+use super::*;
+use std::{
+    io,
+    net::{SocketAddr, ToSocketAddrs, UdpSocket},
+};
+
+/// Moves already encoded packets between peers. Implementations only need to preserve
+/// packet boundaries, framing and (de)serialization are handled by [`encode`]/[`decode`].
+pub trait Transport: Send + Sync + 'static {
+    fn send(&mut self, packet: Vec<u8>) -> Result<(), NetError>;
+    /// Returns the next received packet, if any. Must not block.
+    fn recv(&mut self) -> Option<Vec<u8>>;
+}
+
+/// Sender of a packet a [`Listener`] received
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Peer {
+    /// The other end of a [`Loopback`]
+    Local,
+    Remote(SocketAddr),
+}
+
+/// Server end of a transport, talks to every peer that sends it something
+pub trait Listener: Send + Sync + 'static {
+    fn send_to(&mut self, peer: Peer, packet: Vec<u8>) -> Result<(), NetError>;
+    /// Returns the next received packet and who sent it, if any. Must not block.
+    fn recv_from(&mut self) -> Option<(Peer, Vec<u8>)>;
+}
+
+type Queue = Arc<Mutex<VecDeque<Vec<u8>>>>;
+
+/// In-process transport, one end of a pair created by [`Loopback::pair`].
+/// Whatever is sent by one end is received by the other.
+pub struct Loopback {
+    inbox: Queue,
+    outbox: Queue,
+}
+
+impl Loopback {
+    pub fn pair() -> (Self, Self) {
+        let (a, b) = (Queue::default(), Queue::default());
+        (
+            Self {
+                inbox: a.clone(),
+                outbox: b.clone(),
+            },
+            Self {
+                inbox: b,
+                outbox: a,
+            },
+        )
+    }
+}
+
+impl Transport for Loopback {
+    fn send(&mut self, packet: Vec<u8>) -> Result<(), NetError> {
+        let mut outbox = self.outbox.lock().map_err(|_| NetError::Closed)?;
+        outbox.push_back(packet);
+        Ok(())
+    }
+
+    fn recv(&mut self) -> Option<Vec<u8>> {
+        self.inbox.lock().ok()?.pop_front()
+    }
+}
+
+/// The other end is the only peer of a loopback
+impl Listener for Loopback {
+    fn send_to(&mut self, _: Peer, packet: Vec<u8>) -> Result<(), NetError> {
+        self.send(packet)
+    }
+
+    fn recv_from(&mut self) -> Option<(Peer, Vec<u8>)> {
+        self.recv().map(|packet| (Peer::Local, packet))
+    }
+}
+
+/// Largest payload of a single UDP datagram
+const MAX_DATAGRAM: usize = 65_507;
+
+/// Transport over UDP, one datagram per packet. Nothing is resent or reordered, a lost packet
+/// stays lost. The server end is a [`UdpHost`].
+pub struct Udp {
+    socket: UdpSocket,
+    peer: SocketAddr,
+}
+
+impl Udp {
+    /// Only talks to the server at `address`
+    pub fn connect(address: impl ToSocketAddrs) -> io::Result<Self> {
+        let peer = address.to_socket_addrs()?.next().ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidInput, "no address to connect to")
+        })?;
+        let any = if peer.is_ipv4() {
+            "0.0.0.0:0"
+        } else {
+            "[::]:0"
+        };
+        let socket = UdpSocket::bind(any)?;
+        socket.set_nonblocking(true)?;
+        Ok(Self { socket, peer })
+    }
+
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.socket.local_addr()
+    }
+}
+
+impl Transport for Udp {
+    fn send(&mut self, packet: Vec<u8>) -> Result<(), NetError> {
+        self.socket.send_to(&packet, self.peer)?;
+        Ok(())
+    }
+
+    fn recv(&mut self) -> Option<Vec<u8>> {
+        let mut buf = vec![0; MAX_DATAGRAM];
+        loop {
+            let (len, from) = self.socket.recv_from(&mut buf).ok()?;
+            if from != self.peer {
+                continue;
+            }
+            buf.truncate(len);
+            return Some(buf);
+        }
+    }
+}
+
+/// Server end of [`Udp`], every client is a [`Peer::Remote`] told apart by its address
+pub struct UdpHost {
+    socket: UdpSocket,
+}
+
+impl UdpHost {
+    pub fn bind(address: impl ToSocketAddrs) -> io::Result<Self> {
+        let socket = UdpSocket::bind(address)?;
+        socket.set_nonblocking(true)?;
+        Ok(Self { socket })
+    }
+
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.socket.local_addr()
+    }
+}
+
+impl Listener for UdpHost {
+    fn send_to(&mut self, peer: Peer, packet: Vec<u8>) -> Result<(), NetError> {
+        let Peer::Remote(address) = peer else {
+            return Err(NetError::Closed);
+        };
+        self.socket.send_to(&packet, address)?;
+        Ok(())
+    }
+
+    fn recv_from(&mut self) -> Option<(Peer, Vec<u8>)> {
+        let mut buf = vec![0; MAX_DATAGRAM];
+        let (len, from) = self.socket.recv_from(&mut buf).ok()?;
+        buf.truncate(len);
+        Some((Peer::Remote(from), buf))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{thread, time::Duration};
+
+    /// Datagrams take a moment even on localhost
+    fn wait<T>(mut recv: impl FnMut() -> Option<T>) -> T {
+        for _ in 0..500 {
+            if let Some(received) = recv() {
+                return received;
+            }
+            thread::sleep(Duration::from_millis(1));
+        }
+        panic!("nothing received");
+    }
+
+    #[test]
+    fn loopback_pair() {
+        let (mut a, mut b) = Loopback::pair();
+        a.send(vec![1, 2]).unwrap();
+        a.send(vec![3]).unwrap();
+        assert_eq!(b.recv(), Some(vec![1, 2]));
+        assert_eq!(b.recv(), Some(vec![3]));
+        assert_eq!(b.recv(), None);
+        assert_eq!(a.recv(), None);
+    }
+
+    #[test]
+    fn loopback_listener_has_one_peer() {
+        let (mut client, mut server) = Loopback::pair();
+        client.send(vec![1]).unwrap();
+        assert_eq!(server.recv_from(), Some((Peer::Local, vec![1])));
+        server.send_to(Peer::Local, vec![2]).unwrap();
+        assert_eq!(client.recv(), Some(vec![2]));
+    }
+
+    #[test]
+    fn udp_host_answers_each_client() {
+        let mut server = UdpHost::bind("127.0.0.1:0").unwrap();
+        let address = server.local_addr().unwrap();
+        let mut alice = Udp::connect(address).unwrap();
+        let mut bob = Udp::connect(address).unwrap();
+
+        alice.send(vec![1]).unwrap();
+        let (from_alice, packet) = wait(|| server.recv_from());
+        assert_eq!(packet, [1]);
+        bob.send(vec![2]).unwrap();
+        let (from_bob, packet) = wait(|| server.recv_from());
+        assert_eq!(packet, [2]);
+        assert_ne!(from_alice, from_bob);
+
+        server.send_to(from_bob, vec![3]).unwrap();
+        server.send_to(from_alice, vec![4]).unwrap();
+        assert_eq!(wait(|| bob.recv()), [3]);
+        assert_eq!(wait(|| alice.recv()), [4]);
+    }
+}
@@ -15,7 +15,6 @@ pub struct Ground;
 /// Scene logic is only active during the State `Screen::Gameplay`
 pub fn plugin(app: &mut App) {
     app.add_plugins((
-        PhysicsPlugins::default(),
         bevy_fix_gltf_coordinate_system::FixGltfCoordinateSystemPlugin,
         skybox::plugin,
     ))
@@ -0,0 +1,82 @@
+//! Lists the characters of the account, picking one enters [`Screen::Gameplay`] where the
+//! character is spawned by the server.
+
+use super::*;
+use bevy::ecs::spawn::SpawnIter;
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_systems(
+        OnEnter(Screen::CharacterSelect),
+        spawn_character_select_screen,
+    )
+    .add_systems(
+        Update,
+        on_character_selected.run_if(in_state(Screen::CharacterSelect)),
+    );
+}
+
+fn spawn_character_select_screen(mut commands: Commands, characters: Res<AvailableCharacters>) {
+    let buttons: Vec<_> = characters
+        .iter()
+        .map(|character| {
+            let id = character.id;
+            btn_big(
+                format!(
+                    "{} - {} Lv. {}",
+                    character.name, character.job, character.level
+                ),
+                move |_: Trigger<OnPress>, mut to_server: EventWriter<ToServer>| {
+                    to_server.write(ToServer(ClientMessage::SelectCharacter { character: id }));
+                },
+            )
+        })
+        .collect();
+
+    let empty = if characters.is_empty() {
+        "No characters on this server"
+    } else {
+        ""
+    };
+
+    commands.spawn((
+        StateScoped(Screen::CharacterSelect),
+        ui_root("Character Select UI"),
+        BackgroundColor(TRANSLUCENT),
+        children![
+            header("Select a character"),
+            (
+                Name::new("Character List"),
+                Node {
+                    flex_direction: FlexDirection::Column,
+                    row_gap: Vh(2.0),
+                    ..default()
+                },
+                Children::spawn(SpawnIter(buttons.into_iter())),
+            ),
+            label(empty),
+            (label(Opts::new("").color(RED)), LoginErrorLabel),
+            btn_big("Back", to::server_select),
+        ],
+    ));
+}
+
+fn on_character_selected(
+    resource_handles: Res<ResourceHandles>,
+    mut from_server: EventReader<FromServer>,
+    mut error: Single<&mut Text, With<LoginErrorLabel>>,
+    mut next_screen: ResMut<NextState<Screen>>,
+) {
+    for msg in from_server.read() {
+        match &msg.0 {
+            ServerMessage::CharacterSelected { .. } => {
+                if resource_handles.is_all_done() {
+                    next_screen.set(Screen::Gameplay);
+                } else {
+                    next_screen.set(Screen::Loading);
+                }
+            }
+            ServerMessage::AuthRejected { reason } => error.0 = reason.to_string(),
+            _ => {}
+        }
+    }
+}
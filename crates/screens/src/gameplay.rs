@@ -1,6 +1,9 @@
 //! The screen state for the main gameplay.
 
 use super::*;
+use bevy::ecs::spawn::SpawnWith;
+use game::event::types::{HotbarPressed, RespawnRequest};
+use skill_tree::DraggedSkill;
 
 pub(super) fn plugin(app: &mut App) {
     app.add_plugins(game::plugin)
@@ -13,9 +16,27 @@ pub(super) fn plugin(app: &mut App) {
         .add_observer(trigger_menu_toggle_on_esc)
         .add_observer(add_new_modal)
         .add_observer(pop_modal)
-        .add_observer(clear_modals);
+        .add_observer(clear_modals)
+        .add_observer(show_death_dialog)
+        .add_observer(hide_death_dialog)
+        .add_systems(
+            Update,
+            (update_hotbar_labels, update_cast_bar, update_exp_bars)
+                .run_if(in_state(Screen::Gameplay)),
+        );
 }
 
+/// Skill name above a hotbar button
+#[derive(Component)]
+struct HotbarSlotLabel(usize);
+
+/// Level next to an experience bar
+#[derive(Component)]
+struct ExpBarLabel(ExpKind);
+
+#[derive(Component)]
+struct ExpBarFill(ExpKind);
+
 fn spawn_gameplay_ui(mut cmds: Commands, textures: Res<Textures>, settings: Res<Settings>) {
     info!("settings on gameplay enter:{settings:?}");
     let opts = Opts::default().hidden().width(Vw(5.0)).height(Vw(5.0));
@@ -40,6 +61,30 @@ fn spawn_gameplay_ui(mut cmds: Commands, textures: Res<Textures>, settings: Res<
                     (icon(opts.clone().image(textures.mute.clone())), MuteIcon),
                 ]
             ),
+            // levels and experience in the top left corner
+            (
+                Node {
+                    flex_direction: FlexDirection::Column,
+                    position_type: PositionType::Absolute,
+                    top: Vh(2.0),
+                    left: Vw(1.0),
+                    row_gap: Vh(0.5),
+                    ..default()
+                },
+                children![exp_bar(ExpKind::Base), exp_bar(ExpKind::Job)],
+            ),
+            // cast bar over the hotbar
+            (
+                Node {
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    position_type: PositionType::Absolute,
+                    bottom: Vh(2.0),
+                    row_gap: Vh(2.0),
+                    ..default()
+                },
+                children![cast_bar(), hotbar()],
+            ),
         ],
     ));
 }
@@ -217,8 +262,293 @@ fn clear_modals(
     }
 }
 
+fn show_death_dialog(
+    on: Trigger<OnAdd, Dead>,
+    local: Query<(), With<LocalPlayer>>,
+    mut cmds: Commands,
+) {
+    if local.contains(on.target()) {
+        cmds.spawn(death_dialog());
+    }
+}
+
+fn hide_death_dialog(
+    on: Trigger<OnRemove, Dead>,
+    local: Query<(), With<LocalPlayer>>,
+    dialogs: Query<Entity, With<DeathDialog>>,
+    mut cmds: Commands,
+) {
+    if !local.contains(on.target()) {
+        return;
+    }
+    for dialog in dialogs.iter() {
+        // also removed when gameplay is left and the dialog goes with it
+        cmds.entity(dialog).try_despawn();
+    }
+}
+
+fn return_to_save_point(
+    _: Trigger<OnPress>,
+    player: Single<Entity, With<LocalPlayer>>,
+    mut respawn: EventWriter<RespawnRequest>,
+) {
+    respawn.write(RespawnRequest { entity: *player });
+}
+
+fn update_hotbar_labels(
+    skill_db: Option<Res<SkillDb>>,
+    hotbar: Query<&Hotbar, (With<LocalPlayer>, Changed<Hotbar>)>,
+    mut labels: Query<(&HotbarSlotLabel, &mut Text)>,
+) {
+    let (Some(skill_db), Ok(hotbar)) = (skill_db, hotbar.single()) else {
+        return;
+    };
+
+    for (HotbarSlotLabel(slot), mut text) in &mut labels {
+        text.0 = hotbar.0[*slot]
+            .and_then(|skill| skill_db.get(skill))
+            .map(|def| def.name.clone())
+            .unwrap_or_default();
+    }
+}
+
+fn update_cast_bar(
+    skill_db: Option<Res<SkillDb>>,
+    casting: Query<Option<&Casting>, With<LocalPlayer>>,
+    mut bar: Query<&mut Node, With<CastBar>>,
+    mut fill: Query<&mut Node, (With<CastBarFill>, Without<CastBar>)>,
+    mut label: Query<&mut Text, With<CastBarLabel>>,
+) {
+    let (Ok(casting), Ok(mut bar), Ok(mut fill), Ok(mut label)) = (
+        casting.single(),
+        bar.single_mut(),
+        fill.single_mut(),
+        label.single_mut(),
+    ) else {
+        return;
+    };
+
+    // instant casts don't get a bar
+    let Some(casting) = casting.filter(|c| c.timer.duration().as_secs_f32() > 0.0) else {
+        bar.display = Display::None;
+        return;
+    };
+
+    bar.display = Display::Flex;
+    fill.width = Percent(casting.timer.fraction() * 100.0);
+    if let Some(def) = skill_db.as_ref().and_then(|db| db.get(casting.skill))
+        && label.0 != def.name
+    {
+        label.0 = def.name.clone();
+    }
+}
+
+fn update_exp_bars(
+    levels: Option<Res<LevelTable>>,
+    job_db: Option<Res<JobDb>>,
+    player: Query<
+        (&Level, &JobLevel, &Experience, &Job),
+        (
+            With<LocalPlayer>,
+            Or<(
+                Changed<Level>,
+                Changed<JobLevel>,
+                Changed<Experience>,
+                Changed<Job>,
+            )>,
+        ),
+    >,
+    mut labels: Query<(&ExpBarLabel, &mut Text)>,
+    mut fills: Query<(&ExpBarFill, &mut Node)>,
+) {
+    let (Some(levels), Ok((level, job_level, exp, job))) = (levels, player.single()) else {
+        return;
+    };
+    let level_of = |kind| match kind {
+        ExpKind::Base => **level,
+        ExpKind::Job => **job_level,
+    };
+
+    for (ExpBarLabel(kind), mut text) in &mut labels {
+        let name = match kind {
+            ExpKind::Base => "Base",
+            // the name of the job itself once the database is around
+            ExpKind::Job => job_db
+                .as_ref()
+                .and_then(|db| db.get(*job))
+                .map_or("Job", |def| def.name.as_str()),
+        };
+        text.0 = format!("{name} Lv. {}", level_of(*kind));
+    }
+    for (ExpBarFill(kind), mut node) in &mut fills {
+        // full at the max level
+        let fraction = levels
+            .get(*kind)
+            .needed(level_of(*kind))
+            .map_or(1.0, |needed| exp.get(*kind) as f32 / needed as f32);
+        node.width = Percent(fraction.min(1.0) * 100.0);
+    }
+}
+
+// HUD
+
+fn exp_bar(kind: ExpKind) -> impl Bundle {
+    let color = match kind {
+        ExpKind::Base => SAND_YELLOW,
+        ExpKind::Job => LIGHT_BLUE,
+    };
+    (
+        Name::new(format!("{kind:?} Exp Bar")),
+        Node {
+            flex_direction: FlexDirection::Row,
+            align_items: AlignItems::Center,
+            column_gap: Vw(0.5),
+            ..default()
+        },
+        children![
+            (
+                label(Opts::new("").font_size(14.0).width(Vw(9.0))),
+                ExpBarLabel(kind)
+            ),
+            (
+                BorderColor(WHITEISH),
+                BackgroundColor(TRANSLUCENT),
+                Node {
+                    width: Vw(15.0),
+                    height: Vh(1.0),
+                    border: UiRect::all(Px(1.0)),
+                    ..default()
+                },
+                children![(
+                    ExpBarFill(kind),
+                    BackgroundColor(color),
+                    Node {
+                        width: Percent(0.0),
+                        height: Percent(100.0),
+                        ..default()
+                    },
+                )],
+            ),
+        ],
+    )
+}
+
+fn cast_bar() -> impl Bundle {
+    (
+        CastBar,
+        Name::new("Cast Bar"),
+        Node {
+            display: Display::None,
+            flex_direction: FlexDirection::Column,
+            align_items: AlignItems::Center,
+            row_gap: Vh(0.5),
+            ..default()
+        },
+        children![
+            (label(Opts::new("").font_size(16.0)), CastBarLabel),
+            (
+                BorderColor(WHITEISH),
+                BackgroundColor(TRANSLUCENT),
+                Node {
+                    width: Vw(20.0),
+                    height: Vh(1.5),
+                    border: UiRect::all(Px(2.0)),
+                    ..default()
+                },
+                children![(
+                    CastBarFill,
+                    BackgroundColor(LIGHT_BLUE),
+                    Node {
+                        width: Percent(0.0),
+                        height: Percent(100.0),
+                        ..default()
+                    },
+                )],
+            ),
+        ],
+    )
+}
+
+fn hotbar() -> impl Bundle {
+    (
+        Name::new("Hotbar"),
+        Node {
+            flex_direction: FlexDirection::Row,
+            column_gap: Vw(0.5),
+            ..default()
+        },
+        Children::spawn(SpawnWith(|parent: &mut ChildSpawner| {
+            for slot in 0..HOTBAR_SLOTS {
+                parent.spawn(hotbar_slot(slot)).observe(
+                    // a skill dragged from the skill tree
+                    move |_: Trigger<Pointer<DragDrop>>,
+                          dragged: Res<DraggedSkill>,
+                          mut hotbar: Single<&mut Hotbar, With<LocalPlayer>>| {
+                        if let Some(skill) = **dragged {
+                            hotbar.0[slot] = Some(skill);
+                        }
+                    },
+                );
+            }
+        })),
+    )
+}
+
+/// A skill button, same as pressing the key of its slot. Skills dragged from the skill tree are
+/// dropped onto it.
+fn hotbar_slot(slot: usize) -> impl Bundle {
+    (
+        Node {
+            flex_direction: FlexDirection::Column,
+            align_items: AlignItems::Center,
+            width: Vw(6.0),
+            ..default()
+        },
+        children![
+            (label(Opts::new("").font_size(14.0)), HotbarSlotLabel(slot)),
+            btn_small(
+                Opts::new(format!("{}", slot + 1))
+                    .width(Vw(3.5))
+                    .height(Vw(3.5)),
+                move |_: Trigger<OnPress>,
+                      player: Single<Entity, With<LocalPlayer>>,
+                      mut pressed: EventWriter<HotbarPressed>| {
+                    pressed.write(HotbarPressed {
+                        entity: *player,
+                        slot,
+                    });
+                },
+            ),
+        ],
+    )
+}
+
 // MODALS
 
+fn death_dialog() -> impl Bundle {
+    (
+        StateScoped(Screen::Gameplay),
+        DeathDialog,
+        ui_root("Death Dialog"),
+        children![(
+            BorderColor(WHITEISH),
+            BackgroundColor(TRANSLUCENT),
+            Node {
+                border: UiRect::all(Px(2.0)),
+                padding: UiRect::all(Vw(3.0)),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                row_gap: Vh(3.0),
+                ..default()
+            },
+            children![
+                header("You died"),
+                btn_big("Return to save point", return_to_save_point),
+            ]
+        )],
+    )
+}
+
 fn settings_modal() -> impl Bundle {
     (StateScoped(Screen::Gameplay), SettingsModal, settings_ui())
 }
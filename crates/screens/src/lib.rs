@@ -2,20 +2,29 @@
 
 use asset_loading::*;
 use audio::*;
-use bevy::{prelude::*, ui::Val::*};
+use bevy::{input_focus::InputFocus, prelude::*, ui::Val::*};
 // use bevy_enhanced_input::prelude::*;
 use bevy_seedling::prelude::*;
 use models::*;
+use net::*;
 use ui::*;
 // use game::*;
 
+mod character_select;
 mod credits;
 mod gameplay;
 mod loading;
+mod login;
+mod server_select;
 mod settings;
+mod skill_tree;
 mod splash;
+mod status_window;
 mod title;
 
+pub use login::AvailableServers;
+pub use server_select::AvailableCharacters;
+
 pub fn plugin(app: &mut App) {
     app.init_state::<Screen>();
     app.enable_state_scoped_entities::<Screen>();
@@ -24,9 +33,14 @@ pub fn plugin(app: &mut App) {
         splash::plugin,
         loading::plugin,
         title::plugin,
+        login::plugin,
+        server_select::plugin,
+        character_select::plugin,
         settings::plugin,
         credits::plugin,
         gameplay::plugin,
+        status_window::plugin,
+        skill_tree::plugin,
     ))
     .add_systems(Update, track_last_screen.run_if(state_changed::<Screen>))
     .add_observer(on_back)
@@ -80,6 +94,12 @@ pub mod to {
     pub fn credits(_: Trigger<OnPress>, mut cmds: Commands) {
         cmds.trigger(OnGoTo(Screen::Credits));
     }
+    pub fn login(_: Trigger<OnPress>, mut cmds: Commands) {
+        cmds.trigger(OnGoTo(Screen::Login));
+    }
+    pub fn server_select(_: Trigger<OnPress>, mut cmds: Commands) {
+        cmds.trigger(OnGoTo(Screen::ServerSelect));
+    }
     pub fn gameplay_or_loading(
         _: Trigger<OnPress>,
         resource_handles: Res<ResourceHandles>,
@@ -0,0 +1,100 @@
+//! Login screen, the first step of entering the world.
+//! Credentials are checked by the server, which either answers with the servers available to
+//! the account or tells why the login was refused.
+
+use super::*;
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<AvailableServers>()
+        .add_systems(OnEnter(Screen::Login), spawn_login_screen)
+        .add_systems(
+            OnEnter(Screen::Title),
+            logout.run_if(resource_exists::<NetClient>),
+        )
+        .add_systems(Update, on_auth_response.run_if(in_state(Screen::Login)));
+}
+
+/// Servers the account can play on, as sent by the server on login
+#[derive(Resource, Default, Deref)]
+pub struct AvailableServers(pub Vec<ServerInfo>);
+
+fn spawn_login_screen(mut commands: Commands, mut focus: ResMut<InputFocus>) {
+    let opts = Opts::default()
+        .width(Vw(30.0))
+        .padding(UiRect::axes(Vw(1.0), Vh(1.0)));
+
+    commands
+        .spawn((
+            StateScoped(Screen::Login),
+            ui_root("Login UI"),
+            BackgroundColor(TRANSLUCENT),
+        ))
+        .with_children(|parent| {
+            parent.spawn(header("Login"));
+            let username = parent
+                .spawn((
+                    text_input(opts.clone(), TextInput::new("Username")),
+                    UsernameInput,
+                ))
+                .observe(submit::<OnSubmit>)
+                .id();
+            parent
+                .spawn((
+                    text_input(opts, TextInput::new("Password").masked()),
+                    PasswordInput,
+                ))
+                .observe(submit::<OnSubmit>);
+            parent.spawn((label(Opts::new("").color(RED)), LoginErrorLabel));
+            parent.spawn(btn_big("Login", submit::<OnPress>));
+            parent.spawn(btn_big("Back", to::title));
+
+            focus.set(username);
+        });
+}
+
+/// Sends the credentials, both on the login button and on Enter in one of the fields
+fn submit<E: Event>(
+    _: Trigger<E>,
+    username: Single<&TextInput, With<UsernameInput>>,
+    password: Single<&TextInput, With<PasswordInput>>,
+    mut error: Single<&mut Text, With<LoginErrorLabel>>,
+    mut to_server: EventWriter<ToServer>,
+) {
+    let name = username.value.trim();
+    if name.is_empty() {
+        error.0 = "Enter a username".into();
+        return;
+    }
+
+    error.0.clear();
+    to_server.write(ToServer(ClientMessage::Login {
+        username: name.into(),
+        password: password.value.clone(),
+    }));
+}
+
+fn on_auth_response(
+    mut from_server: EventReader<FromServer>,
+    mut servers: ResMut<AvailableServers>,
+    mut error: Single<&mut Text, With<LoginErrorLabel>>,
+    mut next_screen: ResMut<NextState<Screen>>,
+) {
+    for msg in from_server.read() {
+        match &msg.0 {
+            ServerMessage::AuthAccepted { servers: available } => {
+                servers.0 = available.clone();
+                next_screen.set(Screen::ServerSelect);
+            }
+            ServerMessage::AuthRejected { reason } => error.0 = reason.to_string(),
+            _ => {}
+        }
+    }
+}
+
+/// Leaving the login flow for the title drops the session
+fn logout(mut client: ResMut<NetClient>, mut to_server: EventWriter<ToServer>) {
+    if client.state == ConnectionState::Authenticated {
+        to_server.write(ToServer(ClientMessage::Logout));
+        client.state = ConnectionState::Disconnected;
+    }
+}
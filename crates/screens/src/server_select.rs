@@ -0,0 +1,72 @@
+//! Picks the world server to play on, the server answers with the characters of the account.
+
+use super::*;
+use bevy::ecs::spawn::SpawnIter;
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<AvailableCharacters>()
+        .add_systems(OnEnter(Screen::ServerSelect), spawn_server_select_screen)
+        .add_systems(
+            Update,
+            on_character_list.run_if(in_state(Screen::ServerSelect)),
+        );
+}
+
+/// Characters of the account on the selected server
+#[derive(Resource, Default, Deref)]
+pub struct AvailableCharacters(pub Vec<CharacterInfo>);
+
+fn spawn_server_select_screen(mut commands: Commands, servers: Res<AvailableServers>) {
+    let buttons: Vec<_> = servers
+        .iter()
+        .map(|server| {
+            let id = server.id;
+            btn_big(
+                format!("{} ({} online)", server.name, server.population),
+                move |_: Trigger<OnPress>, mut to_server: EventWriter<ToServer>| {
+                    to_server.write(ToServer(ClientMessage::SelectServer { server: id }));
+                },
+            )
+        })
+        .collect();
+
+    commands.spawn((
+        StateScoped(Screen::ServerSelect),
+        ui_root("Server Select UI"),
+        BackgroundColor(TRANSLUCENT),
+        children![
+            header("Select a server"),
+            (
+                Name::new("Server List"),
+                Node {
+                    flex_direction: FlexDirection::Column,
+                    row_gap: Vh(2.0),
+                    ..default()
+                },
+                Children::spawn(SpawnIter(buttons.into_iter())),
+            ),
+            (label(Opts::new("").color(RED)), LoginErrorLabel),
+            btn_big("Back", to::login),
+        ],
+    ));
+}
+
+fn on_character_list(
+    mut from_server: EventReader<FromServer>,
+    mut characters: ResMut<AvailableCharacters>,
+    mut error: Single<&mut Text, With<LoginErrorLabel>>,
+    mut next_screen: ResMut<NextState<Screen>>,
+) {
+    for msg in from_server.read() {
+        match &msg.0 {
+            ServerMessage::CharacterList {
+                characters: available,
+            } => {
+                characters.0 = available.clone();
+                next_screen.set(Screen::CharacterSelect);
+            }
+            ServerMessage::AuthRejected { reason } => error.0 = reason.to_string(),
+            _ => {}
+        }
+    }
+}
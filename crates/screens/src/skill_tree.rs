@@ -0,0 +1,544 @@
+//! Skill tree window: the trees of the player's job and of the jobs it changed from, with the
+//! prerequisites drawn between the skills. "+" spends a skill point on the next level, hovering a
+//! skill shows what every level does and learned skills can be dragged onto the hotbar. The jobs
+//! the player can change into are listed at the bottom.
+
+use super::*;
+use bevy::ecs::spawn::SpawnWith;
+use game::event::types::{ChangeJob, JobChanged, LearnSkill};
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<DraggedSkill>()
+        .add_observer(toggle_skill_tree)
+        .add_systems(
+            Update,
+            (
+                rebuild_on_job_change.run_if(on_event::<JobChanged>),
+                update_skill_nodes.run_if(any_with_component::<SkillTreeWindow>),
+            )
+                .run_if(in_state(Screen::Gameplay)),
+        );
+}
+
+const NODE_WIDTH: f32 = 130.0;
+const NODE_HEIGHT: f32 = 54.0;
+/// Room of a column and of a row of the tree, the node and the gap to the next one
+const CELL_WIDTH: f32 = 150.0;
+const CELL_HEIGHT: f32 = 84.0;
+
+/// The learned skill being dragged from the tree, dropped onto a hotbar slot
+#[derive(Resource, Default, Deref)]
+pub(super) struct DraggedSkill(Option<SkillId>);
+
+#[derive(Component, Clone, Copy)]
+struct SkillNode(SkillId);
+
+#[derive(Component)]
+struct SkillLevelLabel(SkillId);
+
+#[derive(Component)]
+struct SkillPointsLabel;
+
+/// Everything needed to build the tree of one job, owned so it can be spawned later
+struct TreeSection {
+    name: String,
+    /// Skill, its name and where its node sits
+    nodes: Vec<(SkillId, String, Vec2)>,
+    /// Nodes of the prerequisite and of the skill needing it
+    edges: Vec<(Vec2, Vec2)>,
+    size: Vec2,
+}
+
+/// A job the player can change into, its name and the job level it needs
+struct NextJob {
+    job: Job,
+    name: String,
+    job_level: u32,
+}
+
+fn node_position((column, row): (u32, u32)) -> Vec2 {
+    Vec2::new(column as f32 * CELL_WIDTH, row as f32 * CELL_HEIGHT)
+}
+
+fn tree_sections(job_db: &JobDb, skill_db: &SkillDb, job: Job) -> Vec<TreeSection> {
+    job_db
+        .lineage(job)
+        .into_iter()
+        .filter_map(|job| job_db.get(job))
+        .filter(|def| !def.skills.is_empty())
+        .map(|def| {
+            let nodes: Vec<_> = def
+                .skills
+                .iter()
+                .filter_map(|tree_skill| {
+                    let skill = SkillId(tree_skill.skill);
+                    let name = skill_db.get(skill)?.name.clone();
+                    Some((skill, name, node_position(tree_skill.at)))
+                })
+                .collect();
+
+            // prerequisites of other trees are only listed in the tooltip
+            let edges = def
+                .skills
+                .iter()
+                .flat_map(|tree_skill| {
+                    tree_skill.requires.iter().filter_map(|(required, _)| {
+                        let from = def.skills.iter().find(|s| s.skill == *required)?;
+                        Some((node_position(from.at), node_position(tree_skill.at)))
+                    })
+                })
+                .collect();
+
+            let gap = Vec2::new(CELL_WIDTH - NODE_WIDTH, CELL_HEIGHT - NODE_HEIGHT);
+            let size = def.skills.iter().fold(Vec2::ZERO, |size, tree_skill| {
+                size.max(node_position(tree_skill.at) + Vec2::new(CELL_WIDTH, CELL_HEIGHT))
+            }) - gap;
+
+            TreeSection {
+                name: def.name.clone(),
+                nodes,
+                edges,
+                size,
+            }
+        })
+        .collect()
+}
+
+fn next_jobs(job_db: &JobDb, job: Job) -> Vec<NextJob> {
+    let mut next: Vec<_> = job_db
+        .next(job)
+        .filter_map(|(job, def)| {
+            Some(NextJob {
+                job,
+                name: def.name.clone(),
+                job_level: def.change?.job_level,
+            })
+        })
+        .collect();
+    next.sort_by_key(|next| next.job);
+    next
+}
+
+fn toggle_skill_tree(
+    _: Trigger<OnSkillTreeToggle>,
+    screen: Res<State<Screen>>,
+    job_db: Option<Res<JobDb>>,
+    skill_db: Option<Res<SkillDb>>,
+    player: Query<&Job, With<LocalPlayer>>,
+    windows: Query<Entity, Or<(With<SkillTreeWindow>, With<SkillTooltip>)>>,
+    mut cmds: Commands,
+) {
+    if *screen.get() != Screen::Gameplay {
+        return;
+    }
+
+    if windows.is_empty()
+        && let (Some(job_db), Some(skill_db), Ok(job)) = (job_db, skill_db, player.single())
+    {
+        let sections = tree_sections(&job_db, &skill_db, *job);
+        cmds.spawn(skill_tree_window(sections, next_jobs(&job_db, *job)));
+    }
+    for window in &windows {
+        cmds.entity(window).despawn();
+    }
+}
+
+/// A new job comes with new trees and new jobs to change into
+fn rebuild_on_job_change(
+    mut job_changed: EventReader<JobChanged>,
+    job_db: Option<Res<JobDb>>,
+    skill_db: Option<Res<SkillDb>>,
+    player: Query<(Entity, &Job), With<LocalPlayer>>,
+    windows: Query<Entity, With<SkillTreeWindow>>,
+    mut cmds: Commands,
+) {
+    let (Some(job_db), Some(skill_db), Ok((player, job))) = (job_db, skill_db, player.single())
+    else {
+        return;
+    };
+    if !job_changed.read().any(|event| event.entity == player) || windows.is_empty() {
+        return;
+    }
+
+    for window in &windows {
+        cmds.entity(window).despawn();
+    }
+    let sections = tree_sections(&job_db, &skill_db, *job);
+    cmds.spawn(skill_tree_window(sections, next_jobs(&job_db, *job)));
+}
+
+/// Levels, skill points and the border of every node: green once learned, gray while the
+/// prerequisites are missing
+fn update_skill_nodes(
+    job_db: Option<Res<JobDb>>,
+    skill_db: Option<Res<SkillDb>>,
+    player: Query<(&Job, &LearnedSkills, &SkillPoints), With<LocalPlayer>>,
+    mut nodes: Query<(&SkillNode, &mut BorderColor)>,
+    mut levels: Query<(&SkillLevelLabel, &mut Text), Without<SkillPointsLabel>>,
+    mut points_label: Query<&mut Text, With<SkillPointsLabel>>,
+) {
+    let (Some(job_db), Some(skill_db), Ok((job, learned, points))) =
+        (job_db, skill_db, player.single())
+    else {
+        return;
+    };
+
+    for (SkillNode(skill), mut border) in &mut nodes {
+        let unlocked = job_db.tree_skill(*job, *skill).is_some_and(|tree_skill| {
+            tree_skill
+                .requires
+                .iter()
+                .all(|(required, level)| learned.level(SkillId(*required)) >= *level)
+        });
+        let color = match learned.level(*skill) {
+            0 if unlocked => WHITEISH,
+            0 => GRAY,
+            _ => GREEN,
+        };
+        if border.0 != color {
+            border.0 = color;
+        }
+    }
+    for (SkillLevelLabel(skill), mut text) in &mut levels {
+        let max_level = skill_db.get(*skill).map_or(1, |def| def.max_level);
+        let new = format!("Lv {}/{max_level}", learned.level(*skill));
+        if text.0 != new {
+            text.0 = new;
+        }
+    }
+    if let Ok(mut text) = points_label.single_mut() {
+        let new = format!("Skill points: {}", **points);
+        if text.0 != new {
+            text.0 = new;
+        }
+    }
+}
+
+fn show_tooltip(
+    trigger: Trigger<Pointer<Over>>,
+    nodes: Query<&SkillNode>,
+    job_db: Res<JobDb>,
+    skill_db: Res<SkillDb>,
+    status_db: Option<Res<StatusDb>>,
+    player: Single<(&Job, &LearnedSkills), With<LocalPlayer>>,
+    tooltips: Query<Entity, With<SkillTooltip>>,
+    mut cmds: Commands,
+) {
+    let Ok(SkillNode(skill)) = nodes.get(trigger.target()) else {
+        return;
+    };
+    let Some(def) = skill_db.get(*skill) else {
+        return;
+    };
+    let (job, learned) = *player;
+
+    let level = learned.level(*skill);
+    let mut lines = vec![format!("{} Lv {level}/{}", def.name, def.max_level)];
+    for at in 1..=def.max_level {
+        let marker = if at == level { ">" } else { " " };
+        let effects = describe(&def.at_level(at), status_db.as_deref());
+        lines.push(format!("{marker} Lv {at}: {effects}"));
+    }
+    let requires = job_db
+        .tree_skill(*job, *skill)
+        .map(|tree_skill| tree_skill.requires.as_slice())
+        .unwrap_or_default();
+    for (required, level) in requires {
+        let name = skill_db
+            .get(SkillId(*required))
+            .map_or_else(|| format!("skill {required}"), |def| def.name.clone());
+        lines.push(format!("Requires {name} Lv {level}"));
+    }
+
+    for tooltip in &tooltips {
+        cmds.entity(tooltip).despawn();
+    }
+    let at = trigger.pointer_location.position + 16.0;
+    cmds.spawn(tooltip(lines.join("\n"), at));
+}
+
+/// Mp, cast time and effects of a skill at one level
+fn describe(def: &SkillDef, status_db: Option<&StatusDb>) -> String {
+    let mut parts = vec![format!("{:.0} MP", def.mp_cost)];
+    if def.cast_time > 0.0 {
+        parts.push(format!("{:.1}s cast", def.cast_time));
+    }
+    for effect in &def.effects {
+        parts.push(match *effect {
+            SkillEffect::Damage {
+                kind,
+                multiplier,
+                element,
+            } => {
+                let element = element.map(|e| format!(" {e:?}")).unwrap_or_default();
+                format!("{:.0}%{element} {kind:?} damage", multiplier * 100.0)
+            }
+            SkillEffect::Heal { base, magic_attack } => {
+                format!("heals {base:.0} + {magic_attack:.1}x MATK")
+            }
+            SkillEffect::Status { status, chance, .. } => {
+                let name = status_db
+                    .and_then(|db| db.get(StatusId(status)))
+                    .map_or_else(|| format!("status {status}"), |def| def.name.clone());
+                format!("{:.0}% {name}", chance * 100.0)
+            }
+            SkillEffect::Knockback { .. } => "knockback".into(),
+        });
+    }
+    parts.join(", ")
+}
+
+fn hide_tooltip(
+    _: Trigger<Pointer<Out>>,
+    tooltips: Query<Entity, With<SkillTooltip>>,
+    mut cmds: Commands,
+) {
+    for tooltip in &tooltips {
+        cmds.entity(tooltip).despawn();
+    }
+}
+
+/// Only learned skills go on the hotbar
+fn start_drag(
+    trigger: Trigger<Pointer<DragStart>>,
+    nodes: Query<&SkillNode>,
+    skill_db: Res<SkillDb>,
+    player: Single<&LearnedSkills, With<LocalPlayer>>,
+    mut dragged: ResMut<DraggedSkill>,
+    mut cmds: Commands,
+) {
+    if trigger.button != PointerButton::Primary {
+        return;
+    }
+    let Ok(SkillNode(skill)) = nodes.get(trigger.target()) else {
+        return;
+    };
+    let Some(def) = skill_db.get(*skill) else {
+        return;
+    };
+    if player.level(*skill) == 0 {
+        return;
+    }
+
+    dragged.0 = Some(*skill);
+    let at = trigger.pointer_location.position;
+    cmds.spawn(drag_ghost(def.name.clone(), at));
+}
+
+fn drag(trigger: Trigger<Pointer<Drag>>, mut ghosts: Query<&mut Node, With<SkillDragGhost>>) {
+    let at = trigger.pointer_location.position;
+    for mut node in &mut ghosts {
+        node.left = Px(at.x);
+        node.top = Px(at.y);
+    }
+}
+
+fn end_drag(
+    _: Trigger<Pointer<DragEnd>>,
+    mut dragged: ResMut<DraggedSkill>,
+    ghosts: Query<Entity, With<SkillDragGhost>>,
+    mut cmds: Commands,
+) {
+    dragged.0 = None;
+    for ghost in &ghosts {
+        cmds.entity(ghost).despawn();
+    }
+}
+
+// UI
+
+fn skill_tree_window(sections: Vec<TreeSection>, next: Vec<NextJob>) -> impl Bundle {
+    (
+        StateScoped(Screen::Gameplay),
+        SkillTreeWindow,
+        Name::new("Skill Tree Window"),
+        BorderColor(WHITEISH),
+        BackgroundColor(TRANSLUCENT),
+        Node {
+            position_type: PositionType::Absolute,
+            top: Vh(15.0),
+            left: Vw(2.0),
+            flex_direction: FlexDirection::Column,
+            border: UiRect::all(Px(2.0)),
+            padding: UiRect::all(Vw(1.0)),
+            row_gap: Vh(1.0),
+            ..default()
+        },
+        Children::spawn(SpawnWith(move |parent: &mut ChildSpawner| {
+            parent.spawn(header(Opts::new("Skills").font_size(20.0)));
+            parent.spawn((label(Opts::new("").font_size(14.0)), SkillPointsLabel));
+            if sections.is_empty() {
+                parent.spawn(label(Opts::new("Nothing to learn yet").font_size(14.0)));
+            }
+            for section in sections {
+                parent.spawn(label(Opts::new(section.name.clone()).font_size(16.0)));
+                parent.spawn(tree(section));
+            }
+            if !next.is_empty() {
+                parent.spawn(label(Opts::new("Job change").font_size(16.0)));
+            }
+            for next in next {
+                parent.spawn(job_change_button(next));
+            }
+        })),
+    )
+}
+
+fn tree(section: TreeSection) -> impl Bundle {
+    (
+        Node {
+            width: Px(section.size.x),
+            height: Px(section.size.y),
+            ..default()
+        },
+        Children::spawn(SpawnWith(move |parent: &mut ChildSpawner| {
+            // edges first so the nodes cover their ends
+            for (from, to) in section.edges {
+                let from = from + Vec2::new(NODE_WIDTH / 2.0, NODE_HEIGHT);
+                let to = to + Vec2::new(NODE_WIDTH / 2.0, 0.0);
+                let middle = (from.y + to.y) / 2.0;
+                parent.spawn(line(from, from.with_y(middle)));
+                parent.spawn(line(from.with_y(middle), to.with_y(middle)));
+                parent.spawn(line(to.with_y(middle), to));
+            }
+            for (skill, name, at) in section.nodes {
+                parent
+                    .spawn(skill_node(skill, name, at))
+                    .observe(show_tooltip)
+                    .observe(hide_tooltip)
+                    .observe(start_drag)
+                    .observe(drag)
+                    .observe(end_drag);
+            }
+        })),
+    )
+}
+
+/// A straight horizontal or vertical line
+fn line(from: Vec2, to: Vec2) -> impl Bundle {
+    let min = from.min(to);
+    let size = (to - from).abs();
+    (
+        BackgroundColor(WHITEISH),
+        Node {
+            position_type: PositionType::Absolute,
+            left: Px(min.x - 1.0),
+            top: Px(min.y - 1.0),
+            width: Px(size.x + 2.0),
+            height: Px(size.y + 2.0),
+            ..default()
+        },
+        Pickable::IGNORE,
+    )
+}
+
+fn skill_node(skill: SkillId, name: String, at: Vec2) -> impl Bundle {
+    let plus = Opts::new("+")
+        .font_size(14.0)
+        .width(Px(20.0))
+        .height(Px(20.0));
+    (
+        SkillNode(skill),
+        Name::new(format!("Skill {name}")),
+        BorderColor(GRAY),
+        BackgroundColor(DIM_BLUE),
+        Node {
+            position_type: PositionType::Absolute,
+            left: Px(at.x),
+            top: Px(at.y),
+            width: Px(NODE_WIDTH),
+            height: Px(NODE_HEIGHT),
+            border: UiRect::all(Px(2.0)),
+            flex_direction: FlexDirection::Column,
+            align_items: AlignItems::Center,
+            justify_content: JustifyContent::Center,
+            ..default()
+        },
+        children![
+            label(Opts::new(name).font_size(14.0)),
+            (
+                Node {
+                    flex_direction: FlexDirection::Row,
+                    align_items: AlignItems::Center,
+                    column_gap: Px(6.0),
+                    ..default()
+                },
+                children![
+                    (label(Opts::new("").font_size(12.0)), SkillLevelLabel(skill)),
+                    btn_small(
+                        plus,
+                        move |_: Trigger<OnPress>,
+                              player: Single<Entity, With<LocalPlayer>>,
+                              mut learn: EventWriter<LearnSkill>| {
+                            learn.write(LearnSkill {
+                                entity: *player,
+                                skill,
+                            });
+                        },
+                    ),
+                ]
+            ),
+        ],
+    )
+}
+
+/// The job change is checked by the game, a job level too low only gets a warning
+fn job_change_button(next: NextJob) -> impl Bundle {
+    let job = next.job;
+    let text = format!("{} (Job Lv {})", next.name, next.job_level);
+    btn_small(
+        Opts::new(text).font_size(14.0).height(Px(24.0)),
+        move |_: Trigger<OnPress>,
+              player: Single<Entity, With<LocalPlayer>>,
+              mut change_job: EventWriter<ChangeJob>| {
+            change_job.write(ChangeJob {
+                entity: *player,
+                job,
+                forced: false,
+            });
+        },
+    )
+}
+
+fn tooltip(text: String, at: Vec2) -> impl Bundle {
+    (
+        StateScoped(Screen::Gameplay),
+        SkillTooltip,
+        Name::new("Skill Tooltip"),
+        BorderColor(WHITEISH),
+        BackgroundColor(TRANSLUCENT),
+        GlobalZIndex(10),
+        Node {
+            position_type: PositionType::Absolute,
+            left: Px(at.x),
+            top: Px(at.y),
+            border: UiRect::all(Px(1.0)),
+            padding: UiRect::all(Px(8.0)),
+            ..default()
+        },
+        // keeps the node under it hovered
+        Pickable::IGNORE,
+        children![label(Opts::new(text).font_size(13.0))],
+    )
+}
+
+/// Follows the pointer while a skill is dragged to the hotbar
+fn drag_ghost(name: String, at: Vec2) -> impl Bundle {
+    (
+        StateScoped(Screen::Gameplay),
+        SkillDragGhost,
+        Name::new("Skill Drag Ghost"),
+        BackgroundColor(DIM_BLUE),
+        GlobalZIndex(20),
+        Node {
+            position_type: PositionType::Absolute,
+            left: Px(at.x),
+            top: Px(at.y),
+            padding: UiRect::all(Px(6.0)),
+            ..default()
+        },
+        // the hotbar slot under it takes the drop
+        Pickable::IGNORE,
+        children![label(Opts::new(name).font_size(14.0))],
+    )
+}
@@ -0,0 +1,373 @@
+//! Character status window: base attributes with their bonuses, the derived stats and the
+//! stat points left. Points are put on hold with the "+" buttons and only spent once confirmed.
+
+use super::*;
+use bevy::ecs::spawn::SpawnIter;
+use game::event::types::RaiseStats;
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<PendingStats>()
+        .add_observer(toggle_status_window)
+        .add_systems(
+            Update,
+            update_status_texts
+                .run_if(in_state(Screen::Gameplay).and(any_with_component::<StatusWindow>)),
+        );
+}
+
+/// Derived stats listed under the base attributes
+const DERIVED: [Stat; 12] = [
+    Stat::MaxHp,
+    Stat::MaxMp,
+    Stat::Attack,
+    Stat::MagicAttack,
+    Stat::Defence,
+    Stat::MagicDefence,
+    Stat::HitRate,
+    Stat::DodgeRate,
+    Stat::CritRate,
+    Stat::AttackRate,
+    Stat::MoveSpeed,
+    Stat::WeightLimit,
+];
+
+/// Points put on every base attribute but not spent yet, in the order of [`Stat::BASE`]
+#[derive(Resource, Default, Deref, DerefMut)]
+struct PendingStats([u32; 5]);
+
+impl PendingStats {
+    fn raises(&self) -> Vec<(Stat, u32)> {
+        Stat::BASE
+            .into_iter()
+            .zip(self.0)
+            .filter(|(_, by)| *by > 0)
+            .collect()
+    }
+
+    /// What the pending points cost, None when one of them goes past the max
+    fn cost(&self, costs: &StatCosts, attribs: &Attributes) -> Option<u32> {
+        Stat::BASE
+            .into_iter()
+            .zip(self.0)
+            .map(|(stat, by)| costs.raise_cost(attribs.get(stat).unwrap_or_default(), by))
+            .sum()
+    }
+}
+
+/// A text of the window that follows the local player
+#[derive(Component, Clone, Copy)]
+enum StatusText {
+    Base(usize),
+    Bonus(usize),
+    /// Cost of the next point
+    Cost(usize),
+    Derived(Stat),
+    Points,
+}
+
+fn stat_name(stat: Stat) -> &'static str {
+    match stat {
+        Stat::Strength => "STR",
+        Stat::Dexterity => "DEX",
+        Stat::Intelligence => "INT",
+        Stat::Vitality => "VIT",
+        Stat::Luck => "LUK",
+        Stat::Attack => "ATK",
+        Stat::MagicAttack => "MATK",
+        Stat::Defence => "DEF",
+        Stat::MagicDefence => "MDEF",
+        Stat::HitRate => "HIT",
+        Stat::DodgeRate => "FLEE",
+        Stat::CritRate => "CRIT",
+        Stat::AttackRate => "ASPD",
+        Stat::MaxHp => "Max HP",
+        Stat::MaxMp => "Max MP",
+        Stat::MoveSpeed => "Speed",
+        Stat::WeightLimit => "Weight",
+        Stat::MpRegen => "MP Regen",
+    }
+}
+
+fn toggle_status_window(
+    _: Trigger<OnStatusWindowToggle>,
+    screen: Res<State<Screen>>,
+    windows: Query<Entity, Or<(With<StatusWindow>, With<StatusConfirmDialog>)>>,
+    mut pending: ResMut<PendingStats>,
+    mut cmds: Commands,
+) {
+    if *screen.get() != Screen::Gameplay {
+        return;
+    }
+
+    **pending = default();
+    if windows.is_empty() {
+        cmds.spawn(status_window());
+    }
+    for window in &windows {
+        cmds.entity(window).despawn();
+    }
+}
+
+fn update_status_texts(
+    levels: Res<LevelTable>,
+    item_db: Option<Res<ItemDb>>,
+    pending: Res<PendingStats>,
+    player: Query<
+        (
+            &Attributes,
+            &StatModifiers,
+            &ComputedAttributes,
+            &StatPoints,
+            Option<&Inventory>,
+        ),
+        With<LocalPlayer>,
+    >,
+    mut texts: Query<(&StatusText, &mut Text)>,
+) {
+    let Ok((attribs, modifiers, computed, points, inventory)) = player.single() else {
+        return;
+    };
+    let spent = pending.cost(&levels.stats, attribs).unwrap_or_default();
+    let carried = inventory
+        .zip(item_db.as_deref())
+        .map_or(0, |(inventory, item_db)| inventory.weight(item_db));
+
+    for (field, mut text) in &mut texts {
+        let new = match *field {
+            StatusText::Base(i) => {
+                let base = attribs.get(Stat::BASE[i]).unwrap_or_default();
+                match pending[i] {
+                    0 => format!("{base}"),
+                    by => format!("{base} +{by}"),
+                }
+            }
+            StatusText::Bonus(i) => {
+                let stat = Stat::BASE[i];
+                let base = attribs.get(stat).unwrap_or_default() as f32;
+                let bonus = modifiers.apply(stat, base).round() - base;
+                if bonus == 0.0 {
+                    String::new()
+                } else {
+                    format!("{bonus:+}")
+                }
+            }
+            StatusText::Cost(i) => {
+                let value = attribs.get(Stat::BASE[i]).unwrap_or_default() + pending[i] as i32;
+                if value >= levels.stats.max {
+                    "max".into()
+                } else {
+                    format!("{}", levels.stats.cost(value))
+                }
+            }
+            StatusText::Derived(Stat::MaxHp) => {
+                let health = &computed.health;
+                format!("{:.0} / {:.0}", health.hp, health.max_hp)
+            }
+            StatusText::Derived(Stat::MaxMp) => {
+                let mana = &computed.mana;
+                format!("{:.0} / {:.0}", mana.mp, mana.max_mp)
+            }
+            StatusText::Derived(Stat::WeightLimit) => {
+                format!("{carried} / {:.0}", computed.weight_limit)
+            }
+            StatusText::Derived(stat) => format!("{:.1}", computed.get(stat).unwrap_or_default()),
+            StatusText::Points => format!("Stat points: {}", points.saturating_sub(spent)),
+        };
+        if text.0 != new {
+            text.0 = new;
+        }
+    }
+}
+
+fn reset_pending(_: Trigger<OnPress>, mut pending: ResMut<PendingStats>) {
+    **pending = default();
+}
+
+fn ask_to_apply(
+    _: Trigger<OnPress>,
+    levels: Res<LevelTable>,
+    player: Single<&Attributes, With<LocalPlayer>>,
+    pending: Res<PendingStats>,
+    dialogs: Query<(), With<StatusConfirmDialog>>,
+    mut cmds: Commands,
+) {
+    let Some(cost) = pending.cost(&levels.stats, *player) else {
+        return;
+    };
+    if cost == 0 || !dialogs.is_empty() {
+        return;
+    }
+    cmds.spawn(confirm_dialog(cost));
+}
+
+fn confirm_raise(
+    _: Trigger<OnPress>,
+    player: Single<Entity, With<LocalPlayer>>,
+    mut pending: ResMut<PendingStats>,
+    dialogs: Query<Entity, With<StatusConfirmDialog>>,
+    mut raise: EventWriter<RaiseStats>,
+    mut cmds: Commands,
+) {
+    raise.write(RaiseStats {
+        entity: *player,
+        raises: pending.raises(),
+    });
+    **pending = default();
+    for dialog in &dialogs {
+        cmds.entity(dialog).despawn();
+    }
+}
+
+fn cancel_raise(
+    _: Trigger<OnPress>,
+    dialogs: Query<Entity, With<StatusConfirmDialog>>,
+    mut cmds: Commands,
+) {
+    for dialog in &dialogs {
+        cmds.entity(dialog).despawn();
+    }
+}
+
+// UI
+
+fn status_window() -> impl Bundle {
+    (
+        StateScoped(Screen::Gameplay),
+        StatusWindow,
+        Name::new("Status Window"),
+        BorderColor(WHITEISH),
+        BackgroundColor(TRANSLUCENT),
+        Node {
+            position_type: PositionType::Absolute,
+            top: Vh(15.0),
+            right: Vw(2.0),
+            flex_direction: FlexDirection::Column,
+            border: UiRect::all(Px(2.0)),
+            padding: UiRect::all(Vw(1.0)),
+            row_gap: Vh(0.5),
+            ..default()
+        },
+        children![
+            header(Opts::new("Status").font_size(20.0)),
+            (
+                Node {
+                    flex_direction: FlexDirection::Column,
+                    row_gap: Vh(0.5),
+                    ..default()
+                },
+                Children::spawn(SpawnIter((0..Stat::BASE.len()).map(base_row))),
+            ),
+            (label(Opts::new("").font_size(14.0)), StatusText::Points),
+            (
+                Node {
+                    flex_direction: FlexDirection::Row,
+                    column_gap: Vw(0.5),
+                    ..default()
+                },
+                children![
+                    btn_small(opts("Reset").width(Vw(5.0)), reset_pending),
+                    btn_small(opts("Apply").width(Vw(5.0)), ask_to_apply),
+                ]
+            ),
+            (
+                Node {
+                    flex_direction: FlexDirection::Column,
+                    margin: UiRect::top(Vh(1.0)),
+                    ..default()
+                },
+                Children::spawn(SpawnIter(DERIVED.into_iter().map(derived_row))),
+            ),
+        ],
+    )
+}
+
+fn opts(text: &'static str) -> Opts {
+    Opts::new(text).font_size(14.0)
+}
+
+/// Name, base value, bonus, cost of the next point and the "+" button of a base attribute
+fn base_row(i: usize) -> impl Bundle {
+    (
+        Node {
+            flex_direction: FlexDirection::Row,
+            align_items: AlignItems::Center,
+            column_gap: Vw(0.5),
+            ..default()
+        },
+        children![
+            label(opts(stat_name(Stat::BASE[i])).width(Vw(3.0))),
+            (label(opts("").width(Vw(3.5))), StatusText::Base(i)),
+            (
+                label(opts("").width(Vw(2.5)).color(GREEN)),
+                StatusText::Bonus(i)
+            ),
+            (label(opts("").width(Vw(2.5))), StatusText::Cost(i)),
+            // puts a point on hold if it is affordable next to the ones already pending
+            btn_small(
+                opts("+").width(Vw(1.8)).height(Vw(1.8)),
+                move |_: Trigger<OnPress>,
+                      levels: Res<LevelTable>,
+                      player: Single<(&Attributes, &StatPoints), With<LocalPlayer>>,
+                      mut pending: ResMut<PendingStats>| {
+                    let (attribs, points) = *player;
+                    let mut raised = PendingStats(pending.0);
+                    raised[i] += 1;
+                    if raised
+                        .cost(&levels.stats, attribs)
+                        .is_some_and(|cost| cost <= **points)
+                    {
+                        *pending = raised;
+                    }
+                },
+            ),
+        ],
+    )
+}
+
+fn derived_row(stat: Stat) -> impl Bundle {
+    (
+        Node {
+            flex_direction: FlexDirection::Row,
+            column_gap: Vw(0.5),
+            ..default()
+        },
+        children![
+            label(opts(stat_name(stat)).width(Vw(4.0))),
+            (label(opts("")), StatusText::Derived(stat)),
+        ],
+    )
+}
+
+fn confirm_dialog(cost: u32) -> impl Bundle {
+    (
+        StateScoped(Screen::Gameplay),
+        StatusConfirmDialog,
+        ui_root("Stat Points Dialog"),
+        children![(
+            BorderColor(WHITEISH),
+            BackgroundColor(TRANSLUCENT),
+            Node {
+                border: UiRect::all(Px(2.0)),
+                padding: UiRect::all(Vw(2.0)),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                row_gap: Vh(2.0),
+                ..default()
+            },
+            children![
+                label(Opts::new(format!("Spend {cost} stat points?")).font_size(18.0)),
+                (
+                    Node {
+                        flex_direction: FlexDirection::Row,
+                        column_gap: Vw(1.0),
+                        ..default()
+                    },
+                    children![
+                        btn_small(opts("Yes").width(Vw(5.0)), confirm_raise),
+                        btn_small(opts("No").width(Vw(5.0)), cancel_raise),
+                    ]
+                ),
+            ]
+        )],
+    )
+}
@@ -28,13 +28,13 @@ fn setup_menu(mut commands: Commands) {
             // https://github.com/bevyengine/bevy/issues/18953
             #[cfg(target_arch = "wasm32")]
             children![
-                btn_big("Play", to::gameplay_or_loading),
+                btn_big("Play", to::login),
                 btn_big("Credits", to::credits),
                 btn_big("Settings", to::settings),
             ],
             #[cfg(not(target_arch = "wasm32"))]
             children![
-                btn_big("Play", to::gameplay_or_loading),
+                btn_big("Play", to::login),
                 btn_big("Credits", to::credits),
                 btn_big("Settings", to::settings),
                 btn_big("Exit", exit_app)
@@ -0,0 +1,178 @@
+//! Benchmark scene: thousands of monsters and a crowd of players walking in circles through
+//! them, on the same headless simulation as the dedicated server. Frame times are logged every
+//! second, enemy AI, proximity and targeting all go through the spatial grid.
+//!
+//! `cargo run -p server --release --example bench -- <monsters> <players>`, 5000 and 50 by
+//! default.
+
+use avian3d::prelude::*;
+use bevy::{
+    app::ScheduleRunnerPlugin,
+    diagnostic::{EntityCountDiagnosticsPlugin, FrameTimeDiagnosticsPlugin, LogDiagnosticsPlugin},
+    log::LogPlugin,
+    prelude::*,
+    render::mesh::MeshPlugin,
+    scene::ScenePlugin,
+    state::app::StatesPlugin,
+};
+use game::combat::AttackRateTimer;
+use models::*;
+use rand::prelude::*;
+use std::{f32::consts::TAU, time::Duration};
+
+/// Ground per monster, the field grows with the crowd
+const SPACING: f32 = 4.0;
+
+#[derive(Resource)]
+struct Bench {
+    monsters: usize,
+    players: usize,
+    /// Half the side of the ground
+    extent: f32,
+}
+
+/// Stand-in for a player walking a circle
+#[derive(Component)]
+struct Walker {
+    center: Vec2,
+    radius: f32,
+    angle: f32,
+    speed: f32,
+}
+
+fn main() -> AppExit {
+    let mut args = std::env::args().skip(1);
+    let monsters = args.next().and_then(|a| a.parse().ok()).unwrap_or(5000);
+    let players = args.next().and_then(|a| a.parse().ok()).unwrap_or(50);
+
+    let (Ok(cfg), Ok(formulas), Ok(monster_db)) =
+        (Config::read(), StatFormulas::read(), MonsterDb::read())
+    else {
+        eprintln!("unable to load the assets, run from the repository root");
+        return AppExit::error();
+    };
+
+    let mut app = App::new();
+
+    app.configure_sets(
+        Update,
+        (Set::TickTimers, Set::RecordInput, Set::Update).chain(),
+    );
+
+    app.add_plugins((
+        // as fast as it goes, the frame time is the measurement
+        MinimalPlugins.set(ScheduleRunnerPlugin::run_loop(Duration::ZERO)),
+        LogPlugin::default(),
+        TransformPlugin,
+        StatesPlugin,
+        AssetPlugin::default(),
+        MeshPlugin,
+        ScenePlugin,
+        FrameTimeDiagnosticsPlugin::default(),
+        EntityCountDiagnosticsPlugin,
+        LogDiagnosticsPlugin::default(),
+    ))
+    .insert_resource(cfg)
+    .insert_resource(formulas)
+    .insert_resource(monster_db)
+    // no spawn zones, the crowd is placed by hand
+    .insert_resource(MapData {
+        name: "Benchmark".to_string(),
+        spawn_zones: Vec::new(),
+    })
+    .insert_resource(Bench {
+        monsters,
+        players,
+        extent: (monsters as f32).sqrt() * SPACING / 2.0,
+    })
+    .insert_state(Screen::Gameplay)
+    .enable_state_scoped_entities::<Screen>()
+    .add_plugins(game::simulation_plugin)
+    .add_systems(Startup, (spawn_ground, spawn_monsters, spawn_players))
+    .add_systems(Update, walk);
+
+    app.run()
+}
+
+fn spawn_ground(bench: Res<Bench>, mut commands: Commands) {
+    let side = bench.extent * 2.0 + SPACING;
+    commands.spawn((
+        Name::new("Ground"),
+        Transform::from_xyz(0.0, -1.0, 0.0),
+        RigidBody::Static,
+        Collider::cuboid(side, 1.0, side),
+    ));
+}
+
+fn spawn_monsters(
+    bench: Res<Bench>,
+    cfg: Res<Config>,
+    monster_db: Res<MonsterDb>,
+    mut commands: Commands,
+) {
+    let mut rng = StdRng::seed_from_u64(0);
+    let ids: Vec<MonsterId> = monster_db.monsters.keys().copied().map(MonsterId).collect();
+    if ids.is_empty() {
+        warn!("the monster database is empty");
+        return;
+    }
+
+    for i in 0..bench.monsters {
+        let x = rng.gen_range(-bench.extent..=bench.extent);
+        let z = rng.gen_range(-bench.extent..=bench.extent);
+        let pos = Transform::from_xyz(x, 1.0, z);
+        match game::enemy::monster_bundle(&cfg, &monster_db, ids[i % ids.len()], pos) {
+            Ok(bundle) => {
+                commands.spawn(bundle);
+            }
+            Err(e) => warn!("{e}"),
+        }
+    }
+
+    info!("spawned {} monsters", bench.monsters);
+}
+
+fn spawn_players(bench: Res<Bench>, cfg: Res<Config>, mut commands: Commands) {
+    let mut rng = StdRng::seed_from_u64(1);
+
+    for i in 0..bench.players {
+        let center = Vec2::new(
+            rng.gen_range(-bench.extent..=bench.extent),
+            rng.gen_range(-bench.extent..=bench.extent),
+        );
+        // nobody dies, the enemies keep fighting
+        let comp_attribs = ComputedAttributes {
+            health: Health {
+                max_hp: f32::MAX,
+                hp: f32::MAX,
+            },
+            ..default()
+        };
+
+        commands.spawn((
+            Name::new(format!("Bench Player {i}")),
+            Transform::from_xyz(center.x, 1.0, center.y),
+            Player::default(),
+            comp_attribs,
+            AttackRateTimer::new(comp_attribs.attack_rate),
+            Collider::capsule(cfg.player.hitbox.radius, cfg.player.hitbox.height),
+            RigidBody::Kinematic,
+            Walker {
+                center,
+                radius: rng.gen_range(5.0..30.0),
+                angle: rng.gen_range(0.0..TAU),
+                speed: comp_attribs.move_speed,
+            },
+        ));
+    }
+}
+
+fn walk(time: Res<Time>, mut walkers: Query<(&mut Walker, &mut Transform)>) {
+    for (mut walker, mut transform) in &mut walkers {
+        walker.angle += walker.speed / walker.radius * time.delta_secs();
+        let offset = Vec2::from_angle(walker.angle) * walker.radius;
+        let position = walker.center + offset;
+        transform.translation.x = position.x;
+        transform.translation.z = position.y;
+    }
+}
@@ -0,0 +1,115 @@
+//! Dedicated server: runs the game simulation without a window, GPU or audio device.
+//!
+//! Physics, enemies, combat and player characters come from [`game::simulation_plugin`], the very
+//! same systems the client runs. Build it with `cargo run -p server`, the `render` feature of the
+//! `game` crate stays off so none of the camera, model or sound code is compiled in.
+//!
+//! Clients connect over UDP to the [`LocalServer`], on [`DEFAULT_ADDRESS`] unless another one
+//! is given as the first argument: `cargo run -p server -- 0.0.0.0:7000`.
+
+use avian3d::prelude::*;
+use bevy::{
+    app::ScheduleRunnerPlugin, log::LogPlugin, prelude::*, render::mesh::MeshPlugin,
+    scene::ScenePlugin, state::app::StatesPlugin,
+};
+use models::*;
+use net::{LocalServer, UdpHost};
+use std::{env, error::Error, time::Duration};
+
+/// Simulation ticks per second, physics and character controllers run in [`FixedUpdate`]
+const TICK_RATE: f64 = 60.0;
+/// Where clients connect to when no address is given
+const DEFAULT_ADDRESS: &str = "0.0.0.0:7777";
+
+fn main() -> AppExit {
+    // the client gets these from the asset server, here they are read once on startup
+    let (
+        Some(cfg),
+        Some(formulas),
+        Some(job_db),
+        Some(item_db),
+        Some(level_table),
+        Some(monster_db),
+        Some(skill_db),
+        Some(status_db),
+        Some(damage_tables),
+        Some(map),
+    ) = (
+        load(CONFIG_PATH, Config::read()),
+        load(STAT_FORMULAS_PATH, StatFormulas::read()),
+        load(JOB_DB_PATH, JobDb::read()),
+        load(ITEM_DB_PATH, ItemDb::read()),
+        load(LEVEL_TABLE_PATH, LevelTable::read()),
+        load(MONSTER_DB_PATH, MonsterDb::read()),
+        load(SKILL_DB_PATH, SkillDb::read()),
+        load(STATUS_DB_PATH, StatusDb::read()),
+        load(DAMAGE_TABLES_PATH, DamageTables::read()),
+        load(MAP_PATH, MapData::read()),
+    )
+    else {
+        return AppExit::error();
+    };
+
+    let address = env::args().nth(1).unwrap_or(DEFAULT_ADDRESS.into());
+    let transport = match UdpHost::bind(&address) {
+        Ok(transport) => transport,
+        Err(e) => {
+            eprintln!("unable to listen on '{address}': {e}");
+            return AppExit::error();
+        }
+    };
+
+    let mut app = App::new();
+
+    app.configure_sets(
+        Update,
+        (Set::TickTimers, Set::RecordInput, Set::Update).chain(),
+    );
+
+    app.add_plugins((
+        MinimalPlugins.set(ScheduleRunnerPlugin::run_loop(Duration::from_secs_f64(
+            1.0 / TICK_RATE,
+        ))),
+        LogPlugin::default(),
+        TransformPlugin,
+        StatesPlugin,
+        // colliders can be built from mesh and scene assets, physics expects them around
+        AssetPlugin::default(),
+        MeshPlugin,
+        ScenePlugin,
+    ))
+    .insert_resource(cfg)
+    .insert_resource(formulas)
+    .insert_resource(job_db)
+    .insert_resource(item_db)
+    .insert_resource(level_table)
+    .insert_resource(monster_db)
+    .insert_resource(skill_db)
+    .insert_resource(status_db)
+    .insert_resource(damage_tables)
+    .insert_resource(map)
+    .insert_resource(LocalServer::new(transport))
+    // no menus on the server, the world is running as soon as it starts
+    .insert_state(Screen::Gameplay)
+    .enable_state_scoped_entities::<Screen>()
+    .add_plugins((game::simulation_plugin, net::host_plugin))
+    .add_systems(Startup, spawn_ground);
+
+    app.run()
+}
+
+fn load<T>(path: &str, read: Result<T, Box<dyn Error>>) -> Option<T> {
+    read.inspect_err(|e| eprintln!("unable to load '{path}': {e}"))
+        .ok()
+}
+
+/// Same ground plane as `scene::setup` on the client, without the mesh
+fn spawn_ground(cfg: Res<Config>, mut commands: Commands) {
+    let plane = cfg.geom.main_plane;
+    commands.spawn((
+        Name::new("Ground"),
+        Transform::from_xyz(0.0, -1.0, 0.0),
+        RigidBody::Static,
+        Collider::cuboid(plane, 1.0, plane),
+    ));
+}
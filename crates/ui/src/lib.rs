@@ -9,13 +9,20 @@ mod interaction;
 mod opts;
 mod perf;
 mod prefabs;
+mod text_input;
 mod widget;
 
 pub use interaction::*;
 pub use opts::*;
 pub use prefabs::*;
+pub use text_input::*;
 pub use widget::*;
 
 pub fn plugin(app: &mut App) {
-    app.add_plugins((perf::plugin, interaction::plugin, prefabs::plugin));
+    app.add_plugins((
+        perf::plugin,
+        interaction::plugin,
+        text_input::plugin,
+        prefabs::plugin,
+    ));
 }
@@ -0,0 +1,172 @@
+//! Single line text fields, see [`text_input`].
+//!
+//! Clicking a field gives it the [`InputFocus`], typed characters go to the focused field,
+//! Tab moves to the next one and Enter triggers [`OnSubmit`] on it.
+use super::*;
+use bevy::{
+    input::{
+        ButtonState,
+        keyboard::{Key, KeyboardInput},
+    },
+    input_focus::InputFocus,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.register_type::<TextInput>()
+        .init_resource::<InputFocus>()
+        .add_systems(
+            Update,
+            (focus_on_press, type_text, update_text_input_display).chain(),
+        );
+}
+
+/// Editable content of a [`text_input`] widget
+#[derive(Component, Reflect, Debug, Clone, Default)]
+#[reflect(Component)]
+pub struct TextInput {
+    pub value: String,
+    /// Shown while the field is empty and not focused
+    pub placeholder: String,
+    /// Hides the value behind `*`, for passwords
+    pub masked: bool,
+    pub max_len: usize,
+}
+
+impl TextInput {
+    pub const DEFAULT_MAX_LEN: usize = 24;
+
+    pub fn new(placeholder: impl Into<String>) -> Self {
+        Self {
+            placeholder: placeholder.into(),
+            max_len: Self::DEFAULT_MAX_LEN,
+            ..default()
+        }
+    }
+    pub fn masked(mut self) -> Self {
+        self.masked = true;
+        self
+    }
+    pub fn max_len(mut self, max_len: usize) -> Self {
+        self.max_len = max_len;
+        self
+    }
+
+    fn display(&self, focused: bool) -> String {
+        let value = if self.masked {
+            "*".repeat(self.value.chars().count())
+        } else {
+            self.value.clone()
+        };
+
+        match (focused, value.is_empty()) {
+            (true, _) => format!("{value}|"),
+            (false, true) => self.placeholder.clone(),
+            (false, false) => value,
+        }
+    }
+}
+
+/// Event triggered on a focused [`TextInput`] when Enter is pressed
+#[derive(Event)]
+pub struct OnSubmit;
+
+/// A bordered text field, `opts` controls the layout and the text style
+pub fn text_input(opts: impl Into<Opts>, input: TextInput) -> impl Bundle {
+    let opts: Opts = opts.into();
+    let opts = opts.text(input.placeholder.clone());
+    (
+        Name::new("Text Input"),
+        Button,
+        input,
+        opts.node.clone(),
+        opts.border_radius,
+        opts.border_color,
+        opts.ui_palette.clone(),
+        BackgroundColor(TRANSPARENT),
+        children![(TextInputDisplay, opts.into_text_bundle())],
+    )
+}
+
+fn focus_on_press(
+    mouse: Res<ButtonInput<MouseButton>>,
+    mut focus: ResMut<InputFocus>,
+    inputs: Query<(Entity, &Interaction), (Changed<Interaction>, With<TextInput>)>,
+) {
+    let pressed = inputs
+        .iter()
+        .find(|(_, interaction)| matches!(interaction, Interaction::Pressed));
+
+    match pressed {
+        Some((entity, _)) => focus.set(entity),
+        // clicking anywhere else drops the focus
+        None if mouse.just_pressed(MouseButton::Left) => focus.clear(),
+        None => {}
+    }
+}
+
+fn type_text(
+    mut focus: ResMut<InputFocus>,
+    mut keys: EventReader<KeyboardInput>,
+    mut inputs: Query<(Entity, &mut TextInput)>,
+    mut commands: Commands,
+) {
+    let Some(focused) = focus.get().filter(|&e| inputs.contains(e)) else {
+        keys.clear();
+        return;
+    };
+
+    for key in keys.read() {
+        if key.state != ButtonState::Pressed {
+            continue;
+        }
+
+        match &key.logical_key {
+            Key::Enter => commands.trigger_targets(OnSubmit, focused),
+            Key::Tab => {
+                let mut order: Vec<Entity> = inputs.iter().map(|(e, _)| e).collect();
+                order.sort();
+                if let Some(i) = order.iter().position(|&e| e == focused) {
+                    focus.set(order[(i + 1) % order.len()]);
+                }
+                // keys typed after Tab are read next frame and go to the new field
+                return;
+            }
+            Key::Backspace => {
+                if let Ok((_, mut input)) = inputs.get_mut(focused) {
+                    input.value.pop();
+                }
+            }
+            _ => {
+                let Some(text) = &key.text else {
+                    continue;
+                };
+                let Ok((_, mut input)) = inputs.get_mut(focused) else {
+                    continue;
+                };
+                for c in text.chars().filter(|c| !c.is_control()) {
+                    if input.value.chars().count() < input.max_len {
+                        input.value.push(c);
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn update_text_input_display(
+    focus: Res<InputFocus>,
+    inputs: Query<(Entity, Ref<TextInput>, &Children)>,
+    mut text: Query<&mut Text, With<TextInputDisplay>>,
+) {
+    for (entity, input, children) in inputs.iter() {
+        if !focus.is_changed() && !input.is_changed() {
+            continue;
+        }
+
+        let focused = focus.get() == Some(entity);
+        let mut iter = text.iter_many_mut(children);
+        while let Some(mut text) = iter.fetch_next() {
+            text.0 = input.display(focused);
+        }
+    }
+}
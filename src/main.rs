@@ -50,11 +50,23 @@ fn main() {
     app.add_plugins((
         audio::plugin,
         asset_loading::plugin,
+        net::plugin,
         ui::plugin,
         screens::plugin,
     ))
     .add_systems(Startup, set_window_icon);
 
+    // MIDGARD_SERVER=host:port plays on a dedicated server instead of an in-process one
+    let remote = std::env::var("MIDGARD_SERVER").ok().and_then(|address| {
+        net::Udp::connect(&address)
+            .inspect_err(|e| eprintln!("unable to connect to '{address}': {e}"))
+            .ok()
+    });
+    match remote {
+        Some(transport) => app.insert_resource(net::NetClient::new(transport)),
+        None => app.add_plugins(net::local_server_plugin),
+    };
+
     app.run();
 }
 